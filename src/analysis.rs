@@ -0,0 +1,1080 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::io::{self, Write};
+use petgraph::algo::{astar, condensation, connected_components, tarjan_scc, toposort};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+
+use crate::types::{Edge, EdgeType, Node, NodeType};
+
+// Functions with fewer body nodes than this are too small to be meaningful clones
+const MIN_CLONE_SIZE: usize = 5;
+
+// Structural fingerprint of a function: how many nodes and edges of each kind its body holds
+#[derive(Debug, Default)]
+pub struct FunctionProfile {
+    pub node_kinds: HashMap<NodeType, usize>,
+    pub edge_kinds: HashMap<EdgeType, usize>,
+}
+
+impl FunctionProfile {
+    pub fn size(&self) -> usize {
+        self.node_kinds.values().sum()
+    }
+}
+
+// Breadth-first walk from `start` along outgoing edges of the given kinds, including `start`
+fn follow_edges(graph: &DiGraph<Node, Edge>, start: NodeIndex, kinds: &[EdgeType]) -> HashSet<NodeIndex> {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    visited.insert(start);
+    queue.push_back(start);
+
+    while let Some(node_idx) = queue.pop_front() {
+        for edge in graph.edges(node_idx) {
+            if kinds.contains(&edge.weight().kind) && visited.insert(edge.target()) {
+                queue.push_back(edge.target());
+            }
+        }
+    }
+
+    visited
+}
+
+// Collect every node a function transitively contains, including the function itself
+pub fn function_subgraph(graph: &DiGraph<Node, Edge>, func_idx: NodeIndex) -> HashSet<NodeIndex> {
+    follow_edges(graph, func_idx, &[EdgeType::Contains])
+}
+
+// Everything `start` can lead to: its statements, the functions they call or take the address
+// of, and theirs in turn
+pub fn reachable_from(graph: &DiGraph<Node, Edge>, start: NodeIndex) -> HashSet<NodeIndex> {
+    follow_edges(graph, start, &[EdgeType::Calls, EdgeType::References, EdgeType::Contains])
+}
+
+// A function's statements and what they lead to through calls and uses, leaving out the
+// function-pointer `References` that `reachable_from` also follows
+pub fn used_from(graph: &DiGraph<Node, Edge>, start: NodeIndex) -> HashSet<NodeIndex> {
+    follow_edges(graph, start, &[EdgeType::Contains, EdgeType::Calls, EdgeType::Uses])
+}
+
+// The function node with this name, if the program defines or declares one
+pub fn find_function(graph: &DiGraph<Node, Edge>, name: &str) -> Option<NodeIndex> {
+    graph.node_indices().find(|&idx| {
+        (graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main) && graph[idx].name == name
+    })
+}
+
+// Decision points in the function's body plus one. Every branch or loop node adds a path.
+pub fn cyclomatic_complexity(graph: &DiGraph<Node, Edge>, func_idx: NodeIndex) -> u32 {
+    let decisions = function_subgraph(graph, func_idx).into_iter()
+        .filter(|&idx| matches!(graph[idx].kind, NodeType::IfStatement | NodeType::ForLoop | NodeType::WhileLoop))
+        .count();
+
+    decisions as u32 + 1
+}
+
+// Functions with their complexity, most complex first and by name among equals
+pub fn complexity_table(graph: &DiGraph<Node, Edge>) -> Vec<(NodeIndex, u32)> {
+    let mut table: Vec<(NodeIndex, u32)> = graph.node_indices()
+        .filter_map(|idx| graph[idx].complexity.map(|complexity| (idx, complexity)))
+        .collect();
+    table.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| graph[a.0].name.cmp(&graph[b.0].name)));
+    table
+}
+
+// Function-level call graph keyed by name: an edge from each function to everything its body
+// calls. Library functions have no definition node, so their call nodes name them instead.
+fn derive_call_graph(graph: &DiGraph<Node, Edge>) -> (DiGraph<String, ()>, HashMap<String, NodeIndex>) {
+    let mut call_graph = DiGraph::new();
+    let mut indices: HashMap<String, NodeIndex> = HashMap::new();
+    let mut index_of = |call_graph: &mut DiGraph<String, ()>, name: &str| {
+        *indices.entry(name.to_string()).or_insert_with(|| call_graph.add_node(name.to_string()))
+    };
+
+    let functions = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main);
+    for func_idx in functions {
+        let caller = index_of(&mut call_graph, &graph[func_idx].name);
+
+        for node_idx in function_subgraph(graph, func_idx) {
+            let callees: Vec<&str> = graph.edges(node_idx)
+                .filter(|edge| edge.weight().kind == EdgeType::Calls)
+                .map(|edge| graph[edge.target()].name.as_str())
+                .collect();
+            let is_call = matches!(
+                graph[node_idx].kind,
+                NodeType::Call | NodeType::UnsafeCall | NodeType::DangerousSink | NodeType::FormatString | NodeType::MemoryOp
+            );
+
+            if !callees.is_empty() {
+                for callee in callees {
+                    let callee = index_of(&mut call_graph, callee);
+                    call_graph.update_edge(caller, callee, ());
+                }
+            } else if is_call {
+                let callee = index_of(&mut call_graph, symbol_name(&graph[node_idx].name));
+                call_graph.update_edge(caller, callee, ());
+            }
+        }
+    }
+
+    (call_graph, indices)
+}
+
+// Longest chain of calls in the program, counted in calls, so `main -> parse -> strcpy` is 2.
+// Functions that call each other recursively count as a single step.
+pub fn max_call_depth(graph: &DiGraph<Node, Edge>) -> usize {
+    let (call_graph, _) = derive_call_graph(graph);
+    let dag = condensation(call_graph, true);
+    let order = toposort(&dag, None).unwrap_or_default();
+
+    // Callees come later in the order, so walking it backwards sees them first
+    let mut depth = vec![0; dag.node_count()];
+    for &node_idx in order.iter().rev() {
+        depth[node_idx.index()] = dag.neighbors(node_idx)
+            .map(|callee| depth[callee.index()] + 1)
+            .max()
+            .unwrap_or(0);
+    }
+    depth.into_iter().max().unwrap_or(0)
+}
+
+// Groups of functions that call back into themselves: a single self-recursive function, or all
+// the functions of a mutually recursive cycle. Each group is ordered by node index and the groups
+// by their first member.
+pub fn find_recursion(graph: &DiGraph<Node, Edge>) -> Vec<Vec<NodeIndex>> {
+    let (call_graph, _) = derive_call_graph(graph);
+    let functions: HashMap<&str, NodeIndex> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main)
+        .map(|idx| (graph[idx].name.as_str(), idx))
+        .collect();
+
+    let mut cycles: Vec<Vec<NodeIndex>> = tarjan_scc(&call_graph).into_iter()
+        .filter(|component| component.len() > 1 || call_graph.contains_edge(component[0], component[0]))
+        .map(|component| {
+            let mut members: Vec<NodeIndex> = component.iter()
+                .filter_map(|&idx| functions.get(call_graph[idx].as_str()).copied())
+                .collect();
+            members.sort();
+            members
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+// Summary metrics for a quick look at a graph before rendering it
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub functions: usize, // Functions and main, excluding library declarations
+    pub unsafe_calls: usize,
+    pub max_call_depth: usize,
+    pub components: usize, // Weakly connected, so edge direction doesn't split a component
+    pub node_kinds: BTreeMap<String, usize>,
+    pub edge_kinds: BTreeMap<String, usize>,
+}
+
+// Kinds are counted by canonical name, leaving out kinds that don't occur
+pub fn graph_stats(graph: &DiGraph<Node, Edge>) -> Stats {
+    let mut node_kinds = BTreeMap::new();
+    for node in graph.node_weights() {
+        *node_kinds.entry(node.kind.to_string()).or_insert(0) += 1;
+    }
+    let mut edge_kinds = BTreeMap::new();
+    for edge in graph.edge_weights() {
+        *edge_kinds.entry(edge.kind.to_string()).or_insert(0) += 1;
+    }
+
+    Stats {
+        nodes: graph.node_count(),
+        edges: graph.edge_count(),
+        functions: graph.node_weights()
+            .filter(|node| matches!(node.kind, NodeType::Function | NodeType::Main) && !node.library)
+            .count(),
+        unsafe_calls: graph.node_indices()
+            .filter(|&idx| graph[idx].kind == NodeType::UnsafeCall && !is_unsafe_controller(graph, idx))
+            .count(),
+        max_call_depth: max_call_depth(graph),
+        components: connected_components(graph),
+        node_kinds,
+        edge_kinds,
+    }
+}
+
+// What changed between two graphs of the same program, with names sorted. Node indices and lines
+// shift with any edit, so nodes are matched by kind, name and USR instead.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CpgDiff {
+    pub added_functions: Vec<String>,
+    pub removed_functions: Vec<String>,
+    pub added_calls: Vec<(String, String)>, // (caller, callee)
+    pub removed_calls: Vec<(String, String)>,
+    pub new_unsafe_calls: Vec<(String, String)>, // (function, call), once per extra occurrence
+    pub node_kinds: Vec<(String, i64)>, // (kind, change in count), for kinds whose count changed
+    pub edge_kinds: Vec<(String, i64)>,
+    pub gained: Vec<(String, String)>, // (function, operation), once per extra occurrence
+    pub lost: Vec<(String, String)>,
+}
+
+impl CpgDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_functions.is_empty()
+            && self.removed_functions.is_empty()
+            && self.added_calls.is_empty()
+            && self.removed_calls.is_empty()
+            && self.new_unsafe_calls.is_empty()
+            && self.node_kinds.is_empty()
+            && self.edge_kinds.is_empty()
+            && self.gained.is_empty()
+            && self.lost.is_empty()
+    }
+}
+
+type NodeKey = (&'static str, String, Option<String>);
+type CallPair = (NodeKey, NodeKey); // (function, callee or call node)
+
+fn node_key(node: &Node) -> NodeKey {
+    (node.kind.as_str(), node.name.clone(), node.usr.clone())
+}
+
+// Keys of the functions defined or declared by user code
+fn function_keys(graph: &DiGraph<Node, Edge>) -> BTreeSet<NodeKey> {
+    graph.node_weights()
+        .filter(|node| matches!(node.kind, NodeType::Function | NodeType::Main) && !node.library)
+        .map(node_key)
+        .collect()
+}
+
+// Each function's `Calls` edges, lifted from the call node to the function containing it, and
+// how many unsafe calls each function makes to each unsafe function
+fn call_relationships(graph: &DiGraph<Node, Edge>) -> (BTreeSet<CallPair>, BTreeMap<CallPair, usize>) {
+    let mut calls = BTreeSet::new();
+    let mut unsafe_calls = BTreeMap::new();
+
+    let functions = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main);
+    for func_idx in functions {
+        let caller = node_key(&graph[func_idx]);
+        for node_idx in function_subgraph(graph, func_idx) {
+            for edge in graph.edges(node_idx).filter(|edge| edge.weight().kind == EdgeType::Calls) {
+                calls.insert((caller.clone(), node_key(&graph[edge.target()])));
+            }
+            if graph[node_idx].kind == NodeType::UnsafeCall {
+                *unsafe_calls.entry((caller.clone(), node_key(&graph[node_idx]))).or_insert(0) += 1;
+            }
+        }
+    }
+
+    (calls, unsafe_calls)
+}
+
+// Calls and the risks found on them, which are what a reviewer wants to see appear or disappear
+fn is_operation(kind: &NodeType) -> bool {
+    matches!(
+        kind,
+        NodeType::Call
+            | NodeType::UnknownCall
+            | NodeType::UnsafeCall
+            | NodeType::DangerousSink
+            | NodeType::FormatString
+            | NodeType::MemoryOp
+            | NodeType::UnsafeCast
+            | NodeType::OutOfBounds
+            | NodeType::SizeofPointer
+            | NodeType::SizeMisuse
+            | NodeType::AllocationOverflow
+    )
+}
+
+// How many of each operation each function performs
+fn function_operations(graph: &DiGraph<Node, Edge>) -> BTreeMap<(NodeKey, String), usize> {
+    let mut operations = BTreeMap::new();
+
+    let functions = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main);
+    for func_idx in functions {
+        let function = node_key(&graph[func_idx]);
+        for node_idx in function_subgraph(graph, func_idx) {
+            if is_operation(&graph[node_idx].kind) {
+                *operations.entry((function.clone(), graph[node_idx].name.clone())).or_insert(0) += 1;
+            }
+        }
+    }
+
+    operations
+}
+
+// Entries of `new` that outnumber those of `old`, repeated by how many more there are
+fn extra_occurrences<K: Ord + Clone>(old: &BTreeMap<K, usize>, new: &BTreeMap<K, usize>) -> Vec<K> {
+    let mut extra = Vec::new();
+    for (key, &count) in new {
+        let before = old.get(key).copied().unwrap_or(0);
+        for _ in before..count {
+            extra.push(key.clone());
+        }
+    }
+    extra
+}
+
+// Change in the count of each kind, leaving out kinds whose count stayed the same
+fn kind_changes<'a>(old: impl Iterator<Item = &'a str>, new: impl Iterator<Item = &'a str>) -> Vec<(String, i64)> {
+    let mut changes: BTreeMap<&str, i64> = BTreeMap::new();
+    for kind in old {
+        *changes.entry(kind).or_insert(0) -= 1;
+    }
+    for kind in new {
+        *changes.entry(kind).or_insert(0) += 1;
+    }
+    changes.into_iter()
+        .filter(|&(_, change)| change != 0)
+        .map(|(kind, change)| (kind.to_string(), change))
+        .collect()
+}
+
+// Functions and call relationships `new` adds or removes relative to `old`, the unsafe calls it
+// makes more often than `old` did, how the number of nodes and edges of each kind changed, and
+// which operations each function gained or lost
+pub fn diff_cpg(old: &DiGraph<Node, Edge>, new: &DiGraph<Node, Edge>) -> CpgDiff {
+    let name_pair = |(caller, callee): &CallPair| (caller.1.clone(), callee.1.clone());
+
+    let (old_functions, new_functions) = (function_keys(old), function_keys(new));
+    let (old_calls, old_unsafe) = call_relationships(old);
+    let (new_calls, new_unsafe) = call_relationships(new);
+
+    let (old_operations, new_operations) = (function_operations(old), function_operations(new));
+    let operation_pair = |(function, operation): (NodeKey, String)| (function.1, operation);
+
+    let mut diff = CpgDiff {
+        added_functions: new_functions.difference(&old_functions).map(|key| key.1.clone()).collect(),
+        removed_functions: old_functions.difference(&new_functions).map(|key| key.1.clone()).collect(),
+        added_calls: new_calls.difference(&old_calls).map(name_pair).collect(),
+        removed_calls: old_calls.difference(&new_calls).map(name_pair).collect(),
+        new_unsafe_calls: extra_occurrences(&old_unsafe, &new_unsafe).iter().map(name_pair).collect(),
+        node_kinds: kind_changes(
+            old.node_weights().map(|node| node.kind.as_str()),
+            new.node_weights().map(|node| node.kind.as_str()),
+        ),
+        edge_kinds: kind_changes(
+            old.edge_weights().map(|edge| edge.kind.as_str()),
+            new.edge_weights().map(|edge| edge.kind.as_str()),
+        ),
+        gained: extra_occurrences(&old_operations, &new_operations).into_iter().map(operation_pair).collect(),
+        lost: extra_occurrences(&new_operations, &old_operations).into_iter().map(operation_pair).collect(),
+    };
+    // Keys order by kind first, so sort again by name alone
+    diff.added_functions.sort();
+    diff.removed_functions.sort();
+    diff.added_calls.sort();
+    diff.removed_calls.sort();
+    diff.new_unsafe_calls.sort();
+    diff.gained.sort();
+    diff.lost.sort();
+    diff
+}
+
+// Whether any chain of calls leads from function `from` to function `to`
+pub fn can_reach(graph: &DiGraph<Node, Edge>, from: &str, to: &str) -> bool {
+    shortest_call_path(graph, from, to).is_some()
+}
+
+// The fewest calls leading from `from` to `to`, as function names starting with `from`. `to` may be
+// a library function such as `system` that is only ever called.
+pub fn shortest_call_path(graph: &DiGraph<Node, Edge>, from: &str, to: &str) -> Option<Vec<String>> {
+    let (call_graph, indices) = derive_call_graph(graph);
+    let start = *indices.get(from)?;
+    let goal = *indices.get(to)?;
+
+    let (_, path) = astar(&call_graph, start, |idx| idx == goal, |_| 1, |_| 0)?;
+    Some(path.into_iter().map(|idx| call_graph[idx].clone()).collect())
+}
+
+// Whether other translation units can call the function. libclang prefixes the USR of a symbol
+// with internal linkage, such as a `static` function, with its file name (`c:util.c@F@helper`),
+// while external symbols start directly with `c:@`.
+fn is_exported(node: &Node) -> bool {
+    node.usr.as_deref().is_some_and(|usr| usr.contains("\"c:@"))
+}
+
+// Functions defined here that nothing reachable from `main` calls or takes the address of.
+// `main` and exported functions are left out, since another file may be what calls them.
+pub fn find_unreachable_functions(graph: &DiGraph<Node, Edge>) -> Vec<String> {
+    find_unreachable_functions_from(graph, &["main"])
+}
+
+// Like `find_unreachable_functions`, with the walk starting from every named entry point.
+// `References` edges count, so thread handlers and callbacks registered by address are reached.
+pub fn find_unreachable_functions_from(graph: &DiGraph<Node, Edge>, entries: &[&str]) -> Vec<String> {
+    let mut reached = HashSet::new();
+    for &entry in entries {
+        if let Some(entry_idx) = find_function(graph, entry) {
+            reached.extend(reachable_from(graph, entry_idx));
+        }
+    }
+
+    let mut unreachable: Vec<String> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function && !graph[idx].library)
+        .filter(|&idx| graph.edges(idx).any(|edge| edge.weight().kind == EdgeType::Contains))
+        .filter(|idx| !reached.contains(idx) && !is_exported(&graph[*idx]))
+        .map(|idx| graph[idx].name.clone())
+        .collect();
+    unreachable.sort();
+    unreachable
+}
+
+// Functions defined here that no call anywhere names and no code takes the address of, ordered
+// by line. Unlike `find_unreachable_functions` this needs no entry point: a function only called
+// by other dead code isn't dead itself, and a function that only calls itself is. `main` and
+// exported functions are left out, since another file may be what calls them, and so are nested
+// functions and blocks, which live as long as the function containing them.
+pub fn find_dead_functions(graph: &DiGraph<Node, Edge>) -> Vec<NodeIndex> {
+    let mut dead: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function && !graph[idx].library)
+        .filter(|&idx| graph.edges(idx).any(|edge| edge.weight().kind == EdgeType::Contains))
+        .filter(|&idx| !graph.edges_directed(idx, Direction::Incoming).any(|edge| edge.weight().kind == EdgeType::Contains))
+        .filter(|&idx| !is_exported(&graph[idx]))
+        .filter(|&func_idx| {
+            // Calls reach the function from call nodes, so look at where those sit
+            let body = function_subgraph(graph, func_idx);
+            !graph.edges_directed(func_idx, Direction::Incoming).any(|edge| match edge.weight().kind {
+                EdgeType::Calls => !body.contains(&edge.source()),
+                EdgeType::References => true,
+                _ => false,
+            })
+        })
+        .collect();
+    dead.sort_by_key(|&idx| (graph[idx].line, idx));
+    dead
+}
+
+pub fn function_profile(graph: &DiGraph<Node, Edge>, func_idx: NodeIndex) -> FunctionProfile {
+    let mut profile = FunctionProfile::default();
+
+    for node_idx in function_subgraph(graph, func_idx) {
+        // The function node itself is left out so `main` can match an ordinary function
+        if node_idx != func_idx {
+            *profile.node_kinds.entry(graph[node_idx].kind.clone()).or_insert(0) += 1;
+        }
+
+        for edge in graph.edges(node_idx) {
+            *profile.edge_kinds.entry(edge.weight().kind.clone()).or_insert(0) += 1;
+        }
+    }
+
+    profile
+}
+
+// Sum of per-key minimums and maximums across two histograms
+fn histogram_overlap<K: Eq + Hash>(a: &HashMap<K, usize>, b: &HashMap<K, usize>) -> (usize, usize) {
+    let keys: HashSet<&K> = a.keys().chain(b.keys()).collect();
+
+    keys.into_iter().fold((0, 0), |(min_sum, max_sum), key| {
+        let left = a.get(key).copied().unwrap_or(0);
+        let right = b.get(key).copied().unwrap_or(0);
+        (min_sum + left.min(right), max_sum + left.max(right))
+    })
+}
+
+// Weighted Jaccard similarity of two profiles, from 0.0 (disjoint) to 1.0 (identical shape)
+pub fn profile_similarity(a: &FunctionProfile, b: &FunctionProfile) -> f64 {
+    let (node_min, node_max) = histogram_overlap(&a.node_kinds, &b.node_kinds);
+    let (edge_min, edge_max) = histogram_overlap(&a.edge_kinds, &b.edge_kinds);
+
+    let total = node_max + edge_max;
+    if total == 0 {
+        return 1.0;
+    }
+
+    (node_min + edge_min) as f64 / total as f64
+}
+
+pub fn structural_similarity(graph: &DiGraph<Node, Edge>, a: NodeIndex, b: NodeIndex) -> f64 {
+    profile_similarity(&function_profile(graph, a), &function_profile(graph, b))
+}
+
+// Report pairs of functions whose similarity reaches the threshold, most similar first
+pub fn find_clones(graph: &DiGraph<Node, Edge>, threshold: f64) -> Vec<(NodeIndex, NodeIndex, f64)> {
+    let functions: Vec<(NodeIndex, FunctionProfile)> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main)
+        .map(|idx| (idx, function_profile(graph, idx)))
+        .filter(|(_, profile)| profile.size() >= MIN_CLONE_SIZE)
+        .collect();
+
+    let mut clones = Vec::new();
+    for (i, (a_idx, a_profile)) in functions.iter().enumerate() {
+        for (b_idx, b_profile) in &functions[i + 1..] {
+            let score = profile_similarity(a_profile, b_profile);
+            if score >= threshold {
+                clones.push((*a_idx, *b_idx, score));
+            }
+        }
+    }
+
+    clones.sort_by(|a, b| b.2.total_cmp(&a.2));
+    clones
+}
+
+// Find `p = realloc(p, n)`: a realloc whose result is stored into the same pointer it frees.
+// If realloc fails it returns NULL and the only reference to the original block is lost.
+pub fn find_realloc_self_assignments(graph: &DiGraph<Node, Edge>) -> Vec<(NodeIndex, NodeIndex)> {
+    let mut findings = Vec::new();
+
+    for mem_op_idx in graph.node_indices() {
+        let node = &graph[mem_op_idx];
+        if node.kind != NodeType::MemoryOp || node.name != "MemoryOp: realloc" {
+            continue;
+        }
+
+        let freed: HashSet<NodeIndex> = graph.edges(mem_op_idx)
+            .filter(|edge| edge.weight().kind == EdgeType::Frees)
+            .map(|edge| edge.target())
+            .collect();
+
+        for edge in graph.edges_directed(mem_op_idx, Direction::Incoming) {
+            if edge.weight().kind == EdgeType::Allocates && freed.contains(&edge.source()) {
+                findings.push((mem_op_idx, edge.source()));
+            }
+        }
+    }
+
+    findings
+}
+
+// Allocations a function neither frees nor hands on, as (allocation, pointer) ordered by line.
+// A free counts wherever the function makes it, including under a cleanup label that an error
+// path reaches with `goto out;`. The check is per function rather than per path, so a free on
+// only one path still counts. Returning the pointer, storing it into another variable, a member
+// or through a pointer, or passing it to a function of the program hands it on; library calls
+// such as `strcpy` only borrow it.
+pub fn find_leaks(graph: &DiGraph<Node, Edge>) -> Vec<(NodeIndex, NodeIndex)> {
+    let mut leaks = Vec::new();
+
+    let functions = graph.node_indices()
+        .filter(|&idx| matches!(graph[idx].kind, NodeType::Function | NodeType::Main));
+    for func_idx in functions {
+        let body = function_subgraph(graph, func_idx);
+
+        for &ptr_idx in &body {
+            let allocations: Vec<NodeIndex> = graph.edges(ptr_idx)
+                .filter(|edge| edge.weight().kind == EdgeType::Allocates)
+                .map(|edge| edge.target())
+                .collect();
+            if allocations.is_empty() {
+                continue;
+            }
+
+            let kept = graph.edges_directed(ptr_idx, Direction::Incoming).any(|edge| {
+                let user = &graph[edge.source()];
+                let is_free = user.name == "MemoryOp: free" || user.name == "Call: free";
+                match edge.weight().kind {
+                    // realloc frees the block it was given, not the one it hands back
+                    EdgeType::Frees => body.contains(&edge.source()) && !allocations.contains(&edge.source()),
+                    EdgeType::Uses if is_free => body.contains(&edge.source()),
+                    EdgeType::Uses => match user.kind {
+                        NodeType::Call | NodeType::UnknownCall => !user.library,
+                        NodeType::Variable | NodeType::Pointer | NodeType::Array | NodeType::BufferParameter | NodeType::Parameter => true,
+                        _ => false,
+                    },
+                    EdgeType::Reads | EdgeType::Returns => true,
+                    _ => false,
+                }
+            });
+            if !kept {
+                leaks.extend(allocations.into_iter().map(|mem_op_idx| (mem_op_idx, ptr_idx)));
+            }
+        }
+    }
+
+    leaks.sort_by_key(|&(mem_op_idx, ptr_idx)| (graph[mem_op_idx].line, mem_op_idx, ptr_idx));
+    leaks.dedup();
+    leaks
+}
+
+// "'buf' is allocated by malloc but never freed"
+pub fn leak_message(graph: &DiGraph<Node, Edge>, mem_op_idx: NodeIndex, ptr_idx: NodeIndex) -> String {
+    format!(
+        "'{}' is allocated by {} but never freed",
+        symbol_name(&graph[ptr_idx].name),
+        graph[mem_op_idx].name.trim_start_matches("MemoryOp: ")
+    )
+}
+
+// Pointers to const and arrays of const that are freed or stored into, as (operation, pointer)
+// ordered by line. A store counts when it goes through a subscript, as in `name[0] = 'x'`;
+// reassigning the pointer itself is allowed.
+pub fn find_const_violations(graph: &DiGraph<Node, Edge>) -> Vec<(NodeIndex, NodeIndex)> {
+    let mut violations = Vec::new();
+
+    for ptr_idx in graph.node_indices().filter(|&idx| graph[idx].is_const) {
+        for edge in graph.edges_directed(ptr_idx, Direction::Incoming) {
+            let op_idx = edge.source();
+            let violates = match edge.weight().kind {
+                EdgeType::Frees => true,
+                EdgeType::Assigns | EdgeType::Writes => graph.edges(op_idx)
+                    .filter(|child| child.weight().kind == EdgeType::Contains && graph[child.target()].kind == NodeType::ArrayAccess)
+                    .any(|child| graph.contains_edge(child.target(), ptr_idx)),
+                _ => false,
+            };
+            if violates {
+                violations.push((op_idx, ptr_idx));
+            }
+        }
+    }
+
+    violations.sort_by_key(|&(op_idx, ptr_idx)| (graph[op_idx].line, op_idx, ptr_idx));
+    violations
+}
+
+// "'name' points to const but is freed", or "'table' is const but is written" for an array
+pub fn const_violation_message(graph: &DiGraph<Node, Edge>, op_idx: NodeIndex, ptr_idx: NodeIndex) -> String {
+    let name = symbol_name(&graph[ptr_idx].name);
+    if graph[ptr_idx].kind == NodeType::Array {
+        return format!("'{}' is const but is written", name);
+    }
+    let action = if graph[op_idx].kind == NodeType::Assignment { "written through" } else { "freed" };
+    format!("'{}' points to const but is {}", name, action)
+}
+
+fn is_variable(node: &Node) -> bool {
+    matches!(
+        node.kind,
+        NodeType::Parameter | NodeType::BufferParameter | NodeType::Variable | NodeType::Pointer | NodeType::Array
+    )
+}
+
+// Variables holding outside input, each with the input call it first came from. A call such as
+// `fgets` or `scanf` taints the variables it `Assigns`; taint then spreads to a variable
+// initialized from a tainted one, and to the target of an assignment that reads one, directly or
+// through a call on its right-hand side. Order and control flow are ignored, so a variable is
+// tainted everywhere once any statement taints it.
+pub fn tainted_variables(graph: &DiGraph<Node, Edge>) -> HashMap<NodeIndex, NodeIndex> {
+    let mut origins: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    let inputs = graph.edge_references()
+        .filter(|edge| edge.weight().kind == EdgeType::Assigns);
+    for edge in inputs {
+        if let Entry::Vacant(entry) = origins.entry(edge.target()) {
+            entry.insert(edge.source());
+            queue.push_back(edge.target());
+        }
+    }
+
+    while let Some(var_idx) = queue.pop_front() {
+        let origin = origins[&var_idx];
+        let mut derived = Vec::new();
+
+        for edge in graph.edges_directed(var_idx, Direction::Incoming) {
+            if !matches!(edge.weight().kind, EdgeType::Uses | EdgeType::Reads) {
+                continue;
+            }
+            let reader = edge.source();
+            if is_variable(&graph[reader]) {
+                derived.push(reader);
+                continue;
+            }
+
+            // The assignment itself, or the one whose right-hand side holds the reading call
+            let assignment = if graph[reader].kind == NodeType::Assignment {
+                Some(reader)
+            } else {
+                graph.edges_directed(reader, Direction::Incoming)
+                    .find(|parent| parent.weight().kind == EdgeType::Contains && graph[parent.source()].kind == NodeType::Assignment)
+                    .map(|parent| parent.source())
+            };
+            if let Some(assign_idx) = assignment {
+                derived.extend(graph.edges(assign_idx)
+                    .filter(|write| write.weight().kind == EdgeType::Writes)
+                    .map(|write| write.target()));
+            }
+        }
+
+        for derived_idx in derived {
+            if let Entry::Vacant(entry) = origins.entry(derived_idx) {
+                entry.insert(origin);
+                queue.push_back(derived_idx);
+            }
+        }
+    }
+
+    origins
+}
+
+// Tainted variables passed to an unsafe call or command sink, as (input call, sink, variable)
+// ordered by the sink's line. A call that both reads the input and is unsafe, like `gets`, is
+// not reported as reaching itself.
+pub fn find_taint_flows(graph: &DiGraph<Node, Edge>) -> Vec<(NodeIndex, NodeIndex, NodeIndex)> {
+    let origins = tainted_variables(graph);
+
+    let mut flows: Vec<(NodeIndex, NodeIndex, NodeIndex)> = graph.edge_references()
+        .filter(|edge| edge.weight().kind == EdgeType::Uses)
+        .filter(|edge| matches!(graph[edge.source()].kind, NodeType::UnsafeCall | NodeType::DangerousSink))
+        .filter_map(|edge| origins.get(&edge.target()).map(|&origin| (origin, edge.source(), edge.target())))
+        .filter(|&(origin, sink, _)| origin != sink)
+        .collect();
+    flows.sort_by_key(|&(origin, sink, var_idx)| (graph[sink].line, sink, graph[origin].line, var_idx));
+    flows.dedup();
+    flows
+}
+
+// "'line' reaches 'system' with input read by 'fgets' at line 4"
+pub fn taint_flow_message(graph: &DiGraph<Node, Edge>, origin_idx: NodeIndex, sink_idx: NodeIndex, var_idx: NodeIndex) -> String {
+    format!(
+        "'{}' reaches '{}' with input read by '{}' at line {}",
+        symbol_name(&graph[var_idx].name),
+        symbol_name(&graph[sink_idx].name),
+        symbol_name(&graph[origin_idx].name),
+        graph[origin_idx].line.unwrap_or(0)
+    )
+}
+
+// printf-family calls whose format string isn't a literal, as (call line, function name)
+pub fn find_format_string_risks(graph: &DiGraph<Node, Edge>) -> Vec<(Option<usize>, String)> {
+    let mut risks: Vec<(Option<usize>, String)> = graph.node_weights()
+        .filter(|node| node.kind == NodeType::FormatString)
+        .map(|node| (node.line, node.name.trim_start_matches("FormatString: ").to_string()))
+        .collect();
+    risks.sort();
+    risks
+}
+
+// Calls that pass a different number of arguments than the function they resolve to declares, as
+// (call, function, parameters, arguments). Variadic and unprototyped functions accept any count.
+fn arity_mismatches(graph: &DiGraph<Node, Edge>) -> Vec<(NodeIndex, NodeIndex, usize, usize)> {
+    let mut mismatches = Vec::new();
+    for call_idx in graph.node_indices() {
+        let Some(actual) = graph[call_idx].arguments else { continue };
+        let callees = graph.edges(call_idx)
+            .filter(|edge| edge.weight().kind == EdgeType::Calls && !edge.weight().indirect)
+            .map(|edge| edge.target());
+        for func_idx in callees {
+            let expected = graph[func_idx].signature.as_ref()
+                .filter(|signature| !signature.variadic)
+                .map(|signature| signature.parameters.len());
+            if let Some(expected) = expected.filter(|&expected| expected != actual) {
+                mismatches.push((call_idx, func_idx, expected, actual));
+            }
+        }
+    }
+    mismatches
+}
+
+// Calls with the wrong number of arguments, as (call line, function name, parameters, arguments)
+pub fn find_arity_mismatches(graph: &DiGraph<Node, Edge>) -> Vec<(Option<usize>, String, usize, usize)> {
+    let mut mismatches: Vec<(Option<usize>, String, usize, usize)> = arity_mismatches(graph).into_iter()
+        .map(|(call_idx, func_idx, expected, actual)| (graph[call_idx].line, symbol_name(&graph[func_idx].name).to_string(), expected, actual))
+        .collect();
+    mismatches.sort();
+    mismatches
+}
+
+// "'f' takes 2 arguments but is called with 3"
+pub fn arity_message(function_name: &str, expected: usize, actual: usize) -> String {
+    format!(
+        "'{}' takes {} argument{} but is called with {}",
+        function_name,
+        expected,
+        if expected == 1 { "" } else { "s" },
+        actual
+    )
+}
+
+// Declarations that hide a variable or parameter from an enclosing scope, as
+// (inner declaration, outer declaration) ordered by the inner one's line
+pub fn find_shadowing(graph: &DiGraph<Node, Edge>) -> Vec<(NodeIndex, NodeIndex)> {
+    let mut shadowing: Vec<(NodeIndex, NodeIndex)> = graph.edge_references()
+        .filter(|edge| edge.weight().kind == EdgeType::Shadows)
+        .map(|edge| (edge.source(), edge.target()))
+        .collect();
+    shadowing.sort_by_key(|&(inner, outer)| (graph[inner].line, inner.index(), outer.index()));
+    shadowing
+}
+
+// Pair each write of `var` with the reads that follow it before the next write, as
+// (defining node, using node). Statements are ordered by line only, so this is a straight-line
+// approximation: branches and loops are not followed, and nodes without a line are skipped.
+pub fn def_use_chains(graph: &DiGraph<Node, Edge>, var: NodeIndex) -> Vec<(NodeIndex, NodeIndex)> {
+    // (line, whether it writes, node). On one line reads sort first, so `x += 1` reads the
+    // previous value of `x` before it defines the next one.
+    let mut accesses: Vec<(usize, bool, NodeIndex)> = graph.edges_directed(var, Direction::Incoming)
+        .filter_map(|edge| {
+            let is_write = match edge.weight().kind {
+                EdgeType::Assigns | EdgeType::Writes => true,
+                EdgeType::Reads | EdgeType::Uses => false,
+                _ => return None,
+            };
+            graph[edge.source()].line.map(|line| (line, is_write, edge.source()))
+        })
+        .collect();
+    accesses.sort_by_key(|&(line, is_write, node_idx)| (line, is_write, node_idx.index()));
+    accesses.dedup();
+
+    let mut chains = Vec::new();
+    let mut current_def = None;
+    for (_, is_write, node_idx) in accesses {
+        if is_write {
+            current_def = Some(node_idx);
+        } else if let Some(def_idx) = current_def {
+            chains.push((def_idx, node_idx));
+        }
+    }
+
+    chains
+}
+
+// The def-use pairs of the variable or parameter named `var_name` in function `func_name`,
+// ordered by the line of the use. When the name is declared more than once in the function, as
+// with a local that shadows a parameter, each declaration contributes its own pairs, so a use
+// is never paired with a write to the other one. Ordering follows `def_use_chains`, by line
+// rather than control flow.
+pub fn def_use_chain(graph: &DiGraph<Node, Edge>, var_name: &str, func_name: &str) -> Vec<(NodeIndex, NodeIndex)> {
+    let Some(func_idx) = find_function(graph, func_name) else {
+        return Vec::new();
+    };
+
+    let mut chains: Vec<(NodeIndex, NodeIndex)> = function_subgraph(graph, func_idx).into_iter()
+        .filter(|&idx| is_variable(&graph[idx]) && symbol_name(&graph[idx].name) == var_name)
+        .flat_map(|var_idx| def_use_chains(graph, var_idx))
+        .collect();
+    chains.sort_by_key(|&(def_idx, use_idx)| (graph[use_idx].line, graph[def_idx].line, use_idx.index(), def_idx.index()));
+    chains
+}
+
+// A single detection, serialized as one JSON object per line by `write_findings_jsonl`
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub message: String,
+    pub file: String,
+    pub line: Option<usize>,
+    pub nodes: Vec<usize>, // Indices of the graph nodes involved
+}
+
+// The line-less `Unsafe: strcpy` node that `Controls` an unsafe call stands for the same call,
+// so only the call itself is counted or reported
+fn is_unsafe_controller(graph: &DiGraph<Node, Edge>, node_idx: NodeIndex) -> bool {
+    graph.edges(node_idx).any(|edge| edge.weight().kind == EdgeType::Controls)
+}
+
+// Bare symbol name from a declaration label such as `Pointer: cmd (char *)`
+fn symbol_name(label: &str) -> &str {
+    let name = label.split_once(": ").map_or(label, |(_, rest)| rest);
+    name.split_once(" (").map_or(name, |(name, _)| name)
+}
+
+// "'x' declared at line 4 shadows the declaration at line 2"
+pub fn shadowing_message(graph: &DiGraph<Node, Edge>, inner_idx: NodeIndex, outer_idx: NodeIndex) -> String {
+    format!(
+        "'{}' declared at line {} shadows the declaration at line {}",
+        symbol_name(&graph[inner_idx].name),
+        graph[inner_idx].line.unwrap_or(0),
+        graph[outer_idx].line.unwrap_or(0)
+    )
+}
+
+// Run every graph-based detection and collect the results, ordered by line
+pub fn collect_findings(graph: &DiGraph<Node, Edge>, file: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for node_idx in graph.node_indices() {
+        let node = &graph[node_idx];
+        match node.kind {
+            NodeType::UnsafeCall if !is_unsafe_controller(graph, node_idx) => findings.push(Finding {
+                rule: "unsafe-call",
+                message: format!("call to unsafe function '{}'", node.name.trim_start_matches("Unsafe: ")),
+                file: file.to_string(),
+                line: node.line,
+                nodes: vec![node_idx.index()],
+            }),
+            NodeType::DangerousSink => {
+                let mut variables: Vec<usize> = graph.edges(node_idx)
+                    .filter(|edge| edge.weight().kind == EdgeType::Uses)
+                    .map(|edge| edge.target().index())
+                    .collect();
+                variables.sort();
+                let names: Vec<&str> = variables.iter()
+                    .map(|&idx| symbol_name(&graph[NodeIndex::new(idx)].name))
+                    .collect();
+
+                findings.push(Finding {
+                    rule: "command-injection",
+                    message: format!(
+                        "command passed to '{}' is built from {}",
+                        node.name.trim_start_matches("Sink: "),
+                        names.join(", ")
+                    ),
+                    file: file.to_string(),
+                    line: node.line,
+                    nodes: std::iter::once(node_idx.index()).chain(variables).collect(),
+                });
+            },
+            NodeType::FormatString => findings.push(Finding {
+                rule: "format-string",
+                message: format!(
+                    "format string passed to '{}' is not a literal",
+                    node.name.trim_start_matches("FormatString: ")
+                ),
+                file: file.to_string(),
+                line: node.line,
+                nodes: vec![node_idx.index()],
+            }),
+            NodeType::OutOfBounds => {
+                let arrays = graph.edges(node_idx)
+                    .filter(|edge| edge.weight().kind == EdgeType::Accesses)
+                    .map(|edge| edge.target().index());
+
+                findings.push(Finding {
+                    rule: "out-of-bounds",
+                    message: format!("out-of-bounds access {}", node.name.trim_start_matches("OutOfBounds: ")),
+                    file: file.to_string(),
+                    line: node.line,
+                    nodes: std::iter::once(node_idx.index()).chain(arrays).collect(),
+                });
+            },
+            NodeType::SizeofPointer => {
+                let function_name = graph.edges_directed(node_idx, Direction::Incoming)
+                    .find(|edge| edge.weight().kind == EdgeType::Contains)
+                    .map(|edge| symbol_name(&graph[edge.source()].name))
+                    .unwrap_or_default();
+
+                findings.push(Finding {
+                    rule: "sizeof-pointer",
+                    message: format!(
+                        "size passed to '{}' is {}, the size of a pointer rather than of what it points to",
+                        function_name,
+                        node.name.trim_start_matches("SizeofPointer: ")
+                    ),
+                    file: file.to_string(),
+                    line: node.line,
+                    nodes: vec![node_idx.index()],
+                });
+            },
+            NodeType::SizeMisuse => {
+                let function_name = graph.edges_directed(node_idx, Direction::Incoming)
+                    .find(|edge| edge.weight().kind == EdgeType::Contains)
+                    .map(|edge| symbol_name(&graph[edge.source()].name))
+                    .unwrap_or_default();
+
+                findings.push(Finding {
+                    rule: "bounded-size",
+                    message: format!(
+                        "size passed to '{}' is {}, which measures something other than the destination",
+                        function_name,
+                        node.name.trim_start_matches("SizeMisuse: ")
+                    ),
+                    file: file.to_string(),
+                    line: node.line,
+                    nodes: vec![node_idx.index()],
+                });
+            },
+            NodeType::AllocationOverflow => {
+                let function_name = graph.edges_directed(node_idx, Direction::Incoming)
+                    .find(|edge| edge.weight().kind == EdgeType::Contains)
+                    .map(|edge| symbol_name(&graph[edge.source()].name))
+                    .unwrap_or_default();
+                let checked = if function_name == "realloc" { "reallocarray" } else { "calloc" };
+
+                findings.push(Finding {
+                    rule: "allocation-overflow",
+                    message: format!(
+                        "size passed to '{}' is {}, which can overflow; {} checks the multiplication",
+                        function_name,
+                        node.name.trim_start_matches("AllocationOverflow: "),
+                        checked
+                    ),
+                    file: file.to_string(),
+                    line: node.line,
+                    nodes: vec![node_idx.index()],
+                });
+            },
+            NodeType::UnsafeCast => findings.push(Finding {
+                rule: "unsafe-cast",
+                message: format!("dangerous cast {}", node.name.trim_start_matches("UnsafeCast: ")),
+                file: file.to_string(),
+                line: node.line,
+                nodes: vec![node_idx.index()],
+            }),
+            _ => {}
+        }
+    }
+
+    for (mem_op_idx, ptr_idx) in find_realloc_self_assignments(graph) {
+        findings.push(Finding {
+            rule: "realloc-self-assignment",
+            message: format!(
+                "'{}' is overwritten by realloc's result; the original block leaks if realloc fails",
+                graph[ptr_idx].name
+            ),
+            file: file.to_string(),
+            line: graph[mem_op_idx].line,
+            nodes: vec![mem_op_idx.index(), ptr_idx.index()],
+        });
+    }
+
+    for (mem_op_idx, ptr_idx) in find_leaks(graph) {
+        findings.push(Finding {
+            rule: "memory-leak",
+            message: leak_message(graph, mem_op_idx, ptr_idx),
+            file: file.to_string(),
+            line: graph[mem_op_idx].line,
+            nodes: vec![mem_op_idx.index(), ptr_idx.index()],
+        });
+    }
+
+    for (op_idx, ptr_idx) in find_const_violations(graph) {
+        findings.push(Finding {
+            rule: "const-write",
+            message: const_violation_message(graph, op_idx, ptr_idx),
+            file: file.to_string(),
+            line: graph[op_idx].line,
+            nodes: vec![op_idx.index(), ptr_idx.index()],
+        });
+    }
+
+    for (origin_idx, sink_idx, var_idx) in find_taint_flows(graph) {
+        findings.push(Finding {
+            rule: "tainted-sink",
+            message: taint_flow_message(graph, origin_idx, sink_idx, var_idx),
+            file: file.to_string(),
+            line: graph[sink_idx].line,
+            nodes: vec![sink_idx.index(), var_idx.index(), origin_idx.index()],
+        });
+    }
+
+    for (call_idx, func_idx, expected, actual) in arity_mismatches(graph) {
+        findings.push(Finding {
+            rule: "arity-mismatch",
+            message: arity_message(symbol_name(&graph[func_idx].name), expected, actual),
+            file: file.to_string(),
+            line: graph[call_idx].line,
+            nodes: vec![call_idx.index(), func_idx.index()],
+        });
+    }
+
+    for (inner_idx, outer_idx) in find_shadowing(graph) {
+        findings.push(Finding {
+            rule: "shadowing",
+            message: shadowing_message(graph, inner_idx, outer_idx),
+            file: file.to_string(),
+            line: graph[inner_idx].line,
+            nodes: vec![inner_idx.index(), outer_idx.index()],
+        });
+    }
+
+    findings.sort_by_key(|finding| finding.line);
+    findings
+}
+
+pub fn write_findings_jsonl<W: Write>(findings: &[Finding], writer: &mut W) -> io::Result<()> {
+    for finding in findings {
+        serde_json::to_writer(&mut *writer, finding)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{anyhow, Context, Result};
+
+// Library functions that take a function to call back later, with the zero-based index of the
+// argument holding it. A function with several callback arguments has one entry per argument.
+const DEFAULT_CALLBACK_REGISTRARS: &[(&str, usize)] = &[
+    ("pthread_create", 2),
+    ("pthread_once", 1),
+    ("thrd_create", 1),
+    ("qsort", 3),
+    ("bsearch", 4),
+    ("signal", 1),
+    ("atexit", 0),
+    ("at_quick_exit", 0),
+    ("on_exit", 0),
+    ("g_idle_add", 0),
+    ("g_timeout_add", 1),
+    ("g_signal_connect", 2),
+    ("g_thread_new", 1),
+    ("uv_timer_start", 1),
+    ("uv_read_start", 1),
+    ("uv_read_start", 2),
+    ("uv_queue_work", 2),
+    ("uv_queue_work", 3),
+    ("uv_close", 1),
+];
+
+// The built-in registrars as `(function, callback argument index)` pairs
+pub fn default_callback_registrars() -> Vec<(String, usize)> {
+    DEFAULT_CALLBACK_REGISTRARS.iter()
+        .map(|&(function, argument)| (function.to_string(), argument))
+        .collect()
+}
+
+pub fn load_callback_registrars(path: &Path) -> Result<Vec<(String, usize)>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read callback table: {:?}", path))?;
+    parse_callback_registrars(&content).with_context(|| format!("Failed to parse callback table: {:?}", path))
+}
+
+// One registrar per line as `function argument_index`, such as `event_set 3`. Blank lines and
+// `#` comments are ignored.
+pub fn parse_callback_registrars(content: &str) -> Result<Vec<(String, usize)>> {
+    let mut registrars = Vec::new();
+
+    for (number, line) in content.lines().enumerate() {
+        let number = number + 1;
+        let line = line.split_once('#').map_or(line, |(before, _)| before).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let (Some(function), Some(argument), None) = (words.next(), words.next(), words.next()) else {
+            return Err(anyhow!("line {}: expected `function argument_index`", number));
+        };
+        if !function.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow!("line {}: '{}' is not a function name", number, function));
+        }
+        let argument = argument.parse()
+            .map_err(|_| anyhow!("line {}: '{}' is not an argument index", number, argument))?;
+        registrars.push((function.to_string(), argument));
+    }
+
+    Ok(registrars)
+}
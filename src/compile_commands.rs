@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+// One entry of a `compile_commands.json` database. Entries give the command either as an
+// argument list or as a single shell-quoted string.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompileCommand {
+    pub directory: PathBuf,
+    pub file: PathBuf,
+    #[serde(default)]
+    pub arguments: Option<Vec<String>>,
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+impl CompileCommand {
+    // The source file as an absolute path, resolving a relative `file` against `directory`
+    pub fn source_path(&self) -> PathBuf {
+        self.directory.join(&self.file)
+    }
+
+    // Arguments to hand to the clang parser: the recorded command without the compiler, the
+    // source file and output options, plus the working directory so relative include paths
+    // still resolve
+    pub fn clang_args(&self) -> Vec<String> {
+        let words = match (&self.arguments, &self.command) {
+            (Some(arguments), _) => arguments.clone(),
+            (None, Some(command)) => split_command_line(command),
+            (None, None) => Vec::new(),
+        };
+        let source = self.source_path();
+
+        let mut args = vec![format!("-working-directory={}", self.directory.display())];
+        let mut words = words.into_iter().skip(1); // The compiler itself
+        while let Some(word) = words.next() {
+            match word.as_str() {
+                "-c" => {},
+                "-o" => {
+                    words.next();
+                },
+                _ if word.starts_with("-o") && word.len() > 2 => {},
+                _ if !word.starts_with('-') && self.directory.join(&word) == source => {},
+                _ => args.push(word),
+            }
+        }
+
+        args
+    }
+}
+
+pub fn load_compile_commands(path: &Path) -> Result<Vec<CompileCommand>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read compilation database: {:?}", path))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse compilation database: {:?}", path))
+}
+
+// The entry that compiles `file`, comparing canonical paths so `./src/a.c` matches `src/a.c`
+pub fn find_compile_command<'a>(commands: &'a [CompileCommand], file: &Path) -> Option<&'a CompileCommand> {
+    let wanted = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    commands.iter().find(|command| {
+        let source = command.source_path();
+        fs::canonicalize(&source).unwrap_or(source) == wanted
+    })
+}
+
+// Split a command string the way a POSIX shell would for simple commands: whitespace separates
+// words, quotes group them and a backslash escapes the next character outside single quotes
+fn split_command_line(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => current.push(c),
+            (_, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                in_word = true;
+            },
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            },
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            },
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            },
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+
+    words
+}
@@ -1,56 +1,243 @@
-use crate::types::{Edge, EdgeType, Node, NodeType};
+use crate::analysis::{function_subgraph, Finding};
+use crate::theme::Theme;
+use crate::types::{Edge, EdgeType, Node, NodeType, Signature};
 use petgraph::graph::{DiGraph, NodeIndex};
-use serde_json::json;
-use std::collections::HashMap;
+use petgraph::Direction;
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+// Character set used for the emitted graph text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputEncoding {
+    Utf8,  // Labels keep their original UTF-8 characters
+    Ascii, // Non-ASCII characters are escaped in the format's own syntax
+}
+
+impl FromStr for OutputEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utf8" | "utf-8" => Ok(OutputEncoding::Utf8),
+            "ascii" => Ok(OutputEncoding::Ascii),
+            _ => Err(format!("unknown output encoding '{}' (expected utf8 or ascii)", s)),
+        }
+    }
+}
+
+// Direction DOT lays out ranks in, as given to Graphviz's `rankdir`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RankDir {
+    #[default]
+    TopToBottom,
+    LeftToRight,
+    BottomToTop,
+    RightToLeft,
+}
+
+impl RankDir {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RankDir::TopToBottom => "TB",
+            RankDir::LeftToRight => "LR",
+            RankDir::BottomToTop => "BT",
+            RankDir::RightToLeft => "RL",
+        }
+    }
+}
+
+impl FromStr for RankDir {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "TB" => Ok(RankDir::TopToBottom),
+            "LR" => Ok(RankDir::LeftToRight),
+            "BT" => Ok(RankDir::BottomToTop),
+            "RL" => Ok(RankDir::RightToLeft),
+            _ => Err(format!("unknown rank direction '{}' (expected TB, LR, BT or RL)", s)),
+        }
+    }
+}
+
+// Escape a label for use inside a double-quoted DOT string
+pub fn escape_dot_label(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            // Other control characters have no DOT escape and would corrupt the label
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Replace non-ASCII characters in DOT output with numeric character references
+pub fn dot_to_ascii(dot: &str) -> String {
+    let mut output = String::with_capacity(dot.len());
+    for c in dot.chars() {
+        if c.is_ascii() {
+            output.push(c);
+        } else {
+            output.push_str(&format!("&#{};", c as u32));
+        }
+    }
+    output
+}
+
+// Replace non-ASCII characters in JSON output with \u escapes, using surrogate pairs beyond the BMP.
+// Only string contents can hold non-ASCII characters, so this keeps the document valid.
+pub fn json_to_ascii(json: &str) -> String {
+    let mut output = String::with_capacity(json.len());
+    let mut units = [0u16; 2];
+    for c in json.chars() {
+        if c.is_ascii() {
+            output.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut units) {
+                output.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    output
+}
+
+// One DOT node statement, without indentation or trailing newline
+fn dot_node_statement(node_idx: NodeIndex, node: &Node, theme: &Theme) -> String {
+    let (shape, color, style) = node_kind_style(&node.kind);
+
+    // Library calls are drawn dashed so user code stands out; unsafe ones keep their warning color
+    let (color, style) = if node.library && node.kind == NodeType::Call {
+        ("plum", "filled,dashed")
+    } else {
+        (color, style)
+    };
+    
+    // A theme replaces whichever parts of the look it sets
+    let themed = theme.node_style(&node.kind);
+    let shape = themed.and_then(|t| t.shape.as_deref()).unwrap_or(shape);
+    let color = themed.and_then(|t| t.color.as_deref()).unwrap_or(color);
+    let style = themed.and_then(|t| t.style.as_deref()).unwrap_or(style);
+
+    // Add type information if available
+    let label = if let Some(ref type_info) = node.type_info {
+        format!("{} [{}]", node.name, type_info)
+    } else {
+        node.name.clone()
+    };
+
+    format!(
+        "{} [label=\"{}\", shape={}, fillcolor=\"{}\", style=\"{}\"];",
+        node_idx.index(), escape_dot_label(&label), shape, escape_dot_label(color), escape_dot_label(style)
+    )
+}
+
+// Built-in shape, fill color and style of each node kind
+fn node_kind_style(kind: &NodeType) -> (&'static str, &'static str, &'static str) {
+    match kind {
+        NodeType::UnsafeCall => ("ellipse", "red", "filled"),
+        NodeType::DangerousSink => ("doubleoctagon", "red", "filled"),
+        NodeType::FormatString => ("octagon", "tomato", "filled"),
+        NodeType::Call => ("ellipse", "purple", "filled"),
+        NodeType::UnknownCall => ("ellipse", "thistle", "filled,dashed"),
+        NodeType::Main => ("ellipse", "green", "filled"),
+        NodeType::Function => ("ellipse", "lightblue", "filled"),
+        NodeType::BasicBlock => ("box", "red", "filled,rounded"),
+        NodeType::Parameter => ("ellipse", "orange", "filled"),
+        NodeType::BufferParameter => ("ellipse", "blue", "filled"),
+        NodeType::Variable => ("ellipse", "green", "filled"),
+        NodeType::Pointer => ("ellipse", "darkblue", "filled"),
+        NodeType::Array => ("ellipse", "lightyellow", "filled"),
+        NodeType::StringLiteral => ("note", "white", "filled"),
+        NodeType::IfStatement => ("diamond", "indigo", "filled"),
+        NodeType::ForLoop => ("box", "lightblue", "filled,rounded"),
+        NodeType::WhileLoop => ("box", "lightblue", "filled,rounded"),
+        NodeType::Assignment => ("ellipse", "grey", "filled"),
+        NodeType::MemoryOp => ("ellipse", "violet", "filled"),
+        NodeType::Dereference => ("ellipse", "darkred", "filled"),
+        NodeType::AddressOf => ("ellipse", "lightgreen", "filled"),
+        NodeType::PointerArith => ("ellipse", "salmon", "filled"),
+        NodeType::Cast => ("ellipse", "cyan", "filled"),
+        NodeType::UnsafeCast => ("octagon", "orangered", "filled"),
+        NodeType::StructAccess => ("ellipse", "pink", "filled"),
+        NodeType::ArrayAccess => ("ellipse", "yellow", "filled"),
+        NodeType::OutOfBounds => ("octagon", "crimson", "filled"),
+        NodeType::SizeofPointer => ("octagon", "darkorange", "filled"),
+        NodeType::SizeMisuse => ("octagon", "gold", "filled"),
+        NodeType::AllocationOverflow => ("octagon", "tomato", "filled"),
+        NodeType::Label => ("cds", "lightgrey", "filled"),
+        NodeType::Goto => ("rarrow", "khaki", "filled"),
+        NodeType::EnumDecl => ("folder", "wheat", "filled"),
+        NodeType::EnumConstant => ("note", "wheat", "filled"),
+        NodeType::Class => ("component", "lightsteelblue", "filled"),
+        NodeType::Namespace => ("tab", "lightsteelblue", "filled"),
+        NodeType::TranslationUnit => ("house", "lightgrey", "filled"),
+    }
+}
 
 pub fn format_graph_as_dot(graph: &DiGraph<Node, Edge>) -> String {
+    write_dot(graph, true, &Theme::default(), RankDir::default())
+}
+
+// DOT output with every node at the top level, as it was before function clusters
+pub fn format_graph_as_flat_dot(graph: &DiGraph<Node, Edge>) -> String {
+    write_dot(graph, false, &Theme::default(), RankDir::default())
+}
+
+// DOT output with the theme's shapes, colors and styles in place of the built-in ones, laid out
+// in the given direction
+pub fn format_graph_as_themed_dot(graph: &DiGraph<Node, Edge>, theme: &Theme, clusters: bool, rankdir: RankDir) -> String {
+    write_dot(graph, clusters, theme, rankdir)
+}
+
+fn write_dot(graph: &DiGraph<Node, Edge>, clusters: bool, theme: &Theme, rankdir: RankDir) -> String {
     let mut output = String::from("digraph {\n");
 
     // Add global styling
-    output.push_str("    graph [fontname=\"Arial\", rankdir=TB, splines=true];\n");
+    output.push_str(&format!("    graph [fontname=\"Arial\", rankdir={}, splines=true];\n", rankdir.as_str()));
     output.push_str("    node [fontname=\"Arial\"];\n");
     output.push_str("    edge [fontname=\"Arial\"];\n\n");
 
-    // Add nodes with different shapes based on type
-    for node_idx in graph.node_indices() {
-        let node = &graph[node_idx];
-        let node_id = node_idx.index();
-
-        // Determine shape and color based on node type
-        let (shape, color, style) = match node.kind {
-            NodeType::UnsafeCall => ("ellipse", "red", "filled"),
-            NodeType::Call => ("ellipse", "purple", "filled"),
-            NodeType::Main => ("ellipse", "green", "filled"),
-            NodeType::Function => ("ellipse", "lightblue", "filled"),
-            NodeType::BasicBlock => ("box", "red", "filled,rounded"),
-            NodeType::Parameter => ("ellipse", "orange", "filled"),
-            NodeType::BufferParameter => ("ellipse", "blue", "filled"),
-            NodeType::Variable => ("ellipse", "green", "filled"),
-            NodeType::Pointer => ("ellipse", "darkblue", "filled"),
-            NodeType::Array => ("ellipse", "lightyellow", "filled"),
-            NodeType::IfStatement => ("diamond", "indigo", "filled"),
-            NodeType::ForLoop => ("box", "lightblue", "filled,rounded"),
-            NodeType::WhileLoop => ("box", "lightblue", "filled,rounded"),
-            NodeType::Assignment => ("ellipse", "grey", "filled"),
-            NodeType::MemoryOp => ("ellipse", "violet", "filled"),
-            NodeType::Dereference => ("ellipse", "darkred", "filled"),
-            NodeType::AddressOf => ("ellipse", "lightgreen", "filled"),
-            NodeType::Cast => ("ellipse", "cyan", "filled"),
-            NodeType::StructAccess => ("ellipse", "pink", "filled"),
-            NodeType::ArrayAccess => ("ellipse", "yellow", "filled"),
-        };
+    // Each function's statements are grouped in a cluster; edges are written afterwards at the
+    // top level so calls and other cross-function edges can leave their cluster
+    let mut cluster_of: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    let functions: Vec<NodeIndex> = if clusters {
+        graph.node_indices()
+            .filter(|&idx| graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    for &func_idx in &functions {
+        for node_idx in function_subgraph(graph, func_idx) {
+            cluster_of.entry(node_idx).or_insert(func_idx);
+        }
+    }
 
-        // Add type information if available
-        let label = if let Some(ref type_info) = node.type_info {
-            format!("{} [{}]", node.name, type_info)
-        } else {
-            node.name.clone()
-        };
+    for &func_idx in &functions {
+        let mut members: Vec<NodeIndex> = cluster_of.iter()
+            .filter(|&(_, &owner)| owner == func_idx)
+            .map(|(&node_idx, _)| node_idx)
+            .collect();
+        members.sort();
 
-        output.push_str(&format!(
-            "    {} [label=\"{}\", shape={}, fillcolor=\"{}\", style=\"{}\"];\n",
-            node_id, label, shape, color, style
-        ));
+        output.push_str(&format!("    subgraph cluster_{} {{\n", func_idx.index()));
+        output.push_str(&format!("        label=\"{}\";\n", escape_dot_label(&graph[func_idx].name)));
+        for node_idx in members {
+            output.push_str(&format!("        {}\n", dot_node_statement(node_idx, &graph[node_idx], theme)));
+        }
+        output.push_str("    }\n");
+    }
+
+    // Everything outside a function, or every node when clustering is off
+    for node_idx in graph.node_indices().filter(|idx| !cluster_of.contains_key(idx)) {
+        output.push_str(&format!("    {}\n", dot_node_statement(node_idx, &graph[node_idx], theme)));
     }
 
     // Add edges with labels
@@ -60,49 +247,121 @@ pub fn format_graph_as_dot(graph: &DiGraph<Node, Edge>) -> String {
         let target_id = target.index();
         let edge = &graph[edge_idx];
 
-        // Edge label based on type
-        let label = match edge.kind {
-            EdgeType::Calls => "calls",
-            EdgeType::Contains => "contains",
-            EdgeType::Uses => "uses",
-            EdgeType::Defines => "defines",
-            EdgeType::References => "references",
-            EdgeType::Assigns => "assigns",
-            EdgeType::Points => "points_to",
-            EdgeType::Casts => "casts",
-            EdgeType::Accesses => "accesses",
-            EdgeType::Allocates => "allocates",
-            EdgeType::Frees => "frees",
-            EdgeType::Controls => "controls",
-        };
+        let (label, color, weight) = edge_style(&edge.kind);
+        let themed = theme.edge_style(&edge.kind);
+        let color = escape_dot_label(themed.and_then(|t| t.color.as_deref()).unwrap_or(color));
+        let style = themed.and_then(|t| t.style.as_deref());
 
-        // Edge color based on type
-        let color = match edge.kind {
-            EdgeType::Calls => "blue",
-            EdgeType::Contains => "gray",
-            EdgeType::Uses => "green",
-            EdgeType::Defines => "purple",
-            EdgeType::References => "darkblue",
-            EdgeType::Assigns => "black",
-            EdgeType::Points => "darkorange",
-            EdgeType::Casts => "cyan",
-            EdgeType::Accesses => "pink",
-            EdgeType::Allocates => "darkgreen",
-            EdgeType::Frees => "red",
-            EdgeType::Controls => "red",
+        // Calls resolved through a function pointer are drawn dashed
+        let mut attributes = if edge.indirect {
+            format!(
+                "label=\"{} (indirect)\", color=\"{}\", style=\"{}\"",
+                label, color, escape_dot_label(style.unwrap_or("dashed"))
+            )
+        } else if let Some(style) = style {
+            format!("label=\"{}\", color=\"{}\", style=\"{}\"", label, color, escape_dot_label(style))
+        } else {
+            format!("label=\"{}\", color=\"{}\"", label, color)
         };
 
-        output.push_str(&format!(
-            "    {} -> {} [label=\"{}\", color=\"{}\"];\n",
-            source_id, target_id, label, color
-        ));
+        // dot only accepts whole-number weights; the line width keeps the exact value
+        attributes.push_str(&format!(", weight={}, penwidth={}", weight.round(), weight));
+        output.push_str(&format!("    {} -> {} [{}];\n", source_id, target_id, attributes));
     }
 
     output.push_str("}\n");
     output
 }
 
+// JSON output schema, as loaded by vis.html. Fields are declared in alphabetical order, the order
+// the output has always had, so existing consumers see byte-identical files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonGraph {
+    pub edges: Vec<JsonEdge>,
+    pub nodes: Vec<JsonNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonNode {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub array_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complexity: Option<u32>, // Functions only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<usize>, // Last line, for nodes that span a range
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub external: bool, // Calls a function declared but not defined in the input
+    pub group: String,
+    pub id: String,    // `<kind>_<index>`, referenced by edges
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_const: bool, // Pointer to const
+    pub label: String, // Node name, followed by `[type]` when the type is known
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub library: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pointer_depth: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>, // Functions only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<usize>, // First line, set alongside `end`
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub tainted: bool, // Holds outside input
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonEdge {
+    pub color: String,
+    pub dashes: bool, // Set for calls resolved through a function pointer
+    pub from: String,
+    pub label: String,
+    pub to: String,
+    pub weight: f64,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn is_zero(value: &u8) -> bool {
+    *value == 0
+}
+
 pub fn format_graph_as_json(graph: &DiGraph<Node, Edge>) -> String {
+    serde_json::to_string_pretty(&graph_to_json(graph)).unwrap()
+}
+
+// Same document as `format_graph_as_json` on a single line, for tools that don't read it by eye
+pub fn format_graph_as_compact_json(graph: &DiGraph<Node, Edge>) -> String {
+    serde_json::to_string(&graph_to_json(graph)).unwrap()
+}
+
+// Self-contained page that draws the JSON graph with vis-network, loaded from a CDN, plus a
+// legend of the node groups present and their fill colors
+pub fn format_graph_as_html(graph: &DiGraph<Node, Edge>) -> String {
+    let mut colors: BTreeMap<&str, &str> = BTreeMap::new();
+    for node in graph.node_weights() {
+        colors.insert(node_type_to_group(&node.kind), node_kind_style(&node.kind).1);
+    }
+
+    let legend: String = colors.iter()
+        .map(|(group, color)| format!(
+            "        <div class=\"legend-item\"><span class=\"legend-swatch\" style=\"background-color: {}\"></span>{}</div>\n",
+            color, group
+        ))
+        .collect();
+
+    // A label holding `</script>` must not end the script block early
+    let graph_json = serde_json::to_string(&graph_to_json(graph)).unwrap().replace("</", "<\\/");
+    let colors_json = serde_json::to_string(&colors).unwrap();
+
+    // The graph goes in last so text inside a label is never taken for a placeholder
+    include_str!("graph.html")
+        .replace("/*LEGEND*/", &legend)
+        .replace("/*COLORS*/", &colors_json)
+        .replace("/*GRAPH*/", &graph_json)
+}
+
+pub fn graph_to_json(graph: &DiGraph<Node, Edge>) -> JsonGraph {
     let mut nodes = Vec::new();
     let mut edges = Vec::new();
     let mut node_id_map: HashMap<NodeIndex, String> = HashMap::new();
@@ -110,31 +369,13 @@ pub fn format_graph_as_json(graph: &DiGraph<Node, Edge>) -> String {
     // Process nodes
     for node_idx in graph.node_indices() {
         let node = &graph[node_idx];
-        let node_id = format!("{}_{}", node_type_to_prefix(&node.kind), node_idx.index());
+        let node_id = format!("{}_{}", node.kind, node_idx.index());
         node_id_map.insert(node_idx, node_id.clone());
 
-        // Map node type to group
-        let group = match node.kind {
-            NodeType::Function => "function",
-            NodeType::Main => "main_function",
-            NodeType::Variable => "variable",
-            NodeType::Parameter => "param",
-            NodeType::BufferParameter => "buffer_param",
-            NodeType::Pointer => "pointer",
-            NodeType::Array => "array",
-            NodeType::Call => "call",
-            NodeType::UnsafeCall => "unsafe_call",
-            NodeType::BasicBlock => "basic",
-            NodeType::IfStatement => "if_statement",
-            NodeType::ForLoop => "for_loop",
-            NodeType::WhileLoop => "while_loop",
-            NodeType::Assignment => "assignment",
-            NodeType::MemoryOp => "memory_op",
-            NodeType::Dereference => "dereference",
-            NodeType::AddressOf => "address_of",
-            NodeType::Cast => "cast",
-            NodeType::StructAccess => "struct_access",
-            NodeType::ArrayAccess => "array_access",
+        let group = if node.library && node.kind == NodeType::Call {
+            "library_call"
+        } else {
+            node_type_to_group(&node.kind)
         };
 
         // Add type information if available
@@ -144,11 +385,21 @@ pub fn format_graph_as_json(graph: &DiGraph<Node, Edge>) -> String {
             node.name.clone()
         };
 
-        nodes.push(json!({
-            "id": node_id,
-            "label": label,
-            "group": group
-        }));
+        nodes.push(JsonNode {
+            array_size: node.array_size,
+            complexity: node.complexity,
+            end: node.end_line,
+            external: node.external,
+            group: group.to_string(),
+            id: node_id,
+            is_const: node.is_const,
+            label,
+            library: node.library,
+            pointer_depth: node.pointer_depth,
+            signature: node.signature.clone(),
+            start: node.end_line.and(node.line),
+            tainted: node.tainted,
+        });
     }
 
     // Process edges
@@ -158,63 +409,205 @@ pub fn format_graph_as_json(graph: &DiGraph<Node, Edge>) -> String {
         let target_id = node_id_map.get(&target).unwrap();
         let edge = &graph[edge_idx];
 
-        // Map edge type to label, color, and weight
-        let (label, color, weight) = match edge.kind {
-            EdgeType::Calls => ("calls", "blue", 2.0),
-            EdgeType::Contains => ("contains", "gray", 1.0),
-            EdgeType::Uses => ("uses", "green", 2.0),
-            EdgeType::References => ("references", "darkblue", 2.0),
-            EdgeType::Assigns => ("assigns", "black", 1.5),
-            EdgeType::Points => ("points_to", "darkorange", 2.0),
-            EdgeType::Casts => ("casts", "cyan", 1.5),
-            EdgeType::Accesses => ("accesses", "pink", 1.5),
-            EdgeType::Allocates => ("allocates", "darkgreen", 2.0),
-            EdgeType::Frees => ("frees", "red", 2.0),
-            EdgeType::Controls => ("controls", "red", 3.0),
-            EdgeType::Defines => ("defines", "purple", 2.0),
-        };
+        let (label, color, weight) = edge_style(&edge.kind);
 
-        edges.push(json!({
-            "from": source_id,
-            "to": target_id,
-            "label": label,
-            "weight": weight,
-            "color": color,
-            "dashes": false
-        }));
+        edges.push(JsonEdge {
+            color: color.to_string(),
+            dashes: edge.indirect,
+            from: source_id.clone(),
+            label: label.to_string(),
+            to: target_id.clone(),
+            weight,
+        });
     }
 
-    // Build final JSON object
-    let result = json!({
-        "nodes": nodes,
-        "edges": edges
-    });
+    JsonGraph { edges, nodes }
+}
 
-    serde_json::to_string_pretty(&result).unwrap()
+// Label, color and weight for each edge kind, shared by the DOT and JSON formatters so both
+// draw a relationship the same way. Heavier edges matter more to the layout.
+fn edge_style(kind: &EdgeType) -> (&'static str, &'static str, f64) {
+    let (color, weight) = match kind {
+        EdgeType::Calls => ("blue", 2.0),
+        EdgeType::Contains => ("gray", 1.0),
+        EdgeType::Uses => ("green", 2.0),
+        EdgeType::References => ("darkblue", 2.0),
+        EdgeType::Assigns => ("black", 1.5),
+        EdgeType::Writes => ("firebrick", 1.5),
+        EdgeType::Reads => ("seagreen", 1.5),
+        EdgeType::Points => ("darkorange", 2.0),
+        EdgeType::Casts => ("cyan", 1.5),
+        EdgeType::Accesses => ("pink", 1.5),
+        EdgeType::Allocates => ("darkgreen", 2.0),
+        EdgeType::Frees => ("red", 2.0),
+        EdgeType::Controls => ("red", 3.0),
+        EdgeType::Defines => ("purple", 2.0),
+        EdgeType::Jumps => ("brown", 2.0),
+        EdgeType::Shadows => ("goldenrod", 1.5),
+        EdgeType::Returns => ("mediumorchid", 2.0),
+    };
+    (kind.as_str(), color, weight)
 }
 
-// Helper function to map node types to ID prefixes
-fn node_type_to_prefix(node_type: &NodeType) -> &'static str {
+// Map node types to JSON groups. vis.html styles a few groups under their older short names,
+// every other group is the canonical type name.
+fn node_type_to_group(node_type: &NodeType) -> &'static str {
     match node_type {
-        NodeType::Function => "func",
-        NodeType::Main => "main",
-        NodeType::Variable => "var",
+        NodeType::Main => "main_function",
         NodeType::Parameter => "param",
-        NodeType::BufferParameter => "buffer",
-        NodeType::Pointer => "ptr",
-        NodeType::Array => "array",
-        NodeType::Call => "call",
-        NodeType::UnsafeCall => "unsafe",
-        NodeType::BasicBlock => "block",
-        NodeType::IfStatement => "if",
-        NodeType::ForLoop => "for",
-        NodeType::WhileLoop => "while",
-        NodeType::Assignment => "assign",
-        NodeType::MemoryOp => "memop",
-        NodeType::Dereference => "deref",
-        NodeType::AddressOf => "addrof",
-        NodeType::Cast => "cast",
-        NodeType::StructAccess => "struct",
-        NodeType::ArrayAccess => "arr_acc",
+        NodeType::BufferParameter => "buffer_param",
+        NodeType::BasicBlock => "basic",
+        _ => node_type.as_str(),
     }
 }
+
+// Quote a CSV field when it holds a delimiter, quote or line break, doubling inner quotes
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Node and edge lists as two CSV documents, laid out for LOAD CSV and spreadsheet import.
+// Node ids are graph indices, which the edge list refers to; absent values are left empty.
+pub fn format_graph_as_csv(graph: &DiGraph<Node, Edge>) -> (String, String) {
+    let mut nodes = String::from("id,kind,name,line,type_info\n");
+    for node_idx in graph.node_indices() {
+        let node = &graph[node_idx];
+        nodes.push_str(&format!(
+            "{},{},{},{},{}\n",
+            node_idx.index(),
+            node.kind,
+            escape_csv_field(&node.name),
+            node.line.map(|line| line.to_string()).unwrap_or_default(),
+            escape_csv_field(node.type_info.as_deref().unwrap_or_default())
+        ));
+    }
+
+    let mut edges = String::from("source,target,kind\n");
+    for edge in graph.raw_edges() {
+        edges.push_str(&format!(
+            "{},{},{}\n",
+            edge.source().index(),
+            edge.target().index(),
+            edge.weight.kind
+        ));
+    }
+
+    (nodes, edges)
+}
+
+// One line per edge, such as `main --calls--> helper`, sorted so the dump diffs cleanly between
+// runs. Nodes appear by name rather than index; names used by more than one node get their line
+// (`Call: helper @L12`). Nodes without any edge are listed on their own.
+pub fn format_graph_as_text(graph: &DiGraph<Node, Edge>) -> String {
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for node in graph.node_weights() {
+        *name_counts.entry(node.name.as_str()).or_insert(0) += 1;
+    }
+    let display = |node_idx: NodeIndex| {
+        let node = &graph[node_idx];
+        let name = node.name.replace('\n', "\\n").replace('\r', "\\r");
+        match node.line {
+            Some(line) if name_counts[node.name.as_str()] > 1 => format!("{} @L{}", name, line),
+            _ => name,
+        }
+    };
+
+    let mut lines: Vec<String> = graph.edge_references()
+        .map(|edge| {
+            let indirect = if edge.weight().indirect { " (indirect)" } else { "" };
+            format!("{} --{}{}--> {}", display(edge.source()), edge.weight().kind, indirect, display(edge.target()))
+        })
+        .collect();
+    lines.extend(graph.node_indices()
+        .filter(|&idx| graph.neighbors_undirected(idx).next().is_none())
+        .map(display));
+    lines.sort();
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Nearest function or block holding a node, found by walking up `Contains` edges
+fn enclosing_function(graph: &DiGraph<Node, Edge>, node_idx: NodeIndex) -> Option<NodeIndex> {
+    let mut current = node_idx;
+    // Bounded by the node count, so a malformed graph with a `Contains` cycle can't hang the walk
+    for _ in 0..=graph.node_count() {
+        if matches!(graph[current].kind, NodeType::Function | NodeType::Main) {
+            return Some(current);
+        }
+        current = graph.edges_directed(current, Direction::Incoming)
+            .find(|edge| edge.weight().kind == EdgeType::Contains)?
+            .source();
+    }
+    None
+}
+
+fn line_label(line: Option<usize>) -> String {
+    line.map_or_else(|| "line ?".to_string(), |line| format!("line {}", line))
+}
+
+// Text report for review: every function with a body in line order, its unsafe calls and memory
+// operations, then the findings raised inside it. Findings outside any function, such as a
+// shadowed global, come last.
+//
+//     copy_name (line 4)
+//         line 6: Unsafe: strcpy
+//         issues:
+//             line 6: unsafe-call: call to unsafe function 'strcpy'
+pub fn render_report(graph: &DiGraph<Node, Edge>, findings: &[Finding]) -> String {
+    let mut by_function: HashMap<Option<NodeIndex>, Vec<&Finding>> = HashMap::new();
+    for finding in findings {
+        let owner = finding.nodes.first()
+            .and_then(|&idx| enclosing_function(graph, NodeIndex::new(idx)));
+        by_function.entry(owner).or_default().push(finding);
+    }
+
+    let mut functions: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&idx| matches!(graph[idx].kind, NodeType::Function | NodeType::Main))
+        .filter(|&idx| graph.edges(idx).any(|edge| edge.weight().kind == EdgeType::Contains))
+        .collect();
+    functions.sort_by_key(|&idx| (graph[idx].line, idx));
+
+    let write_issues = |report: &mut String, issues: Option<&Vec<&Finding>>| {
+        let Some(issues) = issues else {
+            return;
+        };
+        report.push_str("    issues:\n");
+        for finding in issues {
+            report.push_str(&format!("        {}: {}: {}\n", line_label(finding.line), finding.rule, finding.message));
+        }
+    };
+
+    let mut report = String::new();
+    for func_idx in functions {
+        report.push_str(&format!("{} ({})\n", graph[func_idx].name, line_label(graph[func_idx].line)));
+
+        // Nested functions get their own entry. Allocations in an initializer hang off the
+        // variable rather than the block.
+        let mut operations: Vec<NodeIndex> = function_subgraph(graph, func_idx).into_iter()
+            .filter(|&idx| enclosing_function(graph, idx) == Some(func_idx))
+            .flat_map(|idx| graph.edges(idx)
+                .filter(|edge| edge.weight().kind == EdgeType::Allocates)
+                .map(|edge| edge.target())
+                .chain(std::iter::once(idx)))
+            .filter(|&idx| matches!(graph[idx].kind, NodeType::UnsafeCall | NodeType::MemoryOp))
+            .collect();
+        operations.sort_by_key(|&idx| (graph[idx].line, idx));
+        operations.dedup();
+        for op_idx in operations {
+            report.push_str(&format!("    {}: {}\n", line_label(graph[op_idx].line), graph[op_idx].name));
+        }
+
+        write_issues(&mut report, by_function.get(&Some(func_idx)));
+    }
+
+    if by_function.contains_key(&None) {
+        report.push_str("Outside functions\n");
+        write_issues(&mut report, by_function.get(&None));
+    }
+
+    report
+}
@@ -1,120 +1,505 @@
 use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use anyhow::{anyhow, Context, Result};
 use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
 use petgraph::visit::EdgeRef;
 use clang::{Entity, EntityKind};
+use clang::Unsaved;
+use clang::diagnostic::Severity;
+use log::{debug, log, log_enabled, trace, warn, Level};
 
+use crate::analysis::{cyclomatic_complexity, function_subgraph, tainted_variables};
+use crate::callbacks::default_callback_registrars;
+use crate::symbols::SymbolTable;
 use crate::types::{Node, Edge, NodeType, EdgeType};
 use crate::utils::*;
 use crate::processors::*;
 use crate::processors_ext::*;
 
+// Settings that control how a source file is turned into a graph
+#[derive(Debug, Clone)]
+pub struct AnalysisOptions {
+    pub memory_tracking: bool,  // Advanced memory tracking
+    pub show_diagnostics: bool, // Log clang's parse diagnostics as warnings and errors rather than at debug level
+    pub fail_on_error: bool,    // Refuse to build a graph when clang reports any error, fatal or not
+    pub max_depth: Option<usize>, // Statement nesting below a function body to expand; None is unlimited
+    pub max_nodes: Option<usize>, // Stop expanding statements once the graph holds this many nodes
+    pub keep_stdlib: bool,      // Recover standard library calls found by the source scan
+    pub fallback_regex: bool,   // Scan the raw source for calls and callback handlers the AST pass missed
+    pub callback_registrars: Vec<(String, usize)>, // Functions taking a callback, with its argument index
+    pub include_system: bool,   // Analyze declarations from system headers instead of skipping them
+    pub progress: Option<Instant>, // Report each phase on stderr with the time elapsed since this instant
+    pub clang_args: Vec<String>,
+}
+
+impl AnalysisOptions {
+    // Arguments that parse the input as C++ of the given standard (such as `c++17`) instead of
+    // C11, with the same include paths
+    pub fn cpp_clang_args(standard: &str) -> Vec<String> {
+        vec![
+            "-Wall".to_string(),
+            "-I/usr/include".to_string(),
+            "-I/usr/local/include".to_string(),
+            format!("-std={}", standard),
+            "-x".to_string(), "c++".to_string(),
+        ]
+    }
+}
+
+// Whether a file's extension marks it as C++ source or a C++ header
+pub fn is_cpp_source(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("cpp" | "cc" | "cxx" | "c++" | "hpp" | "hh" | "hxx")
+    )
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions {
+            memory_tracking: false,
+            show_diagnostics: false,
+            fail_on_error: false,
+            max_depth: None,
+            max_nodes: None,
+            keep_stdlib: false,
+            fallback_regex: true,
+            callback_registrars: default_callback_registrars(),
+            include_system: false,
+            progress: None,
+            clang_args: vec![
+                "-Wall".to_string(),
+                "-I/usr/include".to_string(),
+                "-I/usr/local/include".to_string(),
+                "-std=c11".to_string(),         // Specify language standard
+                "-x".to_string(), "c".to_string(), // Force C language
+            ],
+        }
+    }
+}
+
+// Parse a C file and run every analysis pass over it: function discovery, AST processing and
+// call reconnection. Only one clang instance may exist per process, so callers running this from
+// several threads must serialize the calls.
+pub fn build_graph(path: &Path, options: &AnalysisOptions) -> Result<DiGraph<Node, Edge>> {
+    build_graph_from_files(&[path.to_path_buf()], options)
+}
+
+// Like `build_graph`, but for several files that make up one program. Each file is parsed as its
+// own translation unit and gets its own root, while functions are merged by name, so a call in
+// one file links to the definition in another.
+pub fn build_graph_from_files(paths: &[PathBuf], options: &AnalysisOptions) -> Result<DiGraph<Node, Edge>> {
+    // Read the content of the C files
+    let mut contents = Vec::new();
+    for path in paths {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file: {:?}", path))?;
+        contents.push(content);
+    }
+
+    let sources: Vec<(&Path, &str)> = paths.iter()
+        .map(PathBuf::as_path)
+        .zip(contents.iter().map(String::as_str))
+        .collect();
+    build_graph_from_sources(&sources, options)
+}
+
+// Like `build_graph`, but for source that is already in memory, such as an editor buffer or
+// stdin. `path` names the buffer in diagnostics and doesn't need to exist; relative includes
+// are resolved from its directory.
+pub fn build_graph_from_source(path: &Path, content: &str, options: &AnalysisOptions) -> Result<DiGraph<Node, Edge>> {
+    build_graph_from_sources(&[(path, content)], options)
+}
+
+// Shared by the entry points above: every file is parsed before any is analyzed, and all of them
+// go through function discovery before any body is processed, so calls across files resolve
+fn build_graph_from_sources(sources: &[(&Path, &str)], options: &AnalysisOptions) -> Result<DiGraph<Node, Edge>> {
+    report_progress(options.progress, "parsing");
+    let clang = clang::Clang::new().map_err(|e| anyhow!("Failed to initialize clang: {}", e))?;
+    let index = clang::Index::new(&clang, true, true);
+    
+    let mut units = Vec::new();
+    for &(path, content) in sources {
+        // Parse with detailed options for deeper analysis. Clang reads the file from the
+        // in-memory copy, so it doesn't matter whether the path exists on disk.
+        let tu = index.parser(path)
+            .arguments(&options.clang_args)
+            .unsaved(&[Unsaved::new(path, content)])
+            .detailed_preprocessing_record(true)
+            .skip_function_bodies(false)
+            .parse()
+            .with_context(|| format!("Failed to parse C file with Clang: {:?}", path))?;
+
+        // Syntax errors and missing headers leave the AST, and so the graph, silently incomplete
+        let mut error_count = 0;
+        for diagnostic in tu.get_diagnostics() {
+            let severity = diagnostic.get_severity();
+            if severity >= Severity::Error {
+                error_count += 1;
+            }
+            
+            // Logged under the `clang` target, which the logger prints as clang formats it
+            let level = match severity {
+                _ if !options.show_diagnostics => Level::Debug,
+                Severity::Fatal | Severity::Error => Level::Error,
+                Severity::Warning => Level::Warn,
+                _ => Level::Info,
+            };
+            if log_enabled!(target: "clang", level) {
+                let location = diagnostic.get_location().get_file_location();
+                let file = location.file
+                    .map(|f| f.get_path().display().to_string())
+                    .unwrap_or_else(|| "<unknown>".to_string());
+                log!(
+                    target: "clang",
+                    level,
+                    "{}:{}:{}: {}: {}",
+                    file,
+                    location.line,
+                    location.column,
+                    format!("{:?}", severity).to_lowercase(),
+                    diagnostic.get_text()
+                );
+            }
+        }
+        
+        if options.fail_on_error && error_count > 0 {
+            return Err(anyhow!("clang reported {} error(s) while parsing {:?}", error_count, path));
+        }
+        
+        // Without the listing, still say that the graph is built from a damaged AST
+        if error_count > 0 && !(options.show_diagnostics || log_enabled!(target: "clang", Level::Debug)) {
+            warn!(
+                "clang reported {} error(s) while parsing {:?}; the graph may be incomplete (--show-diagnostics lists them)",
+                error_count,
+                path
+            );
+        }
+
+        units.push(tu);
+    }
+
+    // Extract function calls directly from the source code as a backup. Without the fallback,
+    // only calls the AST resolved get reconnected.
+    let mut function_calls = Vec::new();
+    let mut callback_registrations = Vec::new();
+    if options.fallback_regex {
+        for &(_, content) in sources {
+            function_calls.extend(extract_function_calls_from_source(content, options.keep_stdlib));
+            callback_registrations.extend(extract_callback_registrations(content, &options.callback_registrars));
+        }
+    }
+    if log_enabled!(Level::Debug) {
+        debug!("Extracted function calls from source:");
+        for (caller, callee) in &function_calls {
+            debug!("  {} calls {}", caller, callee);
+        }
+        debug!("Extracted callback registrations:");
+        for (caller, registrar, handler_func) in &callback_registrations {
+            debug!("  {} passes {} to {}", caller, handler_func, registrar);
+        }
+    }
+
+    let mut graph = DiGraph::<Node, Edge>::new();
+    let mut node_map = SymbolTable::new();
+    let mut usr_map: HashMap<String, NodeIndex> = HashMap::new();
+    
+    // Track pointer-target relationships for memory operations
+    let mut pointer_targets: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    
+    let mut processed_entities = HashSet::new();
+    
+    // Each file is a root, so every graph has a fixed node to start traversals from
+    let roots: Vec<NodeIndex> = sources.iter()
+        .map(|(path, _)| graph.add_node(Node::new(path.display().to_string(), NodeType::TranslationUnit, None)))
+        .collect();
+    
+    // First pass: identify all functions to ensure they're in the graph. A function declared
+    // in several files is defined by the first of them.
+    report_progress(options.progress, "finding functions");
+    for (tu, &root_idx) in units.iter().zip(&roots) {
+        find_all_functions(tu.get_entity(), &mut graph, &mut node_map, &mut usr_map, options.include_system);
+        link_top_level_definitions(root_idx, &mut graph);
+    }
+    
+    // Second pass: process the entire AST and build relationships
+    report_progress(options.progress, "analyzing");
+    for ((tu, &root_idx), &(_, content)) in units.iter().zip(&roots).zip(sources) {
+        let mut ctx = BuildCtx {
+            graph: &mut graph,
+            node_map: &mut node_map,
+            usr_map: &mut usr_map,
+            pointer_targets: &mut pointer_targets,
+            processed: &mut processed_entities,
+            content,
+            opts: options,
+            remaining_depth: options.max_depth,
+        };
+        analyze_program(tu.get_entity(), root_idx, &mut ctx);
+    }
+    
+    // The graph is still consistent when truncated, it just stops partway through the input
+    if let Some(limit) = options.max_nodes.filter(|&limit| graph.node_count() >= limit) {
+        let paths: Vec<String> = sources.iter().map(|(path, _)| format!("{:?}", path)).collect();
+        warn!(
+            "graph truncated at {} nodes (limit {}); the output covers only part of {}",
+            graph.node_count(),
+            limit,
+            paths.join(", ")
+        );
+    }
+    
+    // Post-process: ensure connections are properly established
+    report_progress(options.progress, "fixing calls");
+    fix_disconnected_calls(&mut graph, &node_map, &usr_map, &function_calls, &callback_registrations, options.keep_stdlib);
+    link_returned_values(&mut graph);
+    
+    // Measured on the full graph so the value survives later pruning or summarizing. Prototypes
+    // without a body in these files contain nothing and get no value.
+    let functions: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main)
+        .filter(|&idx| graph.edges(idx).any(|edge| edge.weight().kind == EdgeType::Contains))
+        .collect();
+    for func_idx in functions {
+        graph[func_idx].complexity = Some(cyclomatic_complexity(&graph, func_idx));
+    }
+    
+    // Likewise marked on the full graph, where every assignment that spreads the input is still present
+    for var_idx in tainted_variables(&graph).into_keys() {
+        graph[var_idx].tainted = true;
+    }
+
+    Ok(graph)
+}
+
 pub fn find_all_functions(
     entity: Entity,
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
+    node_map: &mut SymbolTable,
     usr_map: &mut HashMap<String, NodeIndex>,
+    include_system: bool,
 ) {
-    // Skip system headers
-    if is_system_entity(&entity) {
+    // Skip system headers unless they were asked for
+    if !include_system && is_system_entity(&entity) {
         return;
     }
     
     match entity.get_kind() {
-        EntityKind::FunctionDecl => {
-            if let Some(name) = entity.get_name() {
+        _ if is_function_entity(&entity) => {
+            if let Some(name) = qualified_function_name(&entity) {
                 let is_main = name == "main";
                 let usr = format!("{:?}", entity.get_usr());
                 
                 // Get function return type
                 let return_type = entity.get_type()
-                    .map(|t| t.get_result_type())
-                    .flatten()
+                    .and_then(|t| t.get_result_type())
                     .map(|t| t.get_display_name())
                     .unwrap_or_else(|| "void".to_string());
                 
-                // Create function node if not already in the map
-                if !node_map.contains_key(&name) {
+                let node_idx = if let Some(&node_idx) = node_map.get(&name) {
+                    // A prototype seen first already created the node; the definition takes it over
+                    // so the node points at the body rather than the declaration
+                    if entity.is_definition() {
+                        graph[node_idx].line = get_line_number(&entity);
+                        graph[node_idx].end_line = get_end_line(&entity);
+                        graph[node_idx].usr = Some(usr.clone());
+                        usr_map.insert(usr, node_idx);
+                    }
+                    node_idx
+                } else {
                     let node_type = if is_main { NodeType::Main } else { NodeType::Function };
                     let line = get_line_number(&entity);
                     
+                    // A prototype's extent is only its declaration, so it gets no range
+                    let end_line = if entity.is_definition() { get_end_line(&entity) } else { None };
+                    
                     let node_idx = graph.add_node(Node {
                         name: name.clone(),
                         kind: node_type,
                         line,
+                        end_line,
                         usr: Some(usr.clone()),
                         type_info: Some(return_type),
+                        library: is_system_entity(&entity),
+                        ..Node::default()
                     });
                     
-                    node_map.insert(name.clone(), node_idx);
+                    node_map.insert_global(name.clone(), node_idx);
                     
                     // Store USR for precise matching
                     if !usr.is_empty() {
                         usr_map.insert(usr, node_idx);
                     }
-                }
+                    node_idx
+                };
+                
+                link_to_enclosing_scope(&entity, node_idx, graph, usr_map);
+            }
+        },
+        EntityKind::Namespace | EntityKind::ClassDecl => {
+            // Anonymous namespaces and classes get no node, but their members are still found
+            if let Some(name) = qualified_function_name(&entity) {
+                let usr = format!("{:?}", entity.get_usr());
+                
+                // A namespace can be reopened and a class forward-declared; both keep one node,
+                // placed at the class definition
+                let node_idx = if let Some(&node_idx) = usr_map.get(&usr) {
+                    if entity.is_definition() && entity.get_kind() == EntityKind::ClassDecl {
+                        graph[node_idx].line = get_line_number(&entity);
+                    }
+                    node_idx
+                } else {
+                    let kind = if entity.get_kind() == EntityKind::Namespace { NodeType::Namespace } else { NodeType::Class };
+                    let node_idx = graph.add_node(Node {
+                        name,
+                        kind,
+                        line: get_line_number(&entity),
+                        usr: Some(usr.clone()),
+                        library: is_system_entity(&entity),
+                        ..Node::default()
+                    });
+                    usr_map.insert(usr, node_idx);
+                    node_idx
+                };
+                
+                link_to_enclosing_scope(&entity, node_idx, graph, usr_map);
+            }
+            
+            for child in entity.get_children() {
+                find_all_functions(child, graph, node_map, usr_map, include_system);
             }
         },
         _ => {
             // Recursively process children
             for child in entity.get_children() {
-                find_all_functions(child, graph, node_map, usr_map);
+                find_all_functions(child, graph, node_map, usr_map, include_system);
             }
         }
     }
 }
 
-pub fn analyze_program(
-    entity: Entity,
+// Add a `Defines` edge from the class or namespace that declares `entity` to its node. Parents
+// are visited first, so their nodes already exist; an out-of-line method definition links to
+// the same class as its declaration did.
+fn link_to_enclosing_scope(
+    entity: &Entity,
+    node_idx: NodeIndex,
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    usr_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    processed: &mut HashSet<String>,
-    content: &str,
-    debug: bool,
-    memory_tracking: bool,
+    usr_map: &HashMap<String, NodeIndex>,
 ) {
-    // Skip system headers and already processed entities
-    if is_system_entity(&entity) {
+    let scope_idx = entity.get_semantic_parent()
+        .filter(|parent| matches!(parent.get_kind(), EntityKind::Namespace | EntityKind::ClassDecl))
+        .and_then(|parent| usr_map.get(&format!("{:?}", parent.get_usr())).copied())
+        .filter(|&scope_idx| !graph.contains_edge(scope_idx, node_idx));
+    if let Some(scope_idx) = scope_idx {
+        graph.add_edge(scope_idx, node_idx, Edge { kind: EdgeType::Defines, indirect: false });
+    }
+}
+
+// Add a `Defines` edge from the translation unit to each function, class and namespace that no
+// class or namespace already defines
+fn link_top_level_definitions(root_idx: NodeIndex, graph: &mut DiGraph<Node, Edge>) {
+    let top_level: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&idx| matches!(graph[idx].kind, NodeType::Function | NodeType::Main | NodeType::Class | NodeType::Namespace))
+        .filter(|&idx| !graph.edges_directed(idx, Direction::Incoming).any(|edge| edge.weight().kind == EdgeType::Defines))
+        .collect();
+    for node_idx in top_level {
+        graph.add_edge(root_idx, node_idx, Edge { kind: EdgeType::Defines, indirect: false });
+    }
+}
+
+// Carry returned values across calls: for `x = foo(...)` or `T *x = foo(...)`, `x` gets an
+// indirect `Uses` edge to every variable `foo` `Returns`, so data flow into `x` continues inside
+// `foo`. Runs on the whole graph once calls are resolved, since the callee may be defined after
+// the caller or in another file. The call hangs off the assignment or, in a declaration, off the
+// variable itself.
+fn link_returned_values(graph: &mut DiGraph<Node, Edge>) {
+    let mut links = Vec::new();
+    
+    for holder_idx in graph.node_indices() {
+        let targets: Vec<NodeIndex> = if graph[holder_idx].kind == NodeType::Assignment {
+            graph.edges(holder_idx)
+                .filter(|edge| edge.weight().kind == EdgeType::Writes)
+                .map(|edge| edge.target())
+                .collect()
+        } else if matches!(graph[holder_idx].kind, NodeType::Variable | NodeType::Pointer | NodeType::Array | NodeType::BufferParameter) {
+            vec![holder_idx]
+        } else {
+            continue;
+        };
+        let calls = graph.edges(holder_idx)
+            .filter(|edge| edge.weight().kind == EdgeType::Contains)
+            .flat_map(|edge| graph.edges(edge.target()))
+            .filter(|edge| edge.weight().kind == EdgeType::Calls);
+        for call in calls {
+            let returned = graph.edges(call.target())
+                .filter(|edge| edge.weight().kind == EdgeType::Returns)
+                .map(|edge| edge.target());
+            for value_idx in returned {
+                links.extend(targets.iter().map(|&target_idx| (target_idx, value_idx)));
+            }
+        }
+    }
+    
+    for (target_idx, value_idx) in links {
+        let already_linked = graph.edges_connecting(target_idx, value_idx)
+            .any(|edge| edge.weight().kind == EdgeType::Uses && edge.weight().indirect);
+        if !already_linked {
+            graph.add_edge(target_idx, value_idx, Edge { kind: EdgeType::Uses, indirect: true });
+        }
+    }
+}
+
+// Walk declarations outside function bodies. Variables found here are globals, which `root_idx`,
+// the translation unit, defines.
+pub fn analyze_program(entity: Entity, root_idx: NodeIndex, ctx: &mut BuildCtx) {
+    // Skip system headers and already processed entities, and everything once the graph is full
+    if (!ctx.opts.include_system && is_system_entity(&entity)) || node_limit_reached(ctx.graph, ctx.opts.max_nodes) {
         return;
     }
     
     let entity_id = get_entity_id(&entity);
-    if processed.contains(&entity_id) {
+    if ctx.processed.contains(&entity_id) {
         return;
     }
     
-    processed.insert(entity_id);
+    ctx.processed.insert(entity_id);
     
     // Debug output
-    if debug {
-        if let Some(name) = entity.get_name() {
-            println!("Processing entity: {} ({:?})", name, entity.get_kind());
-        } else {
-            println!("Processing entity: {:?}", entity.get_kind());
-        }
+    if let Some(name) = entity.get_name() {
+        trace!("Processing entity: {} ({:?})", name, entity.get_kind());
+    } else {
+        trace!("Processing entity: {:?}", entity.get_kind());
     }
     
     match entity.get_kind() {
-        EntityKind::FunctionDecl => {
-            process_function(entity, graph, node_map, usr_map, pointer_targets, processed, content, debug, memory_tracking);
+        _ if is_function_entity(&entity) => {
+            process_function(entity, ctx);
         },
         EntityKind::VarDecl => {
-            process_variable_decl(entity, graph, node_map, pointer_targets, debug);
+            if let Some(var_idx) = process_variable_decl(entity, ctx) {
+                ctx.graph.add_edge(root_idx, var_idx, Edge { kind: EdgeType::Defines, indirect: false });
+            }
+        },
+        EntityKind::EnumDecl if entity.is_definition() => {
+            process_enum_decl(entity, ctx.graph, ctx.node_map);
         },
         EntityKind::IfStmt => {
-            process_if_statement(entity, graph, node_map, usr_map, pointer_targets, processed, content, debug, memory_tracking);
+            process_if_statement(entity, ctx);
         },
         EntityKind::ForStmt => {
-            process_loop(entity, graph, node_map, usr_map, pointer_targets, processed, content, NodeType::ForLoop, debug, memory_tracking);
+            process_loop(entity, NodeType::ForLoop, ctx);
         },
         EntityKind::WhileStmt => {
-            process_loop(entity, graph, node_map, usr_map, pointer_targets, processed, content, NodeType::WhileLoop, debug, memory_tracking);
+            process_loop(entity, NodeType::WhileLoop, ctx);
         },
         _ => {
             // Recursively process children
             for child in entity.get_children() {
-                analyze_program(child, graph, node_map, usr_map, pointer_targets, processed, content, debug, memory_tracking);
+                analyze_program(child, root_idx, ctx);
             }
         }
     }
@@ -123,10 +508,11 @@ pub fn analyze_program(
 // Fix any disconnected calls by checking call nodes that should be connected to functions
 pub fn fix_disconnected_calls(
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &HashMap<String, NodeIndex>,
-    _usr_map: &HashMap<String, NodeIndex>,
+    node_map: &SymbolTable,
+    usr_map: &HashMap<String, NodeIndex>,
     extracted_calls: &[(String, String)],
-    pthread_assignments: &[(String, String)],
+    callback_registrations: &[(String, String, String)],
+    keep_stdlib: bool,
 ) {
     let mut new_edges = Vec::new();
     
@@ -148,9 +534,17 @@ pub fn fix_disconnected_calls(
                 .any(|edge| graph[edge.id()].kind == EdgeType::Calls);
             
             if !already_connected {
-                // Try to find the function this call should connect to
-                if let Some(&func_idx) = node_map.get(&function_name) {
-                    new_edges.push((node_idx, func_idx));
+                // A call clang resolved names its callee by USR, which keeps a static function from
+                // matching a same-named one in another file. Only calls without one go by name.
+                let callee = match node.usr.as_deref() {
+                    Some(usr) if !usr.is_empty() && usr != "None" => usr_map.get(usr),
+                    _ => node_map.get(&function_name),
+                };
+                if let Some(&func_idx) = callee {
+                    // Calls through function pointers are resolved during processing, never by name
+                    if graph[func_idx].kind == NodeType::Function || graph[func_idx].kind == NodeType::Main {
+                        new_edges.push((node_idx, func_idx));
+                    }
                 }
             }
         }
@@ -188,13 +582,26 @@ pub fn fix_disconnected_calls(
     
     // For each extracted call, make sure there's a corresponding edge
     for (caller, callee) in extracted_calls {
-        // Skip standard library functions
-        if is_standard_library_function(callee) {
+        // Skip standard library functions unless asked to keep them
+        if !keep_library_call(callee, keep_stdlib) {
+            continue;
+        }
+        
+        // Library functions have no node of their own, so only make sure the caller shows the call
+        if is_standard_library_function(callee) && node_map.get(callee).is_none() {
+            if let (Some(&caller_idx), Some(&caller_block)) = (node_map.get(caller), caller_to_node.get(caller)) {
+                add_missing_library_call(graph, caller_idx, caller_block, callee);
+            }
             continue;
         }
         
         // Get the function and basic block nodes
         if let (Some(&func_idx), Some(&caller_block)) = (node_map.get(callee), caller_to_node.get(caller)) {
+            // A callee that names a variable is a function pointer, resolved during processing
+            if graph[func_idx].kind != NodeType::Function && graph[func_idx].kind != NodeType::Main {
+                continue;
+            }
+            
             // Check if there's already a call to this function from this caller
             let has_call = graph.edges(caller_block)
                 .any(|edge| {
@@ -222,73 +629,35 @@ pub fn fix_disconnected_calls(
                 let node_type = if is_unsafe { NodeType::UnsafeCall } else { NodeType::Call };
                 let call_label = if is_unsafe { format!("Unsafe: {}", callee) } else { format!("Call: {}", callee) };
                 
-                let call_idx = graph.add_node(Node {
-                    name: call_label,
-                    kind: node_type,
-                    line: None,
-                    usr: None,
-                    type_info: None,
-                });
+                let call_idx = graph.add_node(Node::new(call_label, node_type, None));
                 
                 // Connect everything
                 graph.add_edge(
                     caller_block,
                     call_idx,
-                    Edge { kind: EdgeType::Contains },
+                    Edge { kind: EdgeType::Contains, indirect: false },
                 );
                 
                 graph.add_edge(
                     call_idx,
                     func_idx,
-                    Edge { kind: EdgeType::Calls },
+                    Edge { kind: EdgeType::Calls, indirect: false },
                 );
             }
         }
     }
     
-    // Add pthread function references
-    for (caller, handler_func) in pthread_assignments {
+    // Add references for handlers passed to callback registrars such as pthread_create or qsort
+    for (caller, registrar, handler_func) in callback_registrations {
         if let (Some(&caller_idx), Some(&handler_idx)) = (node_map.get(caller), node_map.get(handler_func)) {
-            // Find if there's already a relationship
-            let already_connected = graph.edges(caller_idx)
-                .flat_map(|edge| {
-                    if graph[edge.id()].kind == EdgeType::Contains {
-                        let target = edge.target();
-                        if graph[target].kind == NodeType::BasicBlock {
-                            // Check all children of this basic block
-                            graph.edges(target)
-                                .filter_map(|bb_edge| {
-                                    if graph[bb_edge.id()].kind == EdgeType::Contains {
-                                        let call_node = bb_edge.target();
-                                        if graph[call_node].name == "Call: pthread_create" {
-                                            // Check if this call references the handler
-                                            graph.edges(call_node)
-                                                .filter_map(|call_edge| {
-                                                    if graph[call_edge.id()].kind == EdgeType::References &&
-                                                       call_edge.target() == handler_idx {
-                                                        Some(true)
-                                                    } else {
-                                                        None
-                                                    }
-                                                })
-                                                .next()
-                                        } else {
-                                            None
-                                        }
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .next()
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .next()
-                .is_some();
+            // The AST pass usually linked the registering call to the handler already
+            let call_label = format!("Call: {}", registrar);
+            let already_connected = function_subgraph(graph, caller_idx).into_iter()
+                .filter(|&node_idx| graph[node_idx].name == call_label)
+                .any(|call_idx| {
+                    graph.edges(call_idx)
+                        .any(|edge| edge.weight().kind == EdgeType::References && edge.target() == handler_idx)
+                });
             
             if !already_connected {
                 // Find the basic block for the caller
@@ -308,27 +677,26 @@ pub fn fix_disconnected_calls(
                     .collect();
                 
                 if let Some(&bb_idx) = basic_blocks.first() {
-                    // Create a new node to represent the pthread_create call
-                    let pthread_idx = graph.add_node(Node {
-                        name: format!("Call: pthread_create"),
+                    // Create a new node to represent the registering call
+                    let registrar_idx = graph.add_node(Node {
+                        name: call_label,
                         kind: NodeType::Call,
-                        line: None,
-                        usr: None,
-                        type_info: None,
+                        library: true,
+                        ..Node::default()
                     });
                     
                     // Connect the call to the basic block
                     graph.add_edge(
                         bb_idx,
-                        pthread_idx,
-                        Edge { kind: EdgeType::Contains },
+                        registrar_idx,
+                        Edge { kind: EdgeType::Contains, indirect: false },
                     );
                     
-                    // Create a References edge from pthread_create to the handler function
+                    // Create a References edge from the registering call to the handler function
                     graph.add_edge(
-                        pthread_idx,
+                        registrar_idx,
                         handler_idx,
-                        Edge { kind: EdgeType::References },
+                        Edge { kind: EdgeType::References, indirect: false },
                     );
                 }
             }
@@ -340,7 +708,43 @@ pub fn fix_disconnected_calls(
         graph.add_edge(
             from,
             to,
-            Edge { kind: EdgeType::Calls },
+            Edge { kind: EdgeType::Calls, indirect: false },
         );
     }
-} 
\ No newline at end of file
+}
+
+// Add a library call node under `caller_block` unless the caller already makes that call somewhere
+fn add_missing_library_call(
+    graph: &mut DiGraph<Node, Edge>,
+    caller_idx: NodeIndex,
+    caller_block: NodeIndex,
+    callee: &str,
+) {
+    let labels = [
+        format!("Call: {}", callee),
+        format!("Unsafe: {}", callee),
+        format!("MemoryOp: {}", callee),
+        format!("Sink: {}", callee),
+        format!("FormatString: {}", callee),
+    ];
+    let already_present = function_subgraph(graph, caller_idx)
+        .into_iter()
+        .any(|idx| labels.contains(&graph[idx].name));
+    if already_present {
+        return;
+    }
+    
+    let is_unsafe = is_unsafe_function(callee);
+    let call_idx = graph.add_node(Node {
+        name: if is_unsafe { format!("Unsafe: {}", callee) } else { format!("Call: {}", callee) },
+        kind: if is_unsafe { NodeType::UnsafeCall } else { NodeType::Call },
+        library: true,
+        ..Node::default()
+    });
+    
+    graph.add_edge(
+        caller_block,
+        call_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+}
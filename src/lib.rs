@@ -1,6 +1,12 @@
 pub mod types;
+pub mod symbols;
 pub mod utils;
+pub mod callbacks;
+pub mod compile_commands;
 pub mod graph_builder;
 pub mod processors;
 pub mod processors_ext;
-pub mod formatters; 
\ No newline at end of file
+pub mod theme;
+pub mod formatters;
+pub mod analysis;
+pub mod transforms; 
\ No newline at end of file
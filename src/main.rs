@@ -1,132 +1,708 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Instant;
 
-use anyhow::{Context, Result};
-use cparser::formatters::{format_graph_as_dot, format_graph_as_json};
-use cparser::graph_builder::{analyze_program, find_all_functions, fix_disconnected_calls};
-use cparser::types::{Edge, Node};
-use cparser::utils::{extract_function_calls_from_source, extract_pthread_assignments};
+use anyhow::{anyhow, Context, Result};
+use cparser::analysis::{
+    arity_message, collect_findings, complexity_table, const_violation_message, diff_cpg, find_arity_mismatches,
+    find_clones, find_const_violations, find_dead_functions, find_format_string_risks, find_function, find_leaks,
+    find_realloc_self_assignments, find_recursion, find_shadowing, find_taint_flows, find_unreachable_functions_from,
+    graph_stats, leak_message, reachable_from, shadowing_message, taint_flow_message, write_findings_jsonl,
+};
+use cparser::callbacks::load_callback_registrars;
+use cparser::compile_commands::{find_compile_command, load_compile_commands};
+use cparser::formatters::{
+    dot_to_ascii, format_graph_as_compact_json, format_graph_as_csv, format_graph_as_html, format_graph_as_json, format_graph_as_text,
+    format_graph_as_themed_dot, json_to_ascii, render_report, OutputEncoding, RankDir,
+};
+use cparser::graph_builder::{build_graph, build_graph_from_files, build_graph_from_source, is_cpp_source, AnalysisOptions};
+use cparser::theme::Theme;
+use cparser::transforms::{deduplicate_edges, filter_kinds, prune_node_types, retain_nodes, subgraph_from_function, summarize};
+use cparser::types::{Edge, EdgeType, Node, NodeType};
+use cparser::utils::report_progress;
+use log::{info, warn, Level, LevelFilter, Metadata, Record};
 use petgraph::graph::{DiGraph, NodeIndex};
 use structopt::StructOpt;
 
+// Name given to source read from stdin in diagnostics and findings
+const STDIN_FILE_NAME: &str = "stdin.c";
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "c-code-analyzer", about = "Analyze C code and generate visualizations")]
 struct Opt {
-    /// Input C source file
-    #[structopt(parse(from_os_str))]
-    input: PathBuf,
+    /// Input C source files, analyzed together as one program, or - to read a single source from stdin
+    #[structopt(parse(from_os_str), required = true)]
+    input: Vec<PathBuf>,
 
     /// Output file
     #[structopt(parse(from_os_str), short, long)]
     output: Option<PathBuf>,
     
-    /// Output format (json or dot)
+    /// Write every output format and the findings into this directory, named after the input
+    /// (<stem>.dot, <stem>.json, <stem>.html, <stem>.txt, <stem>.nodes.csv, <stem>.edges.csv, <stem>.findings.jsonl)
+    #[structopt(parse(from_os_str), long, conflicts_with = "output")]
+    output_dir: Option<PathBuf>,
+    
+    /// Output format (json, dot, csv, text, html or svg). With --output, csv writes <output>.nodes.csv and <output>.edges.csv.
+    /// svg runs GraphViz's `dot`, which must be on PATH; without it, use dot or json and render them elsewhere
     #[structopt(short, long, default_value = "dot")]
     format: String,
     
-    /// Debug mode
-    #[structopt(short, long)]
-    debug: bool,
+    /// DOT layout direction (TB, LR, BT or RL)
+    #[structopt(long, default_value = "TB")]
+    rankdir: RankDir,
+    
+    /// Output character encoding (utf8 or ascii)
+    #[structopt(long, default_value = "utf8")]
+    output_encoding: OutputEncoding,
+    
+    /// Debug mode: log each processing step and clang diagnostic on stderr; repeat (-dd) to trace every entity and call
+    #[structopt(short, long, visible_alias = "verbose", parse(from_occurrences))]
+    debug: u8,
+    
+    /// Only log errors on stderr, leaving out warnings and status messages
+    #[structopt(short, long, conflicts_with_all = &["debug", "progress"])]
+    quiet: bool,
     
     /// Advanced memory tracking
     #[structopt(long)]
     memory_tracking: bool,
+    
+    /// Collapse statements nested deeper than this into a placeholder block
+    #[structopt(long)]
+    max_depth: Option<usize>,
+    
+    /// Stop adding nodes once the graph holds this many and emit the partial graph
+    #[structopt(long)]
+    max_nodes: Option<usize>,
+    
+    /// Recover standard library calls that only the source scan found (system/exec* are always kept)
+    #[structopt(long)]
+    keep_stdlib: bool,
+    
+    /// Only use calls found in the clang AST; skip the source-text scan for calls and callback handlers.
+    /// The scan can add spurious edges from macros or comments, but without it calls hidden behind
+    /// macros and some callbacks registered indirectly are missed
+    #[structopt(long, visible_alias = "no-source-fallback")]
+    no_fallback_regex: bool,
+    
+    /// Extra callback-registering functions, one `function argument_index` per line (e.g. `event_set 3`)
+    #[structopt(long, parse(from_os_str))]
+    callbacks: Option<PathBuf>,
+    
+    /// Also analyze declarations from system headers such as /usr/local/include
+    #[structopt(long)]
+    include_system: bool,
+    
+    /// Report each analysis phase and its elapsed time on stderr
+    #[structopt(long)]
+    progress: bool,
+    
+    /// Print clang's parse diagnostics (errors, warnings, missing headers)
+    #[structopt(long)]
+    show_diagnostics: bool,
+    
+    /// Exit with an error when clang reports any error, fatal or not, instead of emitting a partial graph
+    #[structopt(long, visible_aliases = &["werror", "fail-on-fatal"])]
+    fail_on_error: bool,
+    
+    /// Parse the input as C++ (implied for .cpp, .cc, .cxx and C++ header extensions)
+    #[structopt(long)]
+    cpp: bool,
+    
+    /// Language standard for C++ input
+    #[structopt(long, default_value = "c++17")]
+    cpp_std: String,
+    
+    /// Parse the input with the flags recorded for it in this compile_commands.json
+    #[structopt(parse(from_os_str), long)]
+    compile_commands: Option<PathBuf>,
+    
+    /// Print each function in line order with its unsafe calls, memory operations and findings instead of the graph
+    #[structopt(long)]
+    report: bool,
+    
+    /// Print each function's cyclomatic complexity, highest first, instead of the graph
+    #[structopt(long)]
+    complexity: bool,
+    
+    /// Print self-recursive and mutually recursive functions instead of the graph
+    #[structopt(long)]
+    recursion: bool,
+    
+    /// Print node and edge counts, call depth and components instead of the graph (as JSON with --format json)
+    #[structopt(long)]
+    stats: bool,
+    
+    /// List non-exported functions that nothing calls, then those main (and --entry, if given) never reaches,
+    /// with their lines, instead of the graph
+    #[structopt(long)]
+    dead_code: bool,
+    
+    /// Compare against this earlier version of the input and print the functions, calls, unsafe calls and
+    /// node and edge kinds it adds or removes instead of the graph (as JSON with --format json)
+    #[structopt(long, parse(from_os_str))]
+    diff: Option<PathBuf>,
+    
+    /// Only emit what this function reaches through calls, references and its own statements
+    #[structopt(long)]
+    entry: Option<String>,
+    
+    /// Only emit this function, its statements and what they call or use
+    #[structopt(long)]
+    function: Option<String>,
+    
+    /// Report structurally similar function pairs instead of the graph
+    #[structopt(long)]
+    find_clones: bool,
+    
+    /// Minimum similarity (0.0 - 1.0) for --find-clones to report a pair
+    #[structopt(long, default_value = "0.9")]
+    clone_threshold: f64,
+    
+    /// Only keep these node types (comma-separated or repeated, e.g. function,call,memory_op)
+    #[structopt(long, use_delimiter = true, visible_alias = "filter-node-kind")]
+    include_types: Vec<NodeType>,
+    
+    /// Remove these node types (comma-separated)
+    #[structopt(long, use_delimiter = true)]
+    exclude_types: Vec<NodeType>,
+    
+    /// Only emit edges of this kind; repeat for several
+    #[structopt(long, number_of_values = 1)]
+    filter_edge_kind: Vec<EdgeType>,
+    
+    /// Warn about local variables and parameters that shadow an outer declaration
+    #[structopt(long)]
+    detect_shadowing: bool,
+    
+    /// Warn about allocations a function neither frees nor hands on
+    #[structopt(long)]
+    detect_leaks: bool,
+    
+    /// Write every finding to this file as JSON Lines, one object per finding
+    #[structopt(long, parse(from_os_str))]
+    findings_jsonl: Option<PathBuf>,
+    
+    /// Collapse the graph to functions, calls and memory operations
+    #[structopt(long)]
+    summarize: bool,
+    
+    /// Keep repeated edges of the same kind between the same two nodes
+    #[structopt(long)]
+    keep_duplicate_edges: bool,
+    
+    /// Write JSON output on a single line instead of pretty-printing it
+    #[structopt(long)]
+    compact: bool,
+    
+    /// Draw DOT nodes flat instead of grouping each function's statements in a cluster
+    #[structopt(long)]
+    no_clusters: bool,
+    
+    /// TOML file overriding DOT shapes, colors and styles per kind, e.g. [nodes.unsafe_call] color = "#d55e00"
+    #[structopt(long, parse(from_os_str))]
+    theme: Option<PathBuf>,
+}
+
+// Error for a function name the graph doesn't have, listing the ones it does
+fn unknown_function(graph: &DiGraph<Node, Edge>, name: &str) -> anyhow::Error {
+    let mut known: Vec<&str> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::Function || graph[idx].kind == NodeType::Main)
+        .map(|idx| graph[idx].name.as_str())
+        .collect();
+    known.sort();
+    known.dedup();
+    anyhow!("No function named '{}'; known functions: {}", name, known.join(", "))
+}
+
+// Signed counts per kind, such as `+3 call, -1 unsafe_call`
+fn kind_changes_summary(changes: &[(String, i64)]) -> String {
+    changes.iter()
+        .map(|(kind, change)| format!("{:+} {}", change, kind))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Lay out DOT with GraphViz into SVG. `dot` reads the whole graph before writing anything, so
+// feeding it all of stdin first can't deadlock on its output.
+fn dot_to_svg(dot: &str) -> Result<String> {
+    let mut child = Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => anyhow!(
+                "--format svg needs GraphViz, but no `dot` was found on PATH; install GraphViz, or use --format dot or json and render the graph elsewhere"
+            ),
+            _ => anyhow!(e).context("Failed to run GraphViz `dot`"),
+        })?;
+
+    child.stdin.take().expect("stdin is piped")
+        .write_all(dot.as_bytes())
+        .context("Failed to send the graph to GraphViz `dot`")?;
+    let output = child.wait_with_output().context("Failed to run GraphViz `dot`")?;
+    if !output.status.success() {
+        return Err(anyhow!("GraphViz `dot` failed ({}): {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    String::from_utf8(output.stdout).context("GraphViz `dot` produced invalid UTF-8")
+}
+
+// The graph in one of the single-document formats, anything unknown being DOT, in the output
+// encoding asked for
+fn render_graph(graph: &DiGraph<Node, Edge>, format: &str, opt: &Opt, theme: &Theme) -> String {
+    let output = if format == "json" && opt.compact {
+        format_graph_as_compact_json(graph)
+    } else if format == "json" {
+        format_graph_as_json(graph)
+    } else if format == "text" {
+        format_graph_as_text(graph)
+    } else if format == "html" {
+        format_graph_as_html(graph)
+    } else {
+        format_graph_as_themed_dot(graph, theme, !opt.no_clusters, opt.rankdir)
+    };
+    
+    // Escape non-ASCII characters for consumers that can't handle UTF-8
+    match (opt.output_encoding, format) {
+        (OutputEncoding::Utf8, _) => output,
+        // The page embeds the graph as JSON and its other text is plain ASCII
+        (OutputEncoding::Ascii, "json" | "text" | "html") => json_to_ascii(&output),
+        (OutputEncoding::Ascii, _) => dot_to_ascii(&output),
+    }
+}
+
+// Writes log records to stderr, one line each. Warnings and errors get a prefix, except clang's
+// own diagnostics, which already name their severity.
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            _ if record.target() == "clang" => eprintln!("{}", record.args()),
+            Level::Error => eprintln!("error: {}", record.args()),
+            Level::Warn => eprintln!("warning: {}", record.args()),
+            _ => eprintln!("{}", record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+fn init_logger(opt: &Opt) {
+    let level = match opt.debug {
+        0 if opt.quiet => LevelFilter::Error,
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    // Only fails if a logger is already set, which main never does twice
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(level);
 }
 
 fn main() -> Result<()> {
     let opt = Opt::from_args();
+    init_logger(&opt);
 
-    // Read the content of the C file
-    let content = fs::read_to_string(&opt.input)
-        .with_context(|| format!("Failed to read file: {:?}", opt.input))?;
-
-    // Initialize Clang with more options for complete semantic analysis
-    let clang = clang::Clang::new().unwrap();
-    let index = clang::Index::new(&clang, true, true);
-    
-    // Use more clang options for better analysis
-    let clang_args = vec![
-        "-Wall".to_string(),
-        "-I/usr/include".to_string(),
-        "-I/usr/local/include".to_string(),
-        "-std=c11".to_string(),         // Specify language standard
-        "-x".to_string(), "c".to_string(), // Force C language
-    ];
-    
-    // Parse with detailed options for deeper analysis
-    let tu = index.parser(opt.input.to_str().unwrap())
-        .arguments(&clang_args)
-        .detailed_preprocessing_record(true)
-        .skip_function_bodies(false)
-        // .include_all_declarations(true)
-        // .visit_implicit_code(true)
-        .parse()
-        .with_context(|| "Failed to parse C file with Clang")?;
-
-    // Extract function calls directly from the source code as a backup
-    let function_calls = extract_function_calls_from_source(&content);
-    if opt.debug {
-        println!("Extracted function calls from source:");
-        for (caller, callee) in &function_calls {
-            println!("  {} calls {}", caller, callee);
-        }
-    }
-    
-    // Extract pthread function assignments
-    let pthread_assignments = extract_pthread_assignments(&content);
-    if opt.debug {
-        println!("Extracted pthread assignments:");
-        for (caller, handler_func) in &pthread_assignments {
-            println!("  {} assigns {} to pthread", caller, handler_func);
-        }
-    }
-
-    // Build our graph
-    let mut graph = DiGraph::<Node, Edge>::new();
-    let mut node_map: HashMap<String, NodeIndex> = HashMap::new();
-    let mut usr_map: HashMap<String, NodeIndex> = HashMap::new();
-    
-    // Track pointer-target relationships for memory operations
-    let mut pointer_targets: HashMap<NodeIndex, NodeIndex> = HashMap::new();
-    
-    let mut processed_entities = HashSet::new();
-    
-    // First pass: identify all functions to ensure they're in the graph
-    find_all_functions(tu.get_entity(), &mut graph, &mut node_map, &mut usr_map);
-    
-    // Second pass: process the entire AST and build relationships
-    analyze_program(
-        tu.get_entity(), 
-        &mut graph, 
-        &mut node_map, 
-        &mut usr_map,
-        &mut pointer_targets,
-        &mut processed_entities, 
-        &content, 
-        opt.debug,
-        opt.memory_tracking
-    );
-    
-    // Post-process: ensure connections are properly established
-    fix_disconnected_calls(&mut graph, &node_map, &usr_map, &function_calls, &pthread_assignments);
+    let mut options = AnalysisOptions {
+        memory_tracking: opt.memory_tracking,
+        show_diagnostics: opt.show_diagnostics,
+        fail_on_error: opt.fail_on_error,
+        max_depth: opt.max_depth,
+        max_nodes: opt.max_nodes,
+        keep_stdlib: opt.keep_stdlib,
+        fallback_regex: !opt.no_fallback_regex,
+        include_system: opt.include_system,
+        progress: opt.progress.then(Instant::now),
+        ..AnalysisOptions::default()
+    };
+    
+    // Read the theme up front so a broken file fails before the analysis runs
+    let theme = match opt.theme {
+        Some(ref theme_path) => Theme::load(theme_path)?,
+        None => Theme::default(),
+    };
+    
+    // User registrars extend the built-in ones
+    if let Some(ref callbacks_path) = opt.callbacks {
+        options.callback_registrars.extend(load_callback_registrars(callbacks_path)?);
+    }
+    
+    if opt.cpp || opt.input.iter().any(|path| is_cpp_source(path)) {
+        options.clang_args = AnalysisOptions::cpp_clang_args(&opt.cpp_std);
+    }
+    
+    // A compilation database replaces the default include paths and language flags. All inputs
+    // share one set of arguments, taken from the first input's entry.
+    if let Some(ref database_path) = opt.compile_commands {
+        let commands = load_compile_commands(database_path)?;
+        match find_compile_command(&commands, &opt.input[0]) {
+            Some(command) => options.clang_args = command.clang_args(),
+            None => warn!(
+                "{:?} has no entry in {:?}; using the default clang arguments",
+                opt.input[0], database_path
+            ),
+        }
+    }
+    
+    // Source piped in on stdin is parsed from memory under a placeholder file name
+    let reads_stdin = opt.input.iter().any(|path| path == Path::new("-"));
+    let (graph, input_name) = if reads_stdin && opt.input.len() > 1 {
+        return Err(anyhow!("- reads a single source from stdin and can't be combined with other inputs"));
+    } else if reads_stdin {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)
+            .with_context(|| "Failed to read C source from stdin")?;
+        (build_graph_from_source(Path::new(STDIN_FILE_NAME), &content, &options)?, STDIN_FILE_NAME.to_string())
+    } else {
+        // Nodes don't record which file they came from, so findings name all the inputs
+        let names: Vec<String> = opt.input.iter().map(|path| path.to_string_lossy().into_owned()).collect();
+        (build_graph_from_files(&opt.input, &options)?, names.join(", "))
+    };
+    
+    // Artifacts written to --output-dir are named after the (first) input
+    let stem = match opt.input[0].file_stem() {
+        Some(stem) if !reads_stdin => stem.to_string_lossy().into_owned(),
+        _ => Path::new(STDIN_FILE_NAME).file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+    };
+    if let Some(ref output_dir) = opt.output_dir {
+        fs::create_dir_all(output_dir)
+            .with_context(|| format!("Failed to create output directory: {:?}", output_dir))?;
+    }
+    
+    // Collapse edges recorded more than once by the different passes
+    let graph = if opt.keep_duplicate_edges { graph } else { deduplicate_edges(&graph) };
+    
+    // Warn about realloc calls that leak the original block when they fail
+    for (mem_op_idx, ptr_idx) in find_realloc_self_assignments(&graph) {
+        warn!(
+            "line {}: '{}' is overwritten by realloc's result; the original block leaks if realloc fails",
+            graph[mem_op_idx].line.unwrap_or(0),
+            graph[ptr_idx].name
+        );
+    }
+    
+    // Warn about printf-style calls that take their format from data
+    for (line, function_name) in find_format_string_risks(&graph) {
+        warn!(
+            "line {}: format string passed to '{}' is not a literal",
+            line.unwrap_or(0),
+            function_name
+        );
+    }
+    
+    // Warn about pointers to const that are written through or freed
+    for (op_idx, ptr_idx) in find_const_violations(&graph) {
+        warn!(
+            "line {}: {}",
+            graph[op_idx].line.unwrap_or(0),
+            const_violation_message(&graph, op_idx, ptr_idx)
+        );
+    }
+    
+    // Warn about outside input that reaches an unsafe call or a command
+    for (origin_idx, sink_idx, var_idx) in find_taint_flows(&graph) {
+        warn!(
+            "line {}: {}",
+            graph[sink_idx].line.unwrap_or(0),
+            taint_flow_message(&graph, origin_idx, sink_idx, var_idx)
+        );
+    }
+    
+    // Warn about calls that pass the wrong number of arguments
+    for (line, function_name, expected, actual) in find_arity_mismatches(&graph) {
+        warn!("line {}: {}", line.unwrap_or(0), arity_message(&function_name, expected, actual));
+    }
+    
+    // Warn about declarations that hide an outer variable or parameter
+    if opt.detect_shadowing {
+        for (inner_idx, outer_idx) in find_shadowing(&graph) {
+            warn!("{}", shadowing_message(&graph, inner_idx, outer_idx));
+        }
+    }
+    
+    // Warn about allocations a function neither frees nor hands on. The check doesn't follow
+    // paths, so it's only run when asked for.
+    if opt.detect_leaks {
+        for (mem_op_idx, ptr_idx) in find_leaks(&graph) {
+            warn!(
+                "line {}: {}",
+                graph[mem_op_idx].line.unwrap_or(0),
+                leak_message(&graph, mem_op_idx, ptr_idx)
+            );
+        }
+    }
+    
+    let findings_paths: Vec<PathBuf> = opt.findings_jsonl.iter().cloned()
+        .chain(opt.output_dir.iter().map(|output_dir| output_dir.join(format!("{}.findings.jsonl", stem))))
+        .collect();
+    if !findings_paths.is_empty() {
+        let findings = collect_findings(&graph, &input_name);
+        for findings_path in &findings_paths {
+            let mut file = fs::File::create(findings_path)
+                .with_context(|| format!("Failed to create findings file: {:?}", findings_path))?;
+            write_findings_jsonl(&findings, &mut file)
+                .with_context(|| format!("Failed to write findings to: {:?}", findings_path))?;
+        }
+    }
+    
+    // Clone detection reports function pairs in place of the graph
+    if opt.find_clones {
+        let clones = find_clones(&graph, opt.clone_threshold);
+        if clones.is_empty() {
+            println!("No likely clones found (threshold {:.2})", opt.clone_threshold);
+        }
+        for (a, b, score) in clones {
+            println!(
+                "Likely clones: {} (line {}) <-> {} (line {}), similarity {:.2}",
+                graph[a].name,
+                graph[a].line.unwrap_or(0),
+                graph[b].name,
+                graph[b].line.unwrap_or(0),
+                score
+            );
+        }
+        return Ok(());
+    }
+    
+    // The review report replaces the graph, covering the whole program like the findings
+    if opt.report {
+        print!("{}", render_report(&graph, &collect_findings(&graph, &input_name)));
+        return Ok(());
+    }
+    
+    // The complexity table replaces the graph as well
+    if opt.complexity {
+        for (func_idx, complexity) in complexity_table(&graph) {
+            println!(
+                "{:>5}  {} (line {})",
+                complexity,
+                graph[func_idx].name,
+                graph[func_idx].line.unwrap_or(0)
+            );
+        }
+        return Ok(());
+    }
+    
+    // The summary metrics replace it too, as JSON when that's the chosen format
+    if opt.stats {
+        let stats = graph_stats(&graph);
+        if opt.format == "json" {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            return Ok(());
+        }
+        for (section, kinds) in [("Nodes", &stats.node_kinds), ("Edges", &stats.edge_kinds)] {
+            let counts: Vec<String> = kinds.iter().map(|(kind, count)| format!("{} {}", count, kind)).collect();
+            println!("{}: {}", section, counts.join(", "));
+        }
+        println!("Total: {} nodes, {} edges", stats.nodes, stats.edges);
+        println!("Functions: {}", stats.functions);
+        println!("Unsafe calls: {}", stats.unsafe_calls);
+        println!("Max call depth: {}", stats.max_call_depth);
+        println!("Components: {}", stats.components);
+        return Ok(());
+    }
+    
+    // As does the list of recursive call cycles
+    if opt.recursion {
+        let cycles = find_recursion(&graph);
+        if cycles.is_empty() {
+            println!("No recursion found");
+        }
+        for cycle in cycles {
+            let names: Vec<String> = cycle.iter()
+                .map(|&idx| format!("{} (line {})", graph[idx].name, graph[idx].line.unwrap_or(0)))
+                .collect();
+            if names.len() == 1 {
+                println!("Recursive: {}", names[0]);
+            } else {
+                println!("Mutually recursive: {}", names.join(", "));
+            }
+        }
+        return Ok(());
+    }
+    
+    // So does the dead-code list: functions nothing calls, then those only dead code calls. An
+    // entry function counts as a root next to main.
+    if opt.dead_code {
+        let mut entries = vec!["main"];
+        entries.extend(opt.entry.as_deref());
+        let dead: Vec<NodeIndex> = find_dead_functions(&graph).into_iter()
+            .filter(|&idx| !entries.contains(&graph[idx].name.as_str()))
+            .collect();
+        let dead_names: HashSet<&str> = dead.iter().map(|&idx| graph[idx].name.as_str()).collect();
+        let unreachable: Vec<String> = find_unreachable_functions_from(&graph, &entries).into_iter()
+            .filter(|name| !dead_names.contains(name.as_str()))
+            .collect();
+        if dead.is_empty() && unreachable.is_empty() {
+            println!("No unreachable functions found");
+        }
+        for &func_idx in &dead {
+            println!("Uncalled: {} (line {})", graph[func_idx].name, graph[func_idx].line.unwrap_or(0));
+        }
+        for name in unreachable {
+            let line = find_function(&graph, &name).and_then(|func_idx| graph[func_idx].line);
+            println!("Unreachable: {} (line {})", name, line.unwrap_or(0));
+        }
+        return Ok(());
+    }
+    
+    // And so does the comparison with an earlier version, parsed with the same options
+    if let Some(ref old_path) = opt.diff {
+        let old_graph = build_graph(old_path, &options)?;
+        let old_graph = if opt.keep_duplicate_edges { old_graph } else { deduplicate_edges(&old_graph) };
+        let diff = diff_cpg(&old_graph, &graph);
+        if opt.format == "json" {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+            return Ok(());
+        }
+        if diff.is_empty() {
+            println!("No changes");
+        }
+        if !diff.node_kinds.is_empty() {
+            println!("Nodes: {}", kind_changes_summary(&diff.node_kinds));
+        }
+        if !diff.edge_kinds.is_empty() {
+            println!("Edges: {}", kind_changes_summary(&diff.edge_kinds));
+        }
+        for name in &diff.added_functions {
+            println!("Added function: {}", name);
+        }
+        for name in &diff.removed_functions {
+            println!("Removed function: {}", name);
+        }
+        for (caller, callee) in &diff.added_calls {
+            println!("Added call: {} -> {}", caller, callee);
+        }
+        for (caller, callee) in &diff.removed_calls {
+            println!("Removed call: {} -> {}", caller, callee);
+        }
+        for (function, call) in &diff.new_unsafe_calls {
+            println!("New unsafe call: {} in {}", call, function);
+        }
+        for (function, operation) in &diff.gained {
+            println!("Function {} gained {}", function, operation);
+        }
+        for (function, operation) in &diff.lost {
+            println!("Function {} lost {}", function, operation);
+        }
+        return Ok(());
+    }
+    
+    // Narrow the graph to what the entry function can reach
+    let graph = match opt.entry {
+        Some(ref entry) => {
+            let entry_idx = find_function(&graph, entry).ok_or_else(|| unknown_function(&graph, entry))?;
+            retain_nodes(&graph, &reachable_from(&graph, entry_idx))
+        },
+        None => graph,
+    };
+    
+    // Or to the one function asked for and what it calls and uses
+    let graph = match opt.function {
+        Some(ref name) if find_function(&graph, name).is_none() => return Err(unknown_function(&graph, name)),
+        Some(ref name) => subgraph_from_function(&graph, name),
+        None => graph,
+    };
+    
+    // Prune node types the user isn't interested in
+    let graph = if opt.include_types.is_empty() && opt.exclude_types.is_empty() {
+        graph
+    } else {
+        let mut keep: HashSet<NodeType> = if opt.include_types.is_empty() {
+            NodeType::ALL.iter().cloned().collect()
+        } else {
+            opt.include_types.iter().cloned().collect()
+        };
+        for kind in &opt.exclude_types {
+            keep.remove(kind);
+        }
+        prune_node_types(&graph, &keep)
+    };
+    
+    // Contract statement-level detail into a call-graph view
+    let graph = if opt.summarize { summarize(&graph) } else { graph };
+    
+    // Keep only the edge kinds asked for; node kinds were handled with the pruning above
+    let graph = if opt.filter_edge_kind.is_empty() {
+        graph
+    } else {
+        filter_kinds(&graph, &HashSet::new(), &opt.filter_edge_kind.iter().cloned().collect())
+    };
     
     // Generate the output based on selected format
-    let output = if opt.format == "json" {
-        format_graph_as_json(&graph)
+    report_progress(options.progress, "formatting");
+    
+    // One file per format, so a single run produces every artifact
+    if let Some(ref output_dir) = opt.output_dir {
+        let (nodes, edges) = format_graph_as_csv(&graph);
+        let artifacts = [
+            ("dot", render_graph(&graph, "dot", &opt, &theme)),
+            ("json", render_graph(&graph, "json", &opt, &theme)),
+            ("html", render_graph(&graph, "html", &opt, &theme)),
+            ("txt", render_graph(&graph, "text", &opt, &theme)),
+            ("nodes.csv", nodes),
+            ("edges.csv", edges),
+        ];
+        for (extension, content) in artifacts {
+            let path = output_dir.join(format!("{}.{}", stem, extension));
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write to file: {:?}", path))?;
+        }
+        info!("Graph and findings written to {:?}", output_dir);
+        report_progress(options.progress, "done");
+        return Ok(());
+    }
+    
+    // CSV is two documents, so it gets a file each rather than going through the single-output path
+    if opt.format == "csv" {
+        let (nodes, edges) = format_graph_as_csv(&graph);
+        if let Some(output_path) = opt.output {
+            let nodes_path = output_path.with_extension("nodes.csv");
+            let edges_path = output_path.with_extension("edges.csv");
+            fs::write(&nodes_path, nodes)
+                .with_context(|| format!("Failed to write to file: {:?}", nodes_path))?;
+            fs::write(&edges_path, edges)
+                .with_context(|| format!("Failed to write to file: {:?}", edges_path))?;
+            info!("Graph written to {:?} and {:?}", nodes_path, edges_path);
+        } else {
+            print!("{}\n{}", nodes, edges);
+        }
+        report_progress(options.progress, "done");
+        return Ok(());
+    }
+    
+    // SVG is DOT laid out by GraphViz
+    let output = if opt.format == "svg" {
+        dot_to_svg(&render_graph(&graph, "dot", &opt, &theme))?
     } else {
-        format_graph_as_dot(&graph)
+        render_graph(&graph, &opt.format, &opt, &theme)
     };
     
     // Write to file or stdout
     if let Some(output_path) = opt.output {
         fs::write(&output_path, output)
             .with_context(|| format!("Failed to write to file: {:?}", output_path))?;
-        println!("Graph written to {:?}", output_path);
+        info!("Graph written to {:?}", output_path);
     } else {
         println!("{}", output);
     }
+    report_progress(options.progress, "done");
 
     Ok(())
 }
\ No newline at end of file
@@ -1,284 +1,498 @@
 use std::collections::{HashMap, HashSet};
 use petgraph::graph::{DiGraph, NodeIndex};
-use clang::{Entity, EntityKind};
-use crate::processors_ext::{process_array_access, process_assignment_value, process_call_expression, process_function_pointer_references, process_if_statement, process_loop, process_member_access, process_unary_operator};
+use petgraph::visit::EdgeRef;
+use clang::{Entity, EntityKind, TypeKind};
+use log::debug;
+use crate::processors_ext::{add_pointer_arith, link_function_pointer, link_jumps, link_pointer_arith, link_realloc_source, link_returns, process_array_access, process_assignment_value, process_call_expression, process_cast_expression, process_goto_statement, process_if_statement, process_label_statement, process_loop, process_member_access, process_unary_operator};
+use crate::graph_builder::AnalysisOptions;
+use crate::symbols::SymbolTable;
 use crate::types::{Node, Edge, NodeType, EdgeType};
 use crate::utils::*;
 
-pub fn process_function(
-    entity: Entity,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    usr_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    processed: &mut HashSet<String>,
-    content: &str,
-    debug: bool,
-    memory_tracking: bool,
-) {
-    if let Some(name) = entity.get_name() {
+const DEPTH_LIMIT_LABEL: &str = "BasicBlock: ... (depth limit)";
+
+// Everything the processors share while walking one translation unit. `remaining_depth` counts
+// down as statements nest, starting from `--max-depth`.
+pub struct BuildCtx<'a> {
+    pub graph: &'a mut DiGraph<Node, Edge>,
+    pub node_map: &'a mut SymbolTable,
+    pub usr_map: &'a mut HashMap<String, NodeIndex>,
+    pub pointer_targets: &'a mut HashMap<NodeIndex, NodeIndex>,
+    pub processed: &'a mut HashSet<String>,
+    pub content: &'a str,
+    pub opts: &'a AnalysisOptions,
+    pub remaining_depth: Option<usize>,
+}
+
+pub fn process_function(entity: Entity, ctx: &mut BuildCtx) {
+    if let Some(name) = qualified_function_name(&entity) {
         let is_main = name == "main";
         let line = get_line_number(&entity);
         
         // Get function return type
         let return_type = entity.get_type()
-            .map(|t| t.get_result_type())
-            .flatten()
+            .and_then(|t| t.get_result_type())
             .map(|t| t.get_display_name())
             .unwrap_or_else(|| "void".to_string());
         
         // Get or create a node for this function
-        let node_idx = if let Some(&idx) = node_map.get(&name) {
+        let node_idx = if let Some(&idx) = ctx.node_map.get(&name) {
             idx
         } else {
             let node_type = if is_main { NodeType::Main } else { NodeType::Function };
             let usr = format!("{:?}", entity.get_usr());
             
-            let node_idx = graph.add_node(Node {
+            let node_idx = ctx.graph.add_node(Node {
                 name: name.clone(),
                 kind: node_type,
                 line,
+                end_line: if entity.is_definition() { get_end_line(&entity) } else { None },
                 usr: Some(usr.clone()),
                 type_info: Some(return_type),
+                library: is_system_entity(&entity),
+                ..Node::default()
             });
             
-            node_map.insert(name.clone(), node_idx);
+            ctx.node_map.insert_global(name.clone(), node_idx);
             
             // Store USR for precise matching
             if !usr.is_empty() {
-                usr_map.insert(usr, node_idx);
+                ctx.usr_map.insert(usr, node_idx);
             }
             
             node_idx
         };
         
-        // Process function parameters
-        for param in entity.get_arguments().unwrap_or_default() {
-            if let Some(param_name) = param.get_name() {
-                let param_type = param.get_type().unwrap().get_display_name();
-                let is_buffer = param_type.contains("char *") || param_type.contains("char*");
-                let is_pointer = param_type.contains('*');
-                
-                let node_type = if is_buffer { 
-                    NodeType::BufferParameter 
-                } else if is_pointer {
-                    NodeType::Pointer
-                } else { 
-                    NodeType::Parameter 
-                };
-                
-                let param_label = if is_buffer {
-                    format!("BufferParam: {} ({})", param_name, param_type)
-                } else if is_pointer {
-                    format!("Pointer: {} ({})", param_name, param_type)
-                } else {
-                    format!("Param: {} ({})", param_name, param_type)
-                };
-                
-                let param_idx = graph.add_node(Node {
-                    name: param_label,
-                    kind: node_type,
-                    line: get_line_number(&param),
-                    usr: None,
-                    type_info: Some(param_type),
-                });
-                
-                // Add edge from function to parameter
-                graph.add_edge(
-                    node_idx,
-                    param_idx,
-                    Edge { kind: EdgeType::Contains },
-                );
-                
-                // Store parameter in node map for later reference
-                node_map.insert(format!("{}_{}", name, param_name), param_idx);
-                node_map.insert(param_name, param_idx); // Also store just the name for local lookups
-            }
+        // A definition names its parameters even when an earlier prototype didn't
+        if entity.is_definition() || ctx.graph[node_idx].signature.is_none() {
+            ctx.graph[node_idx].signature = function_signature(&entity);
         }
         
-        // Process function body
-        if let Some(body) = entity.get_children().iter().find(|c| c.get_kind() == EntityKind::CompoundStmt) {
-            // Create a basic block for the function body
-            let bb_idx = graph.add_node(Node {
-                name: "BasicBlock: entry".to_string(),
-                kind: NodeType::BasicBlock,
-                line: get_line_number(body),
-                usr: None,
-                type_info: None,
+        process_function_body(entity, node_idx, entity.get_arguments().unwrap_or_default(), ctx);
+    }
+}
+
+// Declare the parameters of a function, nested function or block in a scope of their own and
+// process its body under `node_idx`
+fn process_function_body(entity: Entity, node_idx: NodeIndex, parameters: Vec<Entity>, ctx: &mut BuildCtx) {
+    // Parameters and locals are only visible inside this function
+    ctx.node_map.push_scope();
+    
+    // Process function parameters
+    for param in parameters {
+        if let Some(param_name) = param.get_name() {
+            let clang_type = param.get_type().unwrap();
+            let param_type = clang_type.get_display_name();
+            let pointer_depth = pointer_depth(&clang_type);
+            let is_buffer = is_char_buffer(&clang_type);
+            let is_pointer = pointer_depth > 0;
+            let marker = pointer_marker(pointer_depth);
+            
+            let node_type = if is_buffer { 
+                NodeType::BufferParameter 
+            } else if is_pointer {
+                NodeType::Pointer
+            } else { 
+                NodeType::Parameter 
+            };
+            
+            let param_label = if is_buffer {
+                format!("BufferParam{}: {} ({})", marker, param_name, param_type)
+            } else if is_pointer {
+                format!("Pointer{}: {} ({})", marker, param_name, param_type)
+            } else {
+                format!("Param: {} ({})", param_name, param_type)
+            };
+            
+            let param_idx = ctx.graph.add_node(Node {
+                name: param_label,
+                kind: node_type,
+                line: get_line_number(&param),
+                type_info: Some(param_type),
+                pointer_depth,
+                is_const: is_pointer && points_to_const(&clang_type),
+                ..Node::default()
             });
             
-            // Connect function to basic block
-            graph.add_edge(
+            // Add edge from function to parameter
+            ctx.graph.add_edge(
                 node_idx,
-                bb_idx,
-                Edge { kind: EdgeType::Contains },
+                param_idx,
+                Edge { kind: EdgeType::Contains, indirect: false },
             );
             
-            // Process body contents
-            for child in body.get_children() {
-                process_statement(
-                    child, 
-                    bb_idx, 
-                    graph, 
-                    node_map, 
-                    usr_map, 
-                    pointer_targets,
-                    processed, 
-                    content, 
-                    debug,
-                    memory_tracking
-                );
-            }
+            // Store parameter in the function's scope for later reference
+            link_shadowed(&param_name, param_idx, ctx.graph, ctx.node_map);
+            ctx.node_map.insert(param_name, param_idx);
         }
     }
+    
+    // Process function body
+    if let Some(body) = entity.get_children().iter().find(|c| c.get_kind() == EntityKind::CompoundStmt) {
+        // Create a basic block for the function body
+        let bb_idx = ctx.graph.add_node(Node {
+            name: "BasicBlock: entry".to_string(),
+            kind: NodeType::BasicBlock,
+            line: get_line_number(body),
+            end_line: get_end_line(body),
+            ..Node::default()
+        });
+        
+        // Connect function to basic block
+        ctx.graph.add_edge(
+            node_idx,
+            bb_idx,
+            Edge { kind: EdgeType::Contains, indirect: false },
+        );
+        
+        // Process body contents
+        for child in body.get_children() {
+            process_statement(child, bb_idx, ctx);
+        }
+        
+        // Gotos can only be resolved once every label in the body exists
+        link_jumps(node_idx, ctx.graph);
+        link_returns(node_idx, ctx.graph);
+    }
+    
+    ctx.node_map.pop_scope();
 }
 
-pub fn process_statement(
-    entity: Entity,
-    parent_idx: NodeIndex,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    usr_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    processed: &mut HashSet<String>,
-    content: &str,
-    debug: bool,
-    memory_tracking: bool,
-) {
+// A GCC nested function or a clang block literal (`^{ ... }`), inside the function that contains
+// it. It gets a function node of its own under `parent_idx`, so the calls in its body show up. A
+// nested function is declared in the enclosing scope, where calls to it resolve; a block has no
+// name and is called through the variable it is stored in.
+pub fn process_nested_function(entity: Entity, parent_idx: NodeIndex, ctx: &mut BuildCtx) -> NodeIndex {
+    let line = get_line_number(&entity);
+    let is_block = entity.get_kind() == EntityKind::BlockExpr;
+    let name = match entity.get_name().filter(|_| !is_block) {
+        Some(name) => name,
+        None => format!("<block at line {}>", line.unwrap_or(0)),
+    };
+    debug!("Processing nested function: {}", name);
+    
+    // A block's type is a pointer to its function type
+    let return_type = entity.get_type()
+        .map(|ty| ty.get_pointee_type().unwrap_or(ty))
+        .and_then(|ty| ty.get_result_type())
+        .map(|ty| ty.get_display_name());
+    let usr = if is_block { None } else { Some(format!("{:?}", entity.get_usr())) };
+    
+    let node_idx = ctx.graph.add_node(Node {
+        name: name.clone(),
+        kind: NodeType::Function,
+        line,
+        end_line: get_end_line(&entity),
+        usr: usr.clone(),
+        type_info: return_type,
+        signature: if is_block { None } else { function_signature(&entity) },
+        ..Node::default()
+    });
+    ctx.graph.add_edge(
+        parent_idx,
+        node_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+    
+    if let Some(usr) = usr {
+        ctx.node_map.insert(name, node_idx);
+        ctx.usr_map.insert(usr, node_idx);
+    }
+    
+    let parameters = entity.get_children().into_iter()
+        .filter(|child| child.get_kind() == EntityKind::ParmDecl)
+        .collect();
+    process_function_body(entity, node_idx, parameters, ctx);
+    
+    node_idx
+}
+
+pub fn process_statement(entity: Entity, parent_idx: NodeIndex, ctx: &mut BuildCtx) {
+    if node_limit_reached(ctx.graph, ctx.opts.max_nodes) {
+        return;
+    }
+    
+    // Past the depth limit, everything under this parent collapses into one placeholder block
+    let remaining_depth = ctx.remaining_depth;
+    if remaining_depth == Some(0) {
+        add_depth_limit_placeholder(&entity, parent_idx, ctx.graph);
+        return;
+    }
+    // Whatever this statement holds is one level deeper; the depth is restored for its siblings
+    ctx.remaining_depth = remaining_depth.map(|depth| depth - 1);
+    
     match entity.get_kind() {
         EntityKind::CallExpr => {
-            process_call_expression(entity, parent_idx, graph, node_map, usr_map, pointer_targets, debug, memory_tracking);
+            process_call_expression(entity, parent_idx, ctx.opts.memory_tracking, ctx);
+            
+            // Blocks passed as arguments, such as a completion handler
+            for block in block_literals(&entity) {
+                process_nested_function(block, parent_idx, ctx);
+            }
+        },
+        EntityKind::BlockExpr => {
+            process_nested_function(entity, parent_idx, ctx);
         },
         EntityKind::DeclStmt => {
-            // Handle local variable declarations
+            // Handle local variable, enum and nested function declarations
             for child in entity.get_children() {
                 if child.get_kind() == EntityKind::VarDecl {
-                    let var_idx = process_variable_decl(child, graph, node_map, pointer_targets, debug);
+                    let var_idx = process_variable_decl(child, ctx);
                     
                     if let Some(var_idx) = var_idx {
                         // Connect parent to variable
-                        graph.add_edge(
+                        ctx.graph.add_edge(
                             parent_idx,
                             var_idx,
-                            Edge { kind: EdgeType::Contains },
+                            Edge { kind: EdgeType::Contains, indirect: false },
                         );
+                        
+                        // `void (^done)(int) = ^(int status) { ... };` makes calls through `done` reach the block
+                        for block in block_literals(&child) {
+                            let block_idx = process_nested_function(block, parent_idx, ctx);
+                            link_function_pointer(var_idx, block_idx, ctx.graph, ctx.pointer_targets);
+                        }
                     }
+                } else if child.get_kind() == EntityKind::FunctionDecl && child.is_definition() {
+                    process_nested_function(child, parent_idx, ctx);
+                } else if child.get_kind() == EntityKind::EnumDecl {
+                    let enum_idx = process_enum_decl(child, ctx.graph, ctx.node_map);
+                    ctx.graph.add_edge(
+                        parent_idx,
+                        enum_idx,
+                        Edge { kind: EdgeType::Contains, indirect: false },
+                    );
                 }
             }
         },
         EntityKind::BinaryOperator => {
-            process_binary_operator(entity, parent_idx, graph, node_map, pointer_targets, debug);
+            process_binary_operator(entity, parent_idx, ctx);
+            
+            // `done = ^{ ... };` makes calls through `done` reach the block
+            let assigned = entity.get_children().first()
+                .filter(|_| get_binary_operator(&entity).as_deref() == Some("="))
+                .filter(|lhs| lhs.get_kind() == EntityKind::DeclRefExpr)
+                .and_then(|lhs| lhs.get_name())
+                .and_then(|name| ctx.node_map.get(&name).copied());
+            for block in block_literals(&entity) {
+                let block_idx = process_nested_function(block, parent_idx, ctx);
+                if let Some(var_idx) = assigned {
+                    link_function_pointer(var_idx, block_idx, ctx.graph, ctx.pointer_targets);
+                }
+            }
         },
         EntityKind::UnaryOperator => {
-            process_unary_operator(entity, parent_idx, graph, node_map, pointer_targets, debug);
+            process_unary_operator(entity, parent_idx, ctx);
         },
-        EntityKind::CompoundAssignOperator | EntityKind::CStyleCastExpr => {
-            process_binary_operator(entity, parent_idx, graph, node_map, pointer_targets, debug);
+        EntityKind::CompoundAssignOperator => {
+            process_binary_operator(entity, parent_idx, ctx);
+        },
+        EntityKind::CStyleCastExpr => {
+            process_cast_expression(entity, parent_idx, ctx);
         },
         EntityKind::IfStmt => {
-            let if_idx = process_if_statement(entity, graph, node_map, usr_map, pointer_targets, processed, content, debug, memory_tracking);
+            let if_idx = process_if_statement(entity, ctx);
             
             // Connect parent to if statement
             if let Some(idx) = if_idx {
-                graph.add_edge(
+                ctx.graph.add_edge(
                     parent_idx,
                     idx,
-                    Edge { kind: EdgeType::Contains },
+                    Edge { kind: EdgeType::Contains, indirect: false },
                 );
             }
         },
         EntityKind::ForStmt => {
-            let loop_idx = process_loop(entity, graph, node_map, usr_map, pointer_targets, processed, content, NodeType::ForLoop, debug, memory_tracking);
+            let loop_idx = process_loop(entity, NodeType::ForLoop, ctx);
             
             // Connect parent to for loop
             if let Some(idx) = loop_idx {
-                graph.add_edge(
+                ctx.graph.add_edge(
                     parent_idx,
                     idx,
-                    Edge { kind: EdgeType::Contains },
+                    Edge { kind: EdgeType::Contains, indirect: false },
                 );
             }
         },
         EntityKind::WhileStmt => {
-            let loop_idx = process_loop(entity, graph, node_map, usr_map, pointer_targets, processed, content, NodeType::WhileLoop, debug, memory_tracking);
+            let loop_idx = process_loop(entity, NodeType::WhileLoop, ctx);
             
             // Connect parent to while loop
             if let Some(idx) = loop_idx {
-                graph.add_edge(
+                ctx.graph.add_edge(
                     parent_idx,
                     idx,
-                    Edge { kind: EdgeType::Contains },
+                    Edge { kind: EdgeType::Contains, indirect: false },
                 );
             }
         },
+        EntityKind::LabelStmt => {
+            process_label_statement(entity, parent_idx, ctx);
+        },
+        EntityKind::GotoStmt => {
+            process_goto_statement(entity, parent_idx, ctx.graph);
+        },
         EntityKind::MemberRefExpr => {
-            process_member_access(entity, parent_idx, graph, node_map, pointer_targets, debug);
+            process_member_access(entity, parent_idx, ctx);
         },
         EntityKind::ArraySubscriptExpr => {
-            process_array_access(entity, parent_idx, graph, node_map, pointer_targets, debug);
+            process_array_access(entity, parent_idx, ctx);
         },
         EntityKind::CompoundStmt => {
-            // Process nested blocks
+            // Process nested blocks; their locals go out of scope at the closing brace
+            ctx.node_map.push_scope();
             for child in entity.get_children() {
-                process_statement(
-                    child, 
-                    parent_idx, 
-                    graph, 
-                    node_map, 
-                    usr_map, 
-                    pointer_targets,
-                    processed, 
-                    content, 
-                    debug,
-                    memory_tracking
-                );
+                process_statement(child, parent_idx, ctx);
             }
+            ctx.node_map.pop_scope();
         },
         EntityKind::DeclRefExpr => {
             // Handle variable references
-            if let Some(var_name) = entity.get_name() {
-                if let Some(&var_idx) = node_map.get(&var_name) {
-                    // Add an edge showing that this statement uses the variable
-                    graph.add_edge(
-                        parent_idx,
-                        var_idx,
-                        Edge { kind: EdgeType::Uses },
-                    );
-                }
+            if let Some(var_name) = entity.get_name()
+                && let Some(&var_idx) = ctx.node_map.get(&var_name) {
+                // Add an edge showing that this statement uses the variable
+                ctx.graph.add_edge(
+                    parent_idx,
+                    var_idx,
+                    Edge { kind: EdgeType::Uses, indirect: false },
+                );
             }
         },
+        EntityKind::ReturnStmt => {
+            // `return p;` hands `p` to the caller. The edge starts at the enclosing block until
+            // `link_returns` moves it onto the function.
+            let returned = entity.get_children().into_iter().next()
+                .map(unwrap_casts)
+                .filter(|value| value.get_kind() == EntityKind::DeclRefExpr)
+                .and_then(|value| value.get_name())
+                .and_then(|name| ctx.node_map.get(&name).copied())
+                .filter(|&value_idx| !matches!(ctx.graph[value_idx].kind, NodeType::Function | NodeType::Main | NodeType::EnumConstant));
+            if let Some(value_idx) = returned {
+                ctx.graph.add_edge(
+                    parent_idx,
+                    value_idx,
+                    Edge { kind: EdgeType::Returns, indirect: false },
+                );
+            }
+            
+            for child in entity.get_children() {
+                process_statement(child, parent_idx, ctx);
+            }
+        },
+        // `sizeof(x)` is a constant; `x` isn't read
+        _ if is_unevaluated(&entity) => {},
         _ => {
             // Process other statement types or recurse into children
             for child in entity.get_children() {
-                process_statement(
-                    child, 
-                    parent_idx, 
-                    graph, 
-                    node_map, 
-                    usr_map, 
-                    pointer_targets,
-                    processed, 
-                    content, 
-                    debug,
-                    memory_tracking
-                );
+                process_statement(child, parent_idx, ctx);
             }
         }
     }
+    
+    ctx.remaining_depth = remaining_depth;
+}
+
+// Stand in for statements cut off by `--max-depth`, shared by every sibling that hits the limit
+fn add_depth_limit_placeholder(entity: &Entity, parent_idx: NodeIndex, graph: &mut DiGraph<Node, Edge>) {
+    let already_added = graph.edges(parent_idx).any(|edge| {
+        edge.weight().kind == EdgeType::Contains && graph[edge.target()].name == DEPTH_LIMIT_LABEL
+    });
+    if already_added {
+        return;
+    }
+    
+    let placeholder_idx = graph.add_node(Node::new(
+        DEPTH_LIMIT_LABEL.to_string(),
+        NodeType::BasicBlock,
+        get_line_number(entity),
+    ));
+    
+    graph.add_edge(
+        parent_idx,
+        placeholder_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
 }
 
-pub fn process_variable_decl(
+// Once the graph reaches `--max-nodes`, statements are skipped so pathological inputs can't
+// exhaust memory. Only statements are checked: an expression being processed runs to completion,
+// so the graph may end a few nodes past the limit.
+pub fn node_limit_reached(graph: &DiGraph<Node, Edge>, max_nodes: Option<usize>) -> bool {
+    max_nodes.is_some_and(|limit| graph.node_count() >= limit)
+}
+
+// Add an enum node containing one node per enumerator. The enumerators are declared like
+// variables, so later references to them resolve through `node_map`.
+pub fn process_enum_decl(
     entity: Entity,
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    debug: bool,
-) -> Option<NodeIndex> {
+    node_map: &mut SymbolTable,
+) -> NodeIndex {
+    // Older libclang gives anonymous enums no name, newer ones spell out "(unnamed enum at ...)"
+    let enum_name = entity.get_name()
+        .filter(|name| !name.contains("(unnamed") && !name.contains("(anonymous"))
+        .unwrap_or_else(|| "(anonymous)".to_string());
+    debug!("Processing enum: {}", enum_name);
+    
+    let enum_idx = graph.add_node(Node {
+        name: format!("Enum: {}", enum_name),
+        kind: NodeType::EnumDecl,
+        line: get_line_number(&entity),
+        type_info: entity.get_enum_underlying_type().map(|t| t.get_display_name()),
+        ..Node::default()
+    });
+    
+    for constant in entity.get_children() {
+        if constant.get_kind() != EntityKind::EnumConstantDecl {
+            continue;
+        }
+        let Some(constant_name) = constant.get_name() else {
+            continue;
+        };
+        
+        let label = match constant.get_enum_constant_value() {
+            Some((value, _)) => format!("EnumConstant: {} = {}", constant_name, value),
+            None => format!("EnumConstant: {}", constant_name),
+        };
+        let constant_idx = graph.add_node(Node::new(label, NodeType::EnumConstant, get_line_number(&constant)));
+        
+        graph.add_edge(
+            enum_idx,
+            constant_idx,
+            Edge { kind: EdgeType::Contains, indirect: false },
+        );
+        
+        node_map.insert(constant_name, constant_idx);
+    }
+    
+    enum_idx
+}
+
+pub fn process_variable_decl(entity: Entity, ctx: &mut BuildCtx) -> Option<NodeIndex> {
     if let Some(name) = entity.get_name() {
-        let var_type = entity.get_type().unwrap().get_display_name();
-        let is_buffer = var_type.contains("char *") || var_type.contains("char*");
-        let is_pointer = var_type.contains('*');
-        let is_array = var_type.contains('[') && var_type.contains(']');
+        let clang_type = entity.get_type().unwrap();
+        let var_type = clang_type.get_display_name();
+        let pointer_depth = pointer_depth(&clang_type);
+        let marker = pointer_marker(pointer_depth);
+        
+        // The outermost type decides, so `char *names[4]` is an array (of pointers) and
+        // `int (*row)[10]` a pointer (to an array)
+        let (is_pointer, is_array) = match clang_type.get_canonical_type().get_kind() {
+            TypeKind::Pointer => (true, false),
+            TypeKind::ConstantArray
+            | TypeKind::IncompleteArray
+            | TypeKind::VariableArray
+            | TypeKind::DependentSizedArray => (false, true),
+            _ => (false, false),
+        };
+        let is_buffer = is_pointer && is_char_buffer(&clang_type);
+        
+        // Variable-length and incomplete arrays have no constant size
+        let array_size = if is_array {
+            entity.get_type().and_then(|t| t.get_canonical_type().get_size())
+        } else {
+            None
+        };
         
         let node_type = if is_buffer { 
             NodeType::BufferParameter 
@@ -291,24 +505,28 @@ pub fn process_variable_decl(
         };
         
         let var_label = if is_buffer {
-            format!("BufferParam: {} ({})", name, var_type)
+            format!("BufferParam{}: {} ({})", marker, name, var_type)
         } else if is_pointer {
-            format!("Pointer: {} ({})", name, var_type)
+            format!("Pointer{}: {} ({})", marker, name, var_type)
         } else if is_array {
             format!("Array: {} ({})", name, var_type)
         } else {
             format!("Var: {}", name)
         };
         
-        let var_idx = graph.add_node(Node {
+        let var_idx = ctx.graph.add_node(Node {
             name: var_label,
             kind: node_type,
             line: get_line_number(&entity),
-            usr: None,
             type_info: Some(var_type),
+            array_size,
+            pointer_depth,
+            is_const: (is_pointer || is_array) && points_to_const(&clang_type),
+            ..Node::default()
         });
         
-        node_map.insert(name, var_idx);
+        link_shadowed(&name, var_idx, ctx.graph, ctx.node_map);
+        ctx.node_map.insert(name, var_idx);
         
         // Check for initializer
         if let Some(init) = entity.get_children().iter().find(|c| 
@@ -317,10 +535,12 @@ pub fn process_variable_decl(
             c.get_kind() == EntityKind::UnaryOperator ||
             c.get_kind() == EntityKind::IntegerLiteral ||
             c.get_kind() == EntityKind::StringLiteral ||
-            c.get_kind() == EntityKind::DeclRefExpr) 
+            c.get_kind() == EntityKind::DeclRefExpr ||
+            c.get_kind() == EntityKind::InitListExpr ||
+            c.get_kind() == EntityKind::UnexposedExpr) 
         {
             // Process initializer
-            process_initializer(*init, var_idx, graph, node_map, pointer_targets, debug);
+            process_initializer(*init, var_idx, ctx);
         }
         
         return Some(var_idx);
@@ -328,65 +548,90 @@ pub fn process_variable_decl(
     None
 }
 
-pub fn process_initializer(
-    entity: Entity,
-    var_idx: NodeIndex,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    debug: bool,
-) {
+// Record that the declaration about to be added as `name` hides a variable or parameter from an
+// enclosing scope. Functions are left out, since a local named after a libc function is common.
+fn link_shadowed(name: &str, decl_idx: NodeIndex, graph: &mut DiGraph<Node, Edge>, node_map: &SymbolTable) {
+    let outer = node_map.shadowed(name)
+        .filter(|&idx| !matches!(graph[idx].kind, NodeType::Function | NodeType::Main));
+    if let Some(outer_idx) = outer {
+        graph.add_edge(decl_idx, outer_idx, Edge { kind: EdgeType::Shadows, indirect: false });
+    }
+}
+
+pub fn process_initializer(entity: Entity, var_idx: NodeIndex, ctx: &mut BuildCtx) {
+    link_pointer_arith(&entity, var_idx, ctx.graph, ctx.node_map);
+    
     match entity.get_kind() {
         EntityKind::CallExpr => {
             // Handle initialization with function call
-            if let Some(called_entity) = entity.get_reference() {
-                if let Some(function_name) = called_entity.get_name() {
-                    // Check if this is a memory allocation function
-                    if function_name == "malloc" || function_name == "calloc" || function_name == "realloc" {
-                        if debug {
-                            println!("Memory allocation detected in variable initialization");
-                        }
-                        
-                        // Create a memory operation node
-                        let mem_op_idx = graph.add_node(Node {
-                            name: format!("MemoryOp: {}", function_name),
-                            kind: NodeType::MemoryOp,
-                            line: get_line_number(&entity),
-                            usr: None,
-                            type_info: None,
-                        });
-                        
-                        // Connect variable to memory operation
-                        graph.add_edge(
-                            var_idx,
-                            mem_op_idx,
-                            Edge { kind: EdgeType::Allocates },
-                        );
+            if let Some(called_entity) = entity.get_reference()
+                && let Some(function_name) = called_entity.get_name() {
+                // Check if this is a memory allocation function
+                if function_name == "malloc" || function_name == "calloc" || function_name == "realloc" {
+                    debug!("Memory allocation detected in variable initialization");
+                    
+                    // Create a memory operation node
+                    let mem_op_idx = ctx.graph.add_node(Node::new(
+                        format!("MemoryOp: {}", function_name),
+                        NodeType::MemoryOp,
+                        get_line_number(&entity),
+                    ));
+                    
+                    // Connect variable to memory operation
+                    ctx.graph.add_edge(
+                        var_idx,
+                        mem_op_idx,
+                        Edge { kind: EdgeType::Allocates, indirect: false },
+                    );
+                    
+                    // realloc also releases the block it was given
+                    if function_name == "realloc" {
+                        link_realloc_source(&entity, mem_op_idx, ctx.graph, ctx.node_map);
                     }
                 }
             }
             
-            // Recursively process call arguments to track data flow
-            for arg in entity.get_arguments().unwrap_or_default() {
-                process_function_pointer_references(arg, var_idx, graph, node_map, debug);
-            }
+            // The call gets a node under the variable, as one on the right of an assignment does,
+            // so its arguments are read and what the callee returns reaches the variable. Memory
+            // tracking stays off since the allocation above already stands for it, and the size
+            // checks hang off the call node as they do for assignments.
+            process_call_expression(entity, var_idx, false, ctx);
         },
         EntityKind::DeclRefExpr => {
             // Handle initialization with another variable
-            if let Some(ref_name) = entity.get_name() {
-                if let Some(&ref_idx) = node_map.get(&ref_name) {
-                    // Add edge showing the variable is initialized from another
-                    graph.add_edge(
+            if let Some(ref_name) = entity.get_name()
+                && let Some(&ref_idx) = ctx.node_map.get(&ref_name) {
+                // A function name on the right-hand side initializes a function pointer
+                if ctx.graph[ref_idx].kind == NodeType::Function || ctx.graph[ref_idx].kind == NodeType::Main {
+                    debug!("Function pointer initialized to {}", ref_name);
+                    
+                    link_function_pointer(var_idx, ref_idx, ctx.graph, ctx.pointer_targets);
+                    return;
+                }
+                
+                // Add edge showing the variable is initialized from another
+                ctx.graph.add_edge(
+                    var_idx,
+                    ref_idx,
+                    Edge { kind: EdgeType::Uses, indirect: false },
+                );
+                
+                // If the target is a pointer, record this relationship
+                if ctx.graph[ref_idx].kind == NodeType::Pointer || 
+                   ctx.graph[ref_idx].kind == NodeType::BufferParameter {
+                    ctx.pointer_targets.insert(var_idx, ref_idx);
+                }
+            }
+        },
+        EntityKind::InitListExpr => {
+            // An aggregate like `{x, y}` or `{.a = p}` reads every variable its elements name
+            for name in referenced_variables(&entity) {
+                if let Some(&ref_idx) = ctx.node_map.get(&name) {
+                    ctx.graph.add_edge(
                         var_idx,
                         ref_idx,
-                        Edge { kind: EdgeType::Uses },
+                        Edge { kind: EdgeType::Uses, indirect: false },
                     );
-                    
-                    // If the target is a pointer, record this relationship
-                    if graph[ref_idx].kind == NodeType::Pointer || 
-                       graph[ref_idx].kind == NodeType::BufferParameter {
-                        pointer_targets.insert(var_idx, ref_idx);
-                    }
                 }
             }
         },
@@ -394,110 +639,152 @@ pub fn process_initializer(
             // Check for address-of operator
             let token = entity.get_display_name();
             if token == Some("&".to_string()) {
-                if debug {
-                    println!("Address-of operator detected in initialization");
-                }
+                debug!("Address-of operator detected in initialization");
                 
                 // Find the variable being referenced
                 for child in entity.get_children() {
-                    if child.get_kind() == EntityKind::DeclRefExpr {
-                        if let Some(ref_name) = child.get_name() {
-                            if let Some(&ref_idx) = node_map.get(&ref_name) {
-                                // Add edge showing the pointer points to the variable
-                                graph.add_edge(
-                                    var_idx,
-                                    ref_idx,
-                                    Edge { kind: EdgeType::Points },
-                                );
-                                
-                                // Record this relationship
-                                pointer_targets.insert(var_idx, ref_idx);
-                            }
-                        }
+                    if child.get_kind() == EntityKind::DeclRefExpr
+                        && let Some(ref_name) = child.get_name()
+                        && let Some(&ref_idx) = ctx.node_map.get(&ref_name) {
+                        // Add edge showing the pointer points to the variable
+                        ctx.graph.add_edge(
+                            var_idx,
+                            ref_idx,
+                            Edge { kind: EdgeType::Points, indirect: false },
+                        );
+                        
+                        // Record this relationship
+                        ctx.pointer_targets.insert(var_idx, ref_idx);
                     }
                 }
             }
         },
+        // `sizeof(x)` is a constant; `x` isn't read. A block's body is processed as its own function.
+        _ if is_unevaluated(&entity) || entity.get_kind() == EntityKind::BlockExpr => {},
         _ => {
             // Process children for other initializer types
             for child in entity.get_children() {
-                process_initializer(child, var_idx, graph, node_map, pointer_targets, debug);
+                process_initializer(child, var_idx, ctx);
             }
         }
     }
 }
 
-pub fn process_binary_operator(
-    entity: Entity,
-    parent_idx: NodeIndex,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    debug: bool,
-) {
-    // Check if this is an assignment
-    let token = entity.get_display_name();
-    if token == Some("=".to_string()) {
+pub fn process_binary_operator(entity: Entity, parent_idx: NodeIndex, ctx: &mut BuildCtx) {
+    // Check if this is an assignment. A compound assignment like `x += y` also reads its target.
+    let token = get_binary_operator(&entity).unwrap_or_default();
+    let is_compound = is_compound_assignment(&token);
+    if token == "=" || is_compound {
         // Get left and right hand sides
         let children = entity.get_children();
         if children.len() >= 2 {
             let lhs = &children[0];
             let rhs = &children[1];
             
-            // Handle left-hand side (target)
+            // Handle left-hand side (target). Writing through `*p` targets whatever `p` is known
+            // to point to, and reads `p` itself on the way.
+            let mut through_pointer = None;
             let target_idx = if lhs.get_kind() == EntityKind::DeclRefExpr {
                 if let Some(var_name) = lhs.get_name() {
-                    node_map.get(&var_name).cloned()
+                    ctx.node_map.get(&var_name).cloned()
                 } else {
                     None
                 }
+            } else if let Some(ptr_name) = dereferenced_variable(*lhs) {
+                through_pointer = ctx.node_map.get(&ptr_name).cloned();
+                through_pointer.and_then(|ptr_idx| ctx.pointer_targets.get(&ptr_idx).cloned())
+            } else if unwrap_implicit_expr(*lhs).get_kind() == EntityKind::ArraySubscriptExpr {
+                // Storing into `a[i]` writes the array itself
+                subscripted_variable(*lhs).and_then(|array_name| ctx.node_map.get(&array_name).cloned())
             } else {
                 None
             };
             
             if let Some(target_idx) = target_idx {
                 // Create an assignment node
-                let assign_idx = graph.add_node(Node {
-                    name: format!("Assignment"),
-                    kind: NodeType::Assignment,
-                    line: get_line_number(&entity),
-                    usr: None,
-                    type_info: None,
-                });
+                let assign_idx = ctx.graph.add_node(Node::new(
+                    if is_compound { format!("Assignment: {}", token) } else { "Assignment".to_string() },
+                    NodeType::Assignment,
+                    get_line_number(&entity),
+                ));
                 
                 // Connect parent to assignment
-                graph.add_edge(
+                ctx.graph.add_edge(
                     parent_idx,
                     assign_idx,
-                    Edge { kind: EdgeType::Contains },
+                    Edge { kind: EdgeType::Contains, indirect: false },
                 );
                 
                 // Connect assignment to target
-                graph.add_edge(
+                ctx.graph.add_edge(
                     assign_idx,
                     target_idx,
-                    Edge { kind: EdgeType::Assigns },
+                    Edge { kind: EdgeType::Writes, indirect: through_pointer.is_some() },
                 );
                 
+                if is_compound {
+                    ctx.graph.add_edge(
+                        assign_idx,
+                        target_idx,
+                        Edge { kind: EdgeType::Reads, indirect: false },
+                    );
+                }
+                
+                if let Some(ptr_idx) = through_pointer {
+                    ctx.graph.add_edge(
+                        assign_idx,
+                        ptr_idx,
+                        Edge { kind: EdgeType::Reads, indirect: false },
+                    );
+                }
+                
+                // The subscript itself, so its index variables and bounds are still checked
+                if unwrap_implicit_expr(*lhs).get_kind() == EntityKind::ArraySubscriptExpr {
+                    process_array_access(unwrap_implicit_expr(*lhs), assign_idx, ctx);
+                }
+                
                 // Handle right-hand side (value)
-                process_assignment_value(*rhs, assign_idx, target_idx, graph, node_map, pointer_targets, debug);
+                process_assignment_value(*rhs, assign_idx, target_idx, ctx);
+            } else if unwrap_implicit_expr(*lhs).get_kind() == EntityKind::MemberRefExpr || through_pointer.is_some() {
+                // `s->buf = p` or `*out = p` stores somewhere no variable node stands for. The
+                // assignment still reads what it stores, so the value is seen to leave through it.
+                let assign_idx = ctx.graph.add_node(Node::new(
+                    if is_compound { format!("Assignment: {}", token) } else { "Assignment".to_string() },
+                    NodeType::Assignment,
+                    get_line_number(&entity),
+                ));
+                
+                ctx.graph.add_edge(
+                    parent_idx,
+                    assign_idx,
+                    Edge { kind: EdgeType::Contains, indirect: false },
+                );
+                
+                if unwrap_implicit_expr(*lhs).get_kind() == EntityKind::MemberRefExpr {
+                    process_member_access(unwrap_implicit_expr(*lhs), assign_idx, ctx);
+                }
+                
+                let stored = referenced_variables(rhs).into_iter()
+                    .filter_map(|var_name| ctx.node_map.get(&var_name).cloned());
+                for var_idx in through_pointer.into_iter().chain(stored) {
+                    ctx.graph.add_edge(
+                        assign_idx,
+                        var_idx,
+                        Edge { kind: EdgeType::Reads, indirect: false },
+                    );
+                }
             }
         }
     } else {
-        // For non-assignment binary operators, process operands
+        // For non-assignment binary operators, process operands. Adding to or subtracting from a
+        // pointer computes a new position, so the operands go under a pointer arithmetic node.
+        let operand_parent = if is_pointer_offset(&entity) {
+            add_pointer_arith(&entity, parent_idx, ctx.graph)
+        } else {
+            parent_idx
+        };
         for child in entity.get_children() {
-            process_statement(
-                child, 
-                parent_idx, 
-                graph, 
-                node_map, 
-                &mut HashMap::new(),  // We don't need USR tracking here
-                pointer_targets,
-                &mut HashSet::new(),  // No need to track processed nodes 
-                "",                   // No need for source content
-                debug,
-                false                 // No need for memory tracking
-            );
+            process_statement(child, operand_parent, ctx);
         }
     }
 } 
\ No newline at end of file
@@ -1,84 +1,88 @@
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
-use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
 use clang::{Entity, EntityKind};
-use crate::processors::process_statement;
+use log::{debug, trace};
+use crate::processors::{process_statement, BuildCtx};
+use crate::symbols::SymbolTable;
 use crate::types::{Node, Edge, NodeType, EdgeType};
 use crate::utils::*;
 
-pub fn process_assignment_value(
-    entity: Entity,
-    assign_idx: NodeIndex,
-    target_idx: NodeIndex,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    debug: bool,
-) {
+// Naming a function on the right-hand side takes its address rather than reading a value
+fn read_edge_kind(source: &Node) -> EdgeType {
+    if source.kind == NodeType::Function || source.kind == NodeType::Main {
+        EdgeType::Uses
+    } else {
+        EdgeType::Reads
+    }
+}
+
+pub fn process_assignment_value(entity: Entity, assign_idx: NodeIndex, target_idx: NodeIndex, ctx: &mut BuildCtx) {
+    link_pointer_arith(&entity, assign_idx, ctx.graph, ctx.node_map);
+    
     match entity.get_kind() {
         EntityKind::CallExpr => {
             // Handle assignment from function call
-            if let Some(called_entity) = entity.get_reference() {
-                if let Some(function_name) = called_entity.get_name() {
-                    // Check if this is a memory allocation function
-                    if function_name == "malloc" || function_name == "calloc" || function_name == "realloc" {
-                        if debug {
-                            println!("Memory allocation detected in assignment");
+            if let Some(called_entity) = entity.get_reference()
+                && let Some(function_name) = called_entity.get_name() {
+                // Check if this is a memory allocation function
+                if function_name == "malloc" || function_name == "calloc" || function_name == "realloc" {
+                    debug!("Memory allocation detected in assignment");
+                    
+                    // Create a memory operation node
+                    let mem_op_idx = ctx.graph.add_node(Node::new(
+                        format!("MemoryOp: {}", function_name),
+                        NodeType::MemoryOp,
+                        get_line_number(&entity),
+                    ));
+                    
+                    // Connect assignment to memory operation
+                    ctx.graph.add_edge(
+                        assign_idx,
+                        mem_op_idx,
+                        Edge { kind: EdgeType::Uses, indirect: false },
+                    );
+                    
+                    // Connect target to memory operation
+                    ctx.graph.add_edge(
+                        target_idx,
+                        mem_op_idx,
+                        Edge { kind: EdgeType::Allocates, indirect: false },
+                    );
+                    
+                    // realloc also releases the block it was given
+                    if function_name == "realloc" {
+                        let freed_idx = link_realloc_source(&entity, mem_op_idx, ctx.graph, ctx.node_map);
+                        if freed_idx == Some(target_idx) {
+                            debug!("realloc result overwrites its own source pointer");
                         }
-                        
-                        // Create a memory operation node
-                        let mem_op_idx = graph.add_node(Node {
-                            name: format!("MemoryOp: {}", function_name),
-                            kind: NodeType::MemoryOp,
-                            line: get_line_number(&entity),
-                            usr: None,
-                            type_info: None,
-                        });
-                        
-                        // Connect assignment to memory operation
-                        graph.add_edge(
-                            assign_idx,
-                            mem_op_idx,
-                            Edge { kind: EdgeType::Uses },
-                        );
-                        
-                        // Connect target to memory operation
-                        graph.add_edge(
-                            target_idx,
-                            mem_op_idx,
-                            Edge { kind: EdgeType::Allocates },
-                        );
                     }
                 }
             }
             
-            // Process function call normally
-            process_call_expression(
-                entity, 
-                assign_idx, 
-                graph, 
-                node_map, 
-                &mut HashMap::new(),
-                pointer_targets,
-                debug,
-                false
-            );
+            // Process function call normally. Memory tracking stays off since the allocation above
+            // already stands for it.
+            process_call_expression(entity, assign_idx, false, ctx);
         },
         EntityKind::DeclRefExpr => {
             // Handle assignment from another variable
-            if let Some(ref_name) = entity.get_name() {
-                if let Some(&ref_idx) = node_map.get(&ref_name) {
-                    // Add edge showing the value comes from another variable
-                    graph.add_edge(
-                        assign_idx,
-                        ref_idx,
-                        Edge { kind: EdgeType::Uses },
-                    );
-                    
-                    // If the source is a pointer, record this relationship
-                    if graph[ref_idx].kind == NodeType::Pointer || 
-                       graph[ref_idx].kind == NodeType::BufferParameter {
-                        pointer_targets.insert(target_idx, ref_idx);
-                    }
+            if let Some(ref_name) = entity.get_name()
+                && let Some(&ref_idx) = ctx.node_map.get(&ref_name) {
+                // Add edge showing the value comes from another variable
+                ctx.graph.add_edge(
+                    assign_idx,
+                    ref_idx,
+                    Edge { kind: read_edge_kind(&ctx.graph[ref_idx]), indirect: false },
+                );
+                
+                // If the source is a pointer, record this relationship
+                if ctx.graph[ref_idx].kind == NodeType::Pointer || 
+                   ctx.graph[ref_idx].kind == NodeType::BufferParameter {
+                    ctx.pointer_targets.insert(target_idx, ref_idx);
+                } else if ctx.graph[ref_idx].kind == NodeType::Function || ctx.graph[ref_idx].kind == NodeType::Main {
+                    debug!("Function pointer assigned to {}", ref_name);
+                    link_function_pointer(target_idx, ref_idx, ctx.graph, ctx.pointer_targets);
                 }
             }
         },
@@ -86,315 +90,363 @@ pub fn process_assignment_value(
             // Check for address-of operator
             let token = entity.get_display_name();
             if token == Some("&".to_string()) {
-                if debug {
-                    println!("Address-of operator detected in assignment");
-                }
+                debug!("Address-of operator detected in assignment");
                 
                 // Find the variable being referenced
                 for child in entity.get_children() {
-                    if child.get_kind() == EntityKind::DeclRefExpr {
-                        if let Some(ref_name) = child.get_name() {
-                            if let Some(&ref_idx) = node_map.get(&ref_name) {
-                                // Add edge showing the pointer points to the variable
-                                graph.add_edge(
-                                    target_idx,
-                                    ref_idx,
-                                    Edge { kind: EdgeType::Points },
-                                );
-                                
-                                // Record this relationship
-                                pointer_targets.insert(target_idx, ref_idx);
-                            }
-                        }
+                    if child.get_kind() == EntityKind::DeclRefExpr
+                        && let Some(ref_name) = child.get_name()
+                        && let Some(&ref_idx) = ctx.node_map.get(&ref_name) {
+                        // Add edge showing the pointer points to the variable
+                        ctx.graph.add_edge(
+                            target_idx,
+                            ref_idx,
+                            Edge { kind: EdgeType::Points, indirect: false },
+                        );
+                        
+                        // Record this relationship
+                        ctx.pointer_targets.insert(target_idx, ref_idx);
                     }
                 }
             }
         },
+        EntityKind::BinaryOperator if get_binary_operator(&entity).as_deref() == Some(",") => {
+            // Only the last operand is the value; the others still run, so their side effects
+            // and references hang off the assignment
+            let operands = entity.get_children();
+            if let Some((value, discarded)) = operands.split_last() {
+                for operand in discarded {
+                    process_statement(*operand, assign_idx, ctx);
+                }
+                process_assignment_value(*value, assign_idx, target_idx, ctx);
+            }
+        },
+        EntityKind::StmtExpr => {
+            // A GCC statement-expression `({ ...; value; })` evaluates to its last statement. Its
+            // declarations are local to the braces, so they get their own scope.
+            let statements = entity.get_children().first().map(|body| body.get_children()).unwrap_or_default();
+            ctx.node_map.push_scope();
+            if let Some((value, leading)) = statements.split_last() {
+                for statement in leading {
+                    process_statement(*statement, assign_idx, ctx);
+                }
+                process_assignment_value(*value, assign_idx, target_idx, ctx);
+            }
+            ctx.node_map.pop_scope();
+        },
+        // `sizeof(x)` is a constant; `x` isn't read. A block's body is processed as its own function.
+        _ if is_unevaluated(&entity) || entity.get_kind() == EntityKind::BlockExpr => {},
         _ => {
+            // Reading `a[i]` also gets an access node so a constant index is bounds-checked
+            if entity.get_kind() == EntityKind::ArraySubscriptExpr {
+                process_array_access(entity, assign_idx, ctx);
+            }
+            
             // Process children for other value types
             for child in entity.get_children() {
                 if child.get_kind() == EntityKind::DeclRefExpr {
-                    if let Some(ref_name) = child.get_name() {
-                        if let Some(&ref_idx) = node_map.get(&ref_name) {
-                            // Add edge showing the value uses this variable
-                            graph.add_edge(
-                                assign_idx,
-                                ref_idx,
-                                Edge { kind: EdgeType::Uses },
-                            );
+                    if let Some(ref_name) = child.get_name()
+                        && let Some(&ref_idx) = ctx.node_map.get(&ref_name) {
+                        // Add edge showing the value uses this variable
+                        ctx.graph.add_edge(
+                            assign_idx,
+                            ref_idx,
+                            Edge { kind: read_edge_kind(&ctx.graph[ref_idx]), indirect: false },
+                        );
+                        
+                        // `h = foo` reaches here through the implicit function-to-pointer conversion
+                        if ctx.graph[ref_idx].kind == NodeType::Function || ctx.graph[ref_idx].kind == NodeType::Main {
+                            debug!("Function pointer assigned to {}", ref_name);
+                            link_function_pointer(target_idx, ref_idx, ctx.graph, ctx.pointer_targets);
                         }
                     }
                 } else {
-                    process_assignment_value(child, assign_idx, target_idx, graph, node_map, pointer_targets, debug);
+                    process_assignment_value(child, assign_idx, target_idx, ctx);
                 }
             }
         }
     }
 }
 
-pub fn process_unary_operator(
-    entity: Entity,
-    parent_idx: NodeIndex,
+// Add a `Frees` edge from a realloc or free memory operation to the pointer passed as its first argument
+pub fn link_realloc_source(
+    entity: &Entity,
+    mem_op_idx: NodeIndex,
+    graph: &mut DiGraph<Node, Edge>,
+    node_map: &SymbolTable,
+) -> Option<NodeIndex> {
+    // `free((void *)name)` still frees `name`
+    let source = unwrap_casts(*entity.get_arguments().unwrap_or_default().first()?);
+    if source.get_kind() != EntityKind::DeclRefExpr {
+        return None;
+    }
+    
+    let ptr_idx = *node_map.get(&source.get_name()?)?;
+    graph.add_edge(
+        mem_op_idx,
+        ptr_idx,
+        Edge { kind: EdgeType::Frees, indirect: false },
+    );
+    
+    Some(ptr_idx)
+}
+
+// Record that a function pointer variable now refers to a function, so calls through it resolve
+pub fn link_function_pointer(
+    var_idx: NodeIndex,
+    func_idx: NodeIndex,
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
     pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    debug: bool,
 ) {
+    graph.add_edge(
+        var_idx,
+        func_idx,
+        Edge { kind: EdgeType::Points, indirect: false },
+    );
+    
+    // Later assignments replace the target, so a call resolves to the most recent one
+    pointer_targets.insert(var_idx, func_idx);
+}
+
+// Add a node for an expression that moves a pointer, labelled with the expression as written.
+// The caller processes the operands under it, so the base pointer and index are its `Uses`.
+pub fn add_pointer_arith(entity: &Entity, parent_idx: NodeIndex, graph: &mut DiGraph<Node, Edge>) -> NodeIndex {
+    let expression_text: String = entity.get_range()
+        .map(|range| range.tokenize().iter().map(|token| token.get_spelling()).collect())
+        .unwrap_or_default();
+    debug!("Pointer arithmetic detected: {}", expression_text);
+    
+    let arith_idx = graph.add_node(Node::new(
+        format!("PointerArith: {}", expression_text),
+        NodeType::PointerArith,
+        get_line_number(entity),
+    ));
+    graph.add_edge(
+        parent_idx,
+        arith_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+    arith_idx
+}
+
+// A value that moves a pointer, such as the `p + n` in `end = p + n`, gets a pointer arithmetic
+// node under the assignment or variable it is stored into, using every variable it names
+pub fn link_pointer_arith(
+    value: &Entity,
+    parent_idx: NodeIndex,
+    graph: &mut DiGraph<Node, Edge>,
+    node_map: &SymbolTable,
+) {
+    if !is_pointer_offset(value) {
+        return;
+    }
+    
+    let arith_idx = add_pointer_arith(value, parent_idx, graph);
+    for var_name in referenced_variables(value) {
+        if let Some(&var_idx) = node_map.get(&var_name) {
+            graph.add_edge(
+                arith_idx,
+                var_idx,
+                Edge { kind: EdgeType::Uses, indirect: false },
+            );
+        }
+    }
+}
+
+pub fn process_unary_operator(entity: Entity, parent_idx: NodeIndex, ctx: &mut BuildCtx) {
     // Check for pointer dereference or address-of
     let token = entity.get_display_name();
     
     if token == Some("*".to_string()) {
         // Pointer dereference
-        if debug {
-            println!("Pointer dereference detected");
-        }
+        debug!("Pointer dereference detected");
         
         // Create a dereference node
-        let deref_idx = graph.add_node(Node {
-            name: format!("Dereference"),
-            kind: NodeType::Dereference,
-            line: get_line_number(&entity),
-            usr: None,
-            type_info: None,
-        });
+        let deref_idx = ctx.graph.add_node(Node::new(
+            "Dereference".to_string(),
+            NodeType::Dereference,
+            get_line_number(&entity),
+        ));
         
         // Connect parent to dereference
-        graph.add_edge(
+        ctx.graph.add_edge(
             parent_idx,
             deref_idx,
-            Edge { kind: EdgeType::Contains },
+            Edge { kind: EdgeType::Contains, indirect: false },
         );
         
         // Find the pointer being dereferenced
         for child in entity.get_children() {
             if child.get_kind() == EntityKind::DeclRefExpr {
-                if let Some(ptr_name) = child.get_name() {
-                    if let Some(&ptr_idx) = node_map.get(&ptr_name) {
-                        // Add edge showing the dereference uses the pointer
-                        graph.add_edge(
+                if let Some(ptr_name) = child.get_name()
+                    && let Some(&ptr_idx) = ctx.node_map.get(&ptr_name) {
+                    // Add edge showing the dereference uses the pointer
+                    ctx.graph.add_edge(
+                        deref_idx,
+                        ptr_idx,
+                        Edge { kind: EdgeType::Uses, indirect: false },
+                    );
+                    
+                    // If we know what this pointer points to, add that connection
+                    if let Some(&target_idx) = ctx.pointer_targets.get(&ptr_idx) {
+                        ctx.graph.add_edge(
                             deref_idx,
-                            ptr_idx,
-                            Edge { kind: EdgeType::Uses },
+                            target_idx,
+                            Edge { kind: EdgeType::Accesses, indirect: false },
                         );
-                        
-                        // If we know what this pointer points to, add that connection
-                        if let Some(&target_idx) = pointer_targets.get(&ptr_idx) {
-                            graph.add_edge(
-                                deref_idx,
-                                target_idx,
-                                Edge { kind: EdgeType::Accesses },
-                            );
-                        }
                     }
                 }
             } else {
                 // Recurse for complex dereferences
-                process_statement(
-                    child.clone(), 
-                    deref_idx, 
-                    graph, 
-                    node_map, 
-                    &mut HashMap::new(),
-                    pointer_targets,
-                    &mut HashSet::new(),
-                    "",
-                    debug,
-                    false
-                );
+                process_statement(child, deref_idx, ctx);
             }
         }
+    } else if token == Some("&".to_string()) && is_pointer_offset(&entity) {
+        // `&a[i]` is `a + i`: the element is never read, so it isn't an array access
+        let arith_idx = add_pointer_arith(&entity, parent_idx, ctx.graph);
+        let subscript = unwrap_implicit_expr(entity.get_children()[0]);
+        for child in subscript.get_children() {
+            process_statement(child, arith_idx, ctx);
+        }
     } else if token == Some("&".to_string()) {
         // Address-of operator
-        if debug {
-            println!("Address-of operator detected");
-        }
+        debug!("Address-of operator detected");
         
         // Create an address-of node
-        let addr_idx = graph.add_node(Node {
-            name: format!("AddressOf"),
-            kind: NodeType::AddressOf,
-            line: get_line_number(&entity),
-            usr: None,
-            type_info: None,
-        });
+        let addr_idx = ctx.graph.add_node(Node::new("AddressOf".to_string(), NodeType::AddressOf, get_line_number(&entity)));
         
         // Connect parent to address-of
-        graph.add_edge(
+        ctx.graph.add_edge(
             parent_idx,
             addr_idx,
-            Edge { kind: EdgeType::Contains },
+            Edge { kind: EdgeType::Contains, indirect: false },
         );
         
         // Find the variable being referenced
         for child in entity.get_children() {
             if child.get_kind() == EntityKind::DeclRefExpr {
-                if let Some(var_name) = child.get_name() {
-                    if let Some(&var_idx) = node_map.get(&var_name) {
-                        // Add edge showing the address-of uses the variable
-                        graph.add_edge(
-                            addr_idx,
-                            var_idx,
-                            Edge { kind: EdgeType::Uses },
-                        );
-                    }
+                if let Some(var_name) = child.get_name()
+                    && let Some(&var_idx) = ctx.node_map.get(&var_name) {
+                    // Add edge showing the address-of uses the variable
+                    ctx.graph.add_edge(
+                        addr_idx,
+                        var_idx,
+                        Edge { kind: EdgeType::Uses, indirect: false },
+                    );
                 }
             } else {
                 // Recurse for complex address-of expressions
-                process_statement(
-                    child.clone(), 
-                    addr_idx, 
-                    graph, 
-                    node_map, 
-                    &mut HashMap::new(),
-                    pointer_targets,
-                    &mut HashSet::new(),
-                    "",
-                    debug,
-                    false
-                );
+                process_statement(child, addr_idx, ctx);
             }
         }
     } else {
-        // For other unary operators, just process operand
+        // For other unary operators, just process operand. Stepping a pointer with `++` or `--`
+        // moves it, so its operand goes under a pointer arithmetic node.
+        let operand_parent = if is_pointer_offset(&entity) {
+            add_pointer_arith(&entity, parent_idx, ctx.graph)
+        } else {
+            parent_idx
+        };
         for child in entity.get_children() {
-            process_statement(
-                child.clone(), 
-                parent_idx, 
-                graph, 
-                node_map, 
-                &mut HashMap::new(),
-                pointer_targets,
-                &mut HashSet::new(),
-                "",
-                debug,
-                false
-            );
+            process_statement(child, operand_parent, ctx);
         }
     }
 }
 
-pub fn process_member_access(
-    entity: Entity,
-    parent_idx: NodeIndex,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    debug: bool,
-) {
-    if debug {
-        println!("Processing struct/union member access");
-    }
+pub fn process_member_access(entity: Entity, parent_idx: NodeIndex, ctx: &mut BuildCtx) {
+    trace!("Processing struct/union member access");
     
     // Extract member name
     let member_name = entity.get_name().unwrap_or_else(|| "unknown_member".to_string());
     
     // Create struct access node
-    let access_idx = graph.add_node(Node {
-        name: format!("StructAccess: {}", member_name),
-        kind: NodeType::StructAccess,
-        line: get_line_number(&entity),
-        usr: None,
-        type_info: None,
-    });
+    let access_idx = ctx.graph.add_node(Node::new(
+        format!("StructAccess: {}", member_name),
+        NodeType::StructAccess,
+        get_line_number(&entity),
+    ));
     
     // Connect parent to struct access
-    graph.add_edge(
+    ctx.graph.add_edge(
         parent_idx,
         access_idx,
-        Edge { kind: EdgeType::Contains },
+        Edge { kind: EdgeType::Contains, indirect: false },
     );
     
     // Find the struct being accessed
     for child in entity.get_children() {
         if child.get_kind() == EntityKind::DeclRefExpr {
-            if let Some(struct_name) = child.get_name() {
-                if let Some(&struct_idx) = node_map.get(&struct_name) {
-                    // Add edge showing the access uses the struct
-                    graph.add_edge(
-                        access_idx,
-                        struct_idx,
-                        Edge { kind: EdgeType::Accesses },
-                    );
-                }
+            if let Some(struct_name) = child.get_name()
+                && let Some(&struct_idx) = ctx.node_map.get(&struct_name) {
+                // Add edge showing the access uses the struct
+                ctx.graph.add_edge(
+                    access_idx,
+                    struct_idx,
+                    Edge { kind: EdgeType::Accesses, indirect: false },
+                );
             }
         } else {
             // Recurse for complex member access
-            process_statement(
-                child.clone(), 
-                access_idx, 
-                graph, 
-                node_map, 
-                &mut HashMap::new(),
-                pointer_targets,
-                &mut HashSet::new(),
-                "",
-                debug,
-                false
-            );
+            process_statement(child, access_idx, ctx);
         }
     }
 }
 
-pub fn process_array_access(
-    entity: Entity,
-    parent_idx: NodeIndex,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    debug: bool,
-) {
-    if debug {
-        println!("Processing array access");
-    }
+pub fn process_array_access(entity: Entity, parent_idx: NodeIndex, ctx: &mut BuildCtx) {
+    trace!("Processing array access");
+    
+    // Array access has two children: the array and the index
+    let children = entity.get_children();
+    let array_expr = children.first().map(|&child| unwrap_implicit_expr(child));
+    let array_idx = array_expr
+        .filter(|expr| expr.get_kind() == EntityKind::DeclRefExpr)
+        .and_then(|expr| expr.get_name())
+        .and_then(|array_name| ctx.node_map.get(&array_name).cloned());
+    
+    // A constant index at or past the declared element count is out of bounds
+    let out_of_bounds = match (array_idx, children.get(1)) {
+        (Some(array_idx), Some(&index_expr)) => {
+            let array_size = ctx.graph[array_idx].array_size;
+            integer_literal_value(index_expr)
+                .zip(array_size)
+                .filter(|&(index, size)| index >= size as u64)
+        },
+        _ => None,
+    };
+    
+    let (kind, name) = match out_of_bounds {
+        Some((index, size)) => {
+            debug!("Out-of-bounds index {} into array of {}", index, size);
+            let array_name = array_expr.and_then(|expr| expr.get_name()).unwrap_or_default();
+            (NodeType::OutOfBounds, format!("OutOfBounds: {}[{}] (size {})", array_name, index, size))
+        },
+        None => (NodeType::ArrayAccess, "ArrayAccess".to_string()),
+    };
     
     // Create array access node
-    let access_idx = graph.add_node(Node {
-        name: format!("ArrayAccess"),
-        kind: NodeType::ArrayAccess,
-        line: get_line_number(&entity),
-        usr: None,
-        type_info: None,
-    });
+    let access_idx = ctx.graph.add_node(Node::new(name, kind, get_line_number(&entity)));
     
     // Connect parent to array access
-    graph.add_edge(
+    ctx.graph.add_edge(
         parent_idx,
         access_idx,
-        Edge { kind: EdgeType::Contains },
+        Edge { kind: EdgeType::Contains, indirect: false },
     );
     
-    // Array access has two children: the array and the index
-    let children = entity.get_children();
-    
     // Find the array being accessed
-    if children.len() >= 1 {
-        let array_expr = &children[0];
-        
+    if let Some(array_expr) = array_expr {
         if array_expr.get_kind() == EntityKind::DeclRefExpr {
-            if let Some(array_name) = array_expr.get_name() {
-                if let Some(&array_idx) = node_map.get(&array_name) {
-                    // Add edge showing the access uses the array
-                    graph.add_edge(
-                        access_idx,
-                        array_idx,
-                        Edge { kind: EdgeType::Accesses },
-                    );
-                }
+            if let Some(array_idx) = array_idx {
+                // Add edge showing the access uses the array
+                ctx.graph.add_edge(
+                    access_idx,
+                    array_idx,
+                    Edge { kind: EdgeType::Accesses, indirect: false },
+                );
             }
         } else {
             // Recurse for complex array expressions
-            process_statement(
-                array_expr.clone(), 
-                access_idx, 
-                graph, 
-                node_map, 
-                &mut HashMap::new(),
-                pointer_targets,
-                &mut HashSet::new(),
-                "",
-                debug,
-                false
-            );
+            process_statement(array_expr, access_idx, ctx);
         }
     }
     
@@ -403,7 +455,63 @@ pub fn process_array_access(
         let index_expr = &children[1];
         
         // Look for variables in the index expression
-        find_variable_refs(*index_expr, access_idx, graph, node_map, EdgeType::Uses);
+        find_variable_refs(*index_expr, access_idx, ctx.graph, ctx.node_map, EdgeType::Uses);
+    }
+}
+
+pub fn process_cast_expression(entity: Entity, parent_idx: NodeIndex, ctx: &mut BuildCtx) {
+    // The operand is the last expression child; a leading TypeRef only names the target type
+    let operand = match entity.get_children().into_iter().rev().find(|c| c.is_expression()) {
+        Some(operand) => operand,
+        None => return,
+    };
+    
+    // Look through implicit conversions and parentheses to the value being cast
+    let inner = unwrap_implicit_expr(operand);
+    
+    let target_type = entity.get_type();
+    let source_type = operand.get_type();
+    
+    // Constants like `(void *)0` are deliberate, so only flag casts of computed values
+    let is_dangerous = match (source_type, target_type) {
+        (Some(source), Some(target)) => {
+            inner.get_kind() != EntityKind::IntegerLiteral && is_dangerous_cast(&source, &target)
+        },
+        _ => false,
+    };
+    
+    let source_name = source_type.map(|t| t.get_display_name()).unwrap_or_else(|| "unknown".to_string());
+    let target_name = target_type.map(|t| t.get_display_name()).unwrap_or_else(|| "unknown".to_string());
+    
+    debug!("Cast from {} to {}{}", source_name, target_name, if is_dangerous { " (dangerous)" } else { "" });
+    
+    let (node_type, cast_label) = if is_dangerous {
+        (NodeType::UnsafeCast, format!("UnsafeCast: {} -> {}", source_name, target_name))
+    } else {
+        (NodeType::Cast, format!("Cast: {} -> {}", source_name, target_name))
+    };
+    
+    let cast_idx = ctx.graph.add_node(Node::new(cast_label, node_type, get_line_number(&entity)));
+    
+    // Connect parent to cast
+    ctx.graph.add_edge(
+        parent_idx,
+        cast_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+    
+    if inner.get_kind() == EntityKind::DeclRefExpr {
+        // Add edge showing which variable's value is converted
+        if let Some(&var_idx) = inner.get_name().and_then(|name| ctx.node_map.get(&name)) {
+            ctx.graph.add_edge(
+                cast_idx,
+                var_idx,
+                Edge { kind: EdgeType::Casts, indirect: false },
+            );
+        }
+    } else {
+        // Recurse for casts of calls, dereferences and other expressions
+        process_statement(inner, cast_idx, ctx);
     }
 }
 
@@ -411,20 +519,23 @@ pub fn find_variable_refs(
     entity: Entity,
     parent_idx: NodeIndex,
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
+    node_map: &mut SymbolTable,
     edge_type: EdgeType,
 ) {
-    if entity.get_kind() == EntityKind::DeclRefExpr {
-        if let Some(var_name) = entity.get_name() {
-            if let Some(&var_idx) = node_map.get(&var_name) {
-                // Add edge showing the usage
-                graph.add_edge(
-                    parent_idx,
-                    var_idx,
-                    Edge { kind: edge_type.clone() },
-                );
-            }
-        }
+    // The operand of `sizeof` is never read
+    if is_unevaluated(&entity) {
+        return;
+    }
+    
+    if entity.get_kind() == EntityKind::DeclRefExpr
+        && let Some(var_name) = entity.get_name()
+        && let Some(&var_idx) = node_map.get(&var_name) {
+        // Add edge showing the usage
+        graph.add_edge(
+            parent_idx,
+            var_idx,
+            Edge { kind: edge_type.clone(), indirect: false },
+        );
     }
     
     // Recurse into children
@@ -433,26 +544,184 @@ pub fn find_variable_refs(
     }
 }
 
-pub fn process_call_expression(
-    entity: Entity,
-    parent_idx: NodeIndex,
+// Flag a memory operation whose size argument is `sizeof` of a pointer. The flag hangs off
+// `call_idx` and uses the variables named in the `sizeof`.
+pub fn link_sizeof_pointer(
+    call: &Entity,
+    function_name: &str,
+    call_idx: NodeIndex,
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    usr_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    debug: bool,
-    memory_tracking: bool,
+    node_map: &SymbolTable,
 ) {
+    let size_argument = size_argument_index(function_name)
+        .and_then(|position| call.get_arguments().unwrap_or_default().get(position).copied());
+    let operand = match size_argument.and_then(sizeof_pointer_operand) {
+        Some(operand) => operand,
+        None => return,
+    };
+    
+    let operand_text: String = operand.get_range()
+        .map(|range| range.tokenize().iter().map(|token| token.get_spelling()).collect())
+        .unwrap_or_default();
+    debug!("  sizeof({}) of a pointer sizes {}", operand_text, function_name);
+    
+    let sizeof_idx = graph.add_node(Node {
+        name: format!("SizeofPointer: sizeof({})", operand_text),
+        kind: NodeType::SizeofPointer,
+        line: get_line_number(&operand),
+        type_info: operand.get_type().map(|ty| ty.get_display_name()),
+        ..Node::default()
+    });
+    graph.add_edge(
+        call_idx,
+        sizeof_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+    
+    for var_name in referenced_variables(&operand) {
+        if let Some(&var_idx) = node_map.get(&var_name) {
+            graph.add_edge(
+                sizeof_idx,
+                var_idx,
+                Edge { kind: EdgeType::Uses, indirect: false },
+            );
+        }
+    }
+}
+
+// Flag a bounded string call whose size measures something other than its destination, such as
+// `strncpy(dst, src, strlen(src))`. The flag hangs off `call_idx` and uses the variables measured.
+pub fn link_size_misuse(
+    call: &Entity,
+    function_name: &str,
+    call_idx: NodeIndex,
+    graph: &mut DiGraph<Node, Edge>,
+    node_map: &SymbolTable,
+) {
+    let arguments = call.get_arguments().unwrap_or_default();
+    let (destination, size) = match bounded_size_arguments(function_name)
+        .and_then(|(destination, size)| Some((*arguments.get(destination)?, *arguments.get(size)?)))
+    {
+        Some(pair) => pair,
+        None => return,
+    };
+    let measure = match foreign_size_measure(size, &referenced_variables(&destination)) {
+        Some(measure) => measure,
+        None => return,
+    };
+    
+    let measure_text: String = measure.get_range()
+        .map(|range| range.tokenize().iter().map(|token| token.get_spelling()).collect())
+        .unwrap_or_default();
+    debug!("  {} sizes {} by something other than its destination", measure_text, function_name);
+    
+    let misuse_idx = graph.add_node(Node::new(
+        format!("SizeMisuse: {}", measure_text),
+        NodeType::SizeMisuse,
+        get_line_number(&measure),
+    ));
+    graph.add_edge(
+        call_idx,
+        misuse_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+    
+    // `sizeof` reads nothing, but the flag still points at what it measured
+    let measured = sizeof_operand(&measure).unwrap_or(measure);
+    for var_name in referenced_variables(&measured) {
+        if let Some(&var_idx) = node_map.get(&var_name) {
+            graph.add_edge(
+                misuse_idx,
+                var_idx,
+                Edge { kind: EdgeType::Uses, indirect: false },
+            );
+        }
+    }
+}
+
+// Flag a `malloc` or `realloc` whose size multiplies two run-time values, such as
+// `malloc(count * size)`, where calloc would have checked the product for overflow. The flag
+// hangs off `call_idx` and uses the variables multiplied.
+pub fn link_allocation_overflow(
+    call: &Entity,
+    function_name: &str,
+    call_idx: NodeIndex,
+    graph: &mut DiGraph<Node, Edge>,
+    node_map: &SymbolTable,
+) {
+    if function_name != "malloc" && function_name != "realloc" {
+        return;
+    }
+    let size_argument = size_argument_index(function_name)
+        .and_then(|position| call.get_arguments().unwrap_or_default().get(position).copied());
+    let product = match size_argument.and_then(runtime_multiplication) {
+        Some(product) => product,
+        None => return,
+    };
+    
+    let product_text: String = product.get_range()
+        .map(|range| range.tokenize().iter().map(|token| token.get_spelling()).collect())
+        .unwrap_or_default();
+    debug!("  {} sizes {} and can overflow", product_text, function_name);
+    
+    let overflow_idx = graph.add_node(Node::new(
+        format!("AllocationOverflow: {}", product_text),
+        NodeType::AllocationOverflow,
+        get_line_number(&product),
+    ));
+    graph.add_edge(
+        call_idx,
+        overflow_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+    
+    for var_name in referenced_variables(&product) {
+        if let Some(&var_idx) = node_map.get(&var_name) {
+            graph.add_edge(
+                overflow_idx,
+                var_idx,
+                Edge { kind: EdgeType::Uses, indirect: false },
+            );
+        }
+    }
+}
+
+// An input function stores outside data into the variables it is handed, as in
+// `fgets(line, sizeof line, stdin)` or `scanf("%d", &count)`. The call `Assigns` each of them,
+// which is where taint tracking starts.
+pub fn link_tainted_input(
+    call: &Entity,
+    function_name: &str,
+    call_idx: NodeIndex,
+    graph: &mut DiGraph<Node, Edge>,
+    node_map: &SymbolTable,
+) {
+    let arguments = call.get_arguments().unwrap_or_default();
+    let receivers = input_arguments(function_name, arguments.len())
+        .filter_map(|position| arguments.get(position));
+    for receiver in receivers {
+        for var_name in referenced_variables(receiver) {
+            if let Some(&var_idx) = node_map.get(&var_name) {
+                debug!("  {} reads outside input into {}", function_name, var_name);
+                graph.add_edge(
+                    call_idx,
+                    var_idx,
+                    Edge { kind: EdgeType::Assigns, indirect: false },
+                );
+            }
+        }
+    }
+}
+
+pub fn process_call_expression(entity: Entity, parent_idx: NodeIndex, memory_tracking: bool, ctx: &mut BuildCtx) {
     // First look for a direct reference to the called function
     let called_entity = entity.get_reference();
     
-    if debug {
-        println!("Processing call expression: {:?}", entity);
-        if let Some(ref entity) = called_entity {
-            println!("  Called entity: {:?} (name: {:?})", entity.get_kind(), entity.get_name());
-        } else {
-            println!("  No called entity reference found.");
-        }
+    trace!("Processing call expression: {:?}", entity);
+    if let Some(ref entity) = called_entity {
+        trace!("  Called entity: {:?} (name: {:?})", entity.get_kind(), entity.get_name());
+    } else {
+        trace!("  No called entity reference found.");
     }
     
     // Try to extract the function name
@@ -464,20 +733,54 @@ pub fn process_call_expression(
     };
     
     if let Some(function_name) = function_name {
-        if debug {
-            println!("  Function name: {}", function_name);
-        }
+        trace!("  Function name: {}", function_name);
         
         let is_unsafe = is_unsafe_function(&function_name);
+        let is_library = is_standard_library_function(&function_name)
+            || called_entity.as_ref().is_some_and(is_system_entity);
+        
+        // A prototype with no body in this file is external, unless another input file defines it.
+        // A function called without any declaration is more likely a typo than a library call.
+        let is_external = called_entity.as_ref().is_some_and(|called| {
+            called.get_kind() == EntityKind::FunctionDecl
+                && called.get_definition().is_none()
+                && !is_implicit_declaration(called, &entity)
+        });
         let is_memory_op = memory_tracking && 
                           (function_name == "malloc" || 
                            function_name == "calloc" || 
                            function_name == "realloc" || 
                            function_name == "free");
         
+        // A command sink is only dangerous when its command isn't a fixed string literal. For the
+        // exec family that includes the argument vector, not just the path.
+        let tainted_variables = if is_security_sensitive_function(&function_name) {
+            let arguments = entity.get_arguments().unwrap_or_default();
+            let checked = if is_exec_function(&function_name) { arguments.len() } else { 1 };
+            let mut names: Vec<String> = Vec::new();
+            for name in arguments.iter().take(checked).flat_map(referenced_variables) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            names
+        } else {
+            Vec::new()
+        };
+        let is_sink = !tainted_variables.is_empty();
+        
+        // Anything but a literal in format position lets the data choose the conversions
+        let is_format_risk = format_argument_index(&function_name)
+            .and_then(|position| entity.get_arguments().unwrap_or_default().get(position).copied())
+            .is_some_and(|format| unwrap_implicit_expr(format).get_kind() != EntityKind::StringLiteral);
+        
         // Create node for the function call
         let node_type = if is_unsafe { 
             NodeType::UnsafeCall 
+        } else if is_sink {
+            NodeType::DangerousSink
+        } else if is_format_risk {
+            NodeType::FormatString
         } else if is_memory_op {
             NodeType::MemoryOp
         } else { 
@@ -486,94 +789,137 @@ pub fn process_call_expression(
         
         let call_label = if is_unsafe {
             format!("Unsafe: {}", function_name)
+        } else if is_sink {
+            format!("Sink: {}", function_name)
+        } else if is_format_risk {
+            format!("FormatString: {}", function_name)
         } else if is_memory_op {
             format!("MemoryOp: {}", function_name)
         } else {
             format!("Call: {}", function_name)
         };
         
-        let usr = if let Some(ref called) = called_entity {
-            Some(format!("{:?}", called.get_usr()))
-        } else {
-            None
-        };
+        let usr = called_entity.as_ref().map(|called| format!("{:?}", called.get_usr()));
         
-        let call_idx = graph.add_node(Node {
+        let call_idx = ctx.graph.add_node(Node {
             name: call_label,
             kind: node_type,
             line: get_line_number(&entity),
             usr: usr.clone(),
-            type_info: None,
+            library: is_library,
+            external: is_external,
+            arguments: entity.get_arguments().map(|arguments| arguments.len()),
+            ..Node::default()
         });
         
         // Connect parent to call
-        graph.add_edge(
+        ctx.graph.add_edge(
             parent_idx,
             call_idx,
-            Edge { kind: EdgeType::Contains },
+            Edge { kind: EdgeType::Contains, indirect: false },
         );
         
+        // Record which variables flow into the command
+        for var_name in &tainted_variables {
+            debug!("  Variable {} reaches command sink {}", var_name, function_name);
+            if let Some(&var_idx) = ctx.node_map.get(var_name) {
+                ctx.graph.add_edge(
+                    call_idx,
+                    var_idx,
+                    Edge { kind: EdgeType::Uses, indirect: false },
+                );
+            }
+        }
+        
+        // A size computed from `sizeof` of a pointer covers the pointer, not the buffer behind it
+        link_sizeof_pointer(&entity, &function_name, call_idx, ctx.graph, ctx.node_map);
+        
+        // So does a bound measured from the source or another buffer instead of the destination
+        link_size_misuse(&entity, &function_name, call_idx, ctx.graph, ctx.node_map);
+        
+        // A product of run-time values can wrap around to a small allocation
+        link_allocation_overflow(&entity, &function_name, call_idx, ctx.graph, ctx.node_map);
+        
+        // Input read into a variable makes it tainted
+        link_tainted_input(&entity, &function_name, call_idx, ctx.graph, ctx.node_map);
+        
         // Try to find the called function in our maps
         let func_idx = if let Some(ref usr_str) = usr {
             if !usr_str.is_empty() {
-                usr_map.get(usr_str).cloned()
+                ctx.usr_map.get(usr_str).cloned()
             } else {
                 None
             }
         } else {
             None
-        }.or_else(|| node_map.get(&function_name).cloned());
+        }.or_else(|| ctx.node_map.get(&function_name).cloned());
         
         // Connect call to the actual function if it exists in our graph
-        if let Some(func_idx) = func_idx {
-            graph.add_edge(
-                call_idx,
-                func_idx,
-                Edge { kind: EdgeType::Calls },
-            );
-            
-            if debug {
-                println!("  Added 'calls' edge from {} to {}", call_idx.index(), func_idx.index());
+        match func_idx {
+            Some(func_idx) if ctx.graph[func_idx].kind == NodeType::Function || ctx.graph[func_idx].kind == NodeType::Main => {
+                ctx.graph.add_edge(
+                    call_idx,
+                    func_idx,
+                    Edge { kind: EdgeType::Calls, indirect: false },
+                );
+                
+                // The function node only has a range once a definition was found
+                if ctx.graph[func_idx].end_line.is_some() {
+                    ctx.graph[call_idx].external = false;
+                }
+                
+                trace!("  Added 'calls' edge from {} to {}", call_idx.index(), func_idx.index());
+            },
+            Some(ptr_idx) => {
+                // The callee is a variable, so this is a call through a function pointer
+                ctx.graph.add_edge(
+                    call_idx,
+                    ptr_idx,
+                    Edge { kind: EdgeType::Uses, indirect: false },
+                );
+                
+                // Resolve the target from what the pointer was last assigned
+                if let Some(&target_idx) = ctx.pointer_targets.get(&ptr_idx) {
+                    if ctx.graph[target_idx].kind == NodeType::Function || ctx.graph[target_idx].kind == NodeType::Main {
+                        ctx.graph.add_edge(
+                            call_idx,
+                            target_idx,
+                            Edge { kind: EdgeType::Calls, indirect: true },
+                        );
+                        
+                        debug!("  Resolved indirect call through {} to {}", function_name, ctx.graph[target_idx].name);
+                    }
+                } else {
+                    debug!("  Could not resolve target of function pointer: {}", function_name);
+                }
+            },
+            None => {
+                debug!("  Could not find function definition for: {}", function_name);
             }
-        } else if debug {
-            println!("  Could not find function definition for: {}", function_name);
         }
         
         // For unsafe calls, create another node that controls this one
         if is_unsafe {
-            let unsafe_idx = graph.add_node(Node {
-                name: format!("Unsafe: {}", function_name),
-                kind: NodeType::UnsafeCall,
-                line: None,
-                usr: None,
-                type_info: None,
-            });
+            let unsafe_idx = ctx.graph.add_node(Node::new(
+                format!("Unsafe: {}", function_name),
+                NodeType::UnsafeCall,
+                None,
+            ));
             
-            graph.add_edge(
+            ctx.graph.add_edge(
                 unsafe_idx,
                 call_idx,
-                Edge { kind: EdgeType::Controls },
+                Edge { kind: EdgeType::Controls, indirect: false },
             );
         }
         
         // Handle memory operations specially
         if is_memory_op {
-            if function_name == "free" {
-                // For free(), find the pointer being freed
-                if let Some(arg) = entity.get_arguments().unwrap_or_default().first() {
-                    if arg.get_kind() == EntityKind::DeclRefExpr {
-                        if let Some(ptr_name) = arg.get_name() {
-                            if let Some(&ptr_idx) = node_map.get(&ptr_name) {
-                                // Add edge showing the memory operation frees the pointer
-                                graph.add_edge(
-                                    call_idx,
-                                    ptr_idx,
-                                    Edge { kind: EdgeType::Frees },
-                                );
-                            }
-                        }
-                    }
-                }
+            if function_name == "free" || function_name == "realloc" {
+                // For free(), find the pointer being freed. The argument usually sits under an
+                // implicit cast, which the shared helper looks through. realloc releases its
+                // argument too, even when the result isn't assigned, as in `return realloc(p, n)`.
+                link_realloc_source(&entity, call_idx, ctx.graph, ctx.node_map);
             } else {
                 // For allocation functions, nothing special to do here
                 // The connection will be made by the assignment processing
@@ -582,11 +928,39 @@ pub fn process_call_expression(
         
         // Process call arguments to track data flow
         for arg in entity.get_arguments().unwrap_or_default() {
-            process_call_argument(&arg, call_idx, graph, node_map, pointer_targets);
+            process_call_argument(&arg, call_idx, ctx.graph, ctx.node_map, ctx.pointer_targets);
         }
         
         // Also check for function pointers in arguments
-        process_function_pointer_references(entity, call_idx, graph, node_map, debug);
+        process_function_pointer_references(entity, call_idx, ctx.graph, ctx.node_map);
+    } else {
+        // The callee is an expression with no declaration behind it, such as `table[i](x)` or
+        // `(flag ? a : b)(x)`. Keep the call, labelled with the callee as written, so it isn't lost.
+        let callee_text: String = entity.get_children().first()
+            .and_then(|callee| callee.get_range())
+            .map(|range| range.tokenize().iter().map(|token| token.get_spelling()).collect())
+            .unwrap_or_default();
+        debug!("  Unresolved callee: {:?}", callee_text);
+        
+        let call_idx = ctx.graph.add_node(Node::new(
+            if callee_text.is_empty() { "UnknownCall".to_string() } else { format!("UnknownCall: {}", callee_text) },
+            NodeType::UnknownCall,
+            get_line_number(&entity),
+        ));
+        
+        ctx.graph.add_edge(
+            parent_idx,
+            call_idx,
+            Edge { kind: EdgeType::Contains, indirect: false },
+        );
+        
+        // The callee expression and the arguments still read variables
+        if let Some(&callee) = entity.get_children().first() {
+            find_variable_refs(callee, call_idx, ctx.graph, ctx.node_map, EdgeType::Uses);
+        }
+        for arg in entity.get_arguments().unwrap_or_default() {
+            process_call_argument(&arg, call_idx, ctx.graph, ctx.node_map, ctx.pointer_targets);
+        }
     }
 }
 
@@ -596,6 +970,10 @@ pub fn extract_function_name_from_call(entity: &Entity) -> Option<String> {
     if !children.is_empty() {
         match children[0].get_kind() {
             EntityKind::DeclRefExpr => children[0].get_name(),
+            // Look through implicit casts, parentheses and `(*fp)(...)` dereferences
+            EntityKind::UnexposedExpr | EntityKind::ParenExpr | EntityKind::UnaryOperator => {
+                extract_function_name_from_call(&children[0])
+            },
             _ => None,
         }
     } else {
@@ -607,11 +985,11 @@ pub fn process_call_argument(
     arg: &Entity,
     call_idx: NodeIndex,
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
+    node_map: &mut SymbolTable,
     pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
 ) {
     // Try to find references to variables/parameters in the argument
-    let mut current = arg.clone();
+    let mut current = *arg;
     
     // Traverse through the AST looking for variable references
     loop {
@@ -624,7 +1002,7 @@ pub fn process_call_argument(
                         graph.add_edge(
                             call_idx,
                             var_idx,
-                            Edge { kind: EdgeType::Uses },
+                            Edge { kind: EdgeType::Uses, indirect: false },
                         );
                         
                         // If the variable is a pointer, we might want to add a relationship
@@ -633,13 +1011,30 @@ pub fn process_call_argument(
                             graph.add_edge(
                                 call_idx,
                                 target_idx,
-                                Edge { kind: EdgeType::Uses },
+                                Edge { kind: EdgeType::Uses, indirect: false },
                             );
                         }
                     }
                 }
                 break;
             },
+            EntityKind::StringLiteral => {
+                // Literals are part of the call, so they sit under it like its other pieces.
+                // Together with `FormatString` calls this shows which format strings are fixed.
+                let literal_idx = graph.add_node(Node::new(
+                    format!("String: {}", current.get_display_name().unwrap_or_default()),
+                    NodeType::StringLiteral,
+                    get_line_number(&current),
+                ));
+                graph.add_edge(
+                    call_idx,
+                    literal_idx,
+                    Edge { kind: EdgeType::Contains, indirect: false },
+                );
+                break;
+            },
+            // `sizeof(x)` passes a constant; `x` isn't read. A block passes itself, not what its body reads.
+            _ if is_unevaluated(&current) || current.get_kind() == EntityKind::BlockExpr => break,
             _ => {
                 // Check if there are any children to traverse
                 let children = current.get_children();
@@ -647,7 +1042,7 @@ pub fn process_call_argument(
                     break;
                 }
                 // Just take the first child for simplicity
-                current = children[0].clone();
+                current = children[0];
             }
         }
     }
@@ -657,8 +1052,7 @@ pub fn process_function_pointer_references(
     entity: Entity,
     parent_idx: NodeIndex,
     graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    debug: bool,
+    node_map: &mut SymbolTable,
 ) {
     // This function specifically looks for function pointers in arguments
     match entity.get_kind() {
@@ -669,40 +1063,32 @@ pub fn process_function_pointer_references(
                 if arg.get_kind() == EntityKind::DeclRefExpr || arg.get_kind() == EntityKind::UnexposedExpr {
                     // Try to extract a function name
                     if let Some(func_name) = arg.get_name() {
-                        if debug {
-                            println!("  Found potential function pointer: {} in argument", func_name);
-                        }
+                        debug!("  Found potential function pointer: {} in argument", func_name);
                         
                         // Check if this is a known function name
                         if let Some(&func_idx) = node_map.get(&func_name) {
-                            if debug {
-                                println!("  Connecting function pointer {} to parent", func_name);
-                            }
+                            debug!("  Connecting function pointer {} to parent", func_name);
                             
                             // Add an edge showing the function is referenced/used by this entity
                             graph.add_edge(
                                 parent_idx,
                                 func_idx,
-                                Edge { kind: EdgeType::References },
+                                Edge { kind: EdgeType::References, indirect: false },
                             );
                         }
                     }
                     
                     // Recursively check inside the argument
                     for child in arg.get_children() {
-                        if child.get_kind() == EntityKind::DeclRefExpr {
-                            if let Some(name) = child.get_name() {
-                                if let Some(&idx) = node_map.get(&name) {
-                                    if debug {
-                                        println!("  Found nested function pointer: {}", name);
-                                    }
-                                    graph.add_edge(
-                                        parent_idx,
-                                        idx,
-                                        Edge { kind: EdgeType::References },
-                                    );
-                                }
-                            }
+                        if child.get_kind() == EntityKind::DeclRefExpr
+                            && let Some(name) = child.get_name()
+                            && let Some(&idx) = node_map.get(&name) {
+                            debug!("  Found nested function pointer: {}", name);
+                            graph.add_edge(
+                                parent_idx,
+                                idx,
+                                Edge { kind: EdgeType::References, indirect: false },
+                            );
                         }
                     }
                 }
@@ -711,29 +1097,19 @@ pub fn process_function_pointer_references(
         _ => {
             // Recursively process children for other entity types
             for child in entity.get_children() {
-                process_function_pointer_references(child, parent_idx, graph, node_map, debug);
+                process_function_pointer_references(child, parent_idx, graph, node_map);
             }
         }
     }
 }
 
-pub fn process_if_statement(
-    entity: Entity,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    usr_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    processed: &mut HashSet<String>,
-    content: &str,
-    debug: bool,
-    memory_tracking: bool,
-) -> Option<NodeIndex> {
-    let if_idx = graph.add_node(Node {
+pub fn process_if_statement(entity: Entity, ctx: &mut BuildCtx) -> Option<NodeIndex> {
+    let if_idx = ctx.graph.add_node(Node {
         name: "If statement".to_string(),
         kind: NodeType::IfStatement,
         line: get_line_number(&entity),
-        usr: None,
-        type_info: None,
+        end_line: get_end_line(&entity),
+        ..Node::default()
     });
     
     // Process the condition (to track variable uses)
@@ -743,169 +1119,226 @@ pub fn process_if_statement(
         c.get_kind() == EntityKind::DeclRefExpr
     ) {
         for child in cond.get_children() {
-            if child.get_kind() == EntityKind::DeclRefExpr {
-                if let Some(var_name) = child.get_name() {
-                    if let Some(&var_idx) = node_map.get(&var_name) {
-                        graph.add_edge(
-                            if_idx,
-                            var_idx,
-                            Edge { kind: EdgeType::Uses },
-                        );
-                    }
-                }
+            if child.get_kind() == EntityKind::DeclRefExpr
+                && let Some(var_name) = child.get_name()
+                && let Some(&var_idx) = ctx.node_map.get(&var_name) {
+                ctx.graph.add_edge(
+                    if_idx,
+                    var_idx,
+                    Edge { kind: EdgeType::Uses, indirect: false },
+                );
             }
         }
     }
     
-    // Process the then branch
-    if let Some(then_branch) = entity.get_children().iter().find(|c| c.get_kind() == EntityKind::CompoundStmt) {
-        let then_bb_idx = graph.add_node(Node {
-            name: "BasicBlock: then".to_string(),
+    // Children are the condition, the then branch and an optional else branch. Branches without
+    // braces hold a single statement, which is how `if (!p) goto fail;` is usually written.
+    let children = entity.get_children();
+    let branches = [("BasicBlock: then", children.get(1)), ("BasicBlock: else", children.get(2))];
+    for (label, branch) in branches {
+        let Some(branch) = branch else { continue };
+        
+        let bb_idx = ctx.graph.add_node(Node {
+            name: label.to_string(),
             kind: NodeType::BasicBlock,
-            line: get_line_number(then_branch),
-            usr: None,
-            type_info: None,
+            line: get_line_number(branch),
+            end_line: get_end_line(branch),
+            ..Node::default()
         });
         
-        graph.add_edge(
+        ctx.graph.add_edge(
             if_idx,
-            then_bb_idx,
-            Edge { kind: EdgeType::Contains },
+            bb_idx,
+            Edge { kind: EdgeType::Contains, indirect: false },
         );
         
-        for child in then_branch.get_children() {
-            process_statement(
-                child.clone(), 
-                then_bb_idx, 
-                graph, 
-                node_map, 
-                usr_map, 
-                pointer_targets,
-                processed, 
-                content, 
-                debug,
-                memory_tracking
-            );
-        }
-    }
-    
-    // Process the else branch if it exists
-    let children = entity.get_children();
-    if children.len() >= 3 {
-        let else_branch = &children[2];
-        if else_branch.get_kind() == EntityKind::CompoundStmt {
-            let else_bb_idx = graph.add_node(Node {
-                name: "BasicBlock: else".to_string(),
-                kind: NodeType::BasicBlock,
-                line: get_line_number(else_branch),
-                usr: None,
-                type_info: None,
-            });
-            
-            graph.add_edge(
-                if_idx,
-                else_bb_idx,
-                Edge { kind: EdgeType::Contains },
-            );
-            
-            for child in else_branch.get_children() {
-                process_statement(
-                    child.clone(), 
-                    else_bb_idx, 
-                    graph, 
-                    node_map, 
-                    usr_map, 
-                    pointer_targets,
-                    processed, 
-                    content, 
-                    debug,
-                    memory_tracking
-                );
-            }
-        }
+        // A compound branch is flattened into the block by `process_statement`
+        process_statement(*branch, bb_idx, ctx);
     }
     
     Some(if_idx)
 }
 
-pub fn process_loop(
-    entity: Entity,
-    graph: &mut DiGraph<Node, Edge>,
-    node_map: &mut HashMap<String, NodeIndex>,
-    usr_map: &mut HashMap<String, NodeIndex>,
-    pointer_targets: &mut HashMap<NodeIndex, NodeIndex>,
-    processed: &mut HashSet<String>,
-    content: &str,
-    loop_type: NodeType,
-    debug: bool,
-    memory_tracking: bool,
-) -> Option<NodeIndex> {
+pub fn process_loop(entity: Entity, loop_type: NodeType, ctx: &mut BuildCtx) -> Option<NodeIndex> {
     let loop_name = match loop_type {
         NodeType::ForLoop => "For loop",
         NodeType::WhileLoop => "While loop",
         _ => "Loop",
     };
     
-    let loop_idx = graph.add_node(Node {
+    let loop_idx = ctx.graph.add_node(Node {
         name: loop_name.to_string(),
         kind: loop_type,
         line: get_line_number(&entity),
-        usr: None,
-        type_info: None,
+        end_line: get_end_line(&entity),
+        ..Node::default()
     });
     
+    // The body comes last; everything before it is the init, condition and increment
+    let children = entity.get_children();
+    let (body, header) = match children.split_last() {
+        Some((body, header)) => (Some(body), header),
+        None => (None, &children[..]),
+    };
+    
     // Process loop condition variables
-    for child in entity.get_children() {
+    for child in header {
         if child.get_kind() == EntityKind::BinaryOperator || 
            child.get_kind() == EntityKind::UnaryOperator ||
            child.get_kind() == EntityKind::DeclRefExpr {
             for subchild in child.get_children() {
-                if subchild.get_kind() == EntityKind::DeclRefExpr {
-                    if let Some(var_name) = subchild.get_name() {
-                        if let Some(&var_idx) = node_map.get(&var_name) {
-                            graph.add_edge(
-                                loop_idx,
-                                var_idx,
-                                Edge { kind: EdgeType::Uses },
-                            );
-                        }
-                    }
+                if subchild.get_kind() == EntityKind::DeclRefExpr
+                    && let Some(var_name) = subchild.get_name()
+                    && let Some(&var_idx) = ctx.node_map.get(&var_name) {
+                    ctx.graph.add_edge(
+                        loop_idx,
+                        var_idx,
+                        Edge { kind: EdgeType::Uses, indirect: false },
+                    );
                 }
             }
         }
     }
     
-    // Process loop body
-    if let Some(body) = entity.get_children().iter().find(|c| c.get_kind() == EntityKind::CompoundStmt) {
-        let body_idx = graph.add_node(Node {
+    // Process loop body. Without braces it is a single statement, as in `while (x) goto out;`.
+    if let Some(body) = body {
+        let body_idx = ctx.graph.add_node(Node {
             name: "BasicBlock: loop body".to_string(),
             kind: NodeType::BasicBlock,
             line: get_line_number(body),
-            usr: None,
-            type_info: None,
+            end_line: get_end_line(body),
+            ..Node::default()
         });
         
-        graph.add_edge(
+        ctx.graph.add_edge(
             loop_idx,
             body_idx,
-            Edge { kind: EdgeType::Contains },
+            Edge { kind: EdgeType::Contains, indirect: false },
         );
         
-        for child in body.get_children() {
-            process_statement(
-                child.clone(), 
-                body_idx, 
-                graph, 
-                node_map, 
-                usr_map, 
-                pointer_targets,
-                processed, 
-                content, 
-                debug,
-                memory_tracking
+        // A compound body is flattened into the block by `process_statement`
+        process_statement(*body, body_idx, ctx);
+    }
+    
+    Some(loop_idx)
+}
+
+pub fn process_label_statement(entity: Entity, parent_idx: NodeIndex, ctx: &mut BuildCtx) {
+    let label_name = entity.get_name().unwrap_or_default();
+    debug!("Processing label: {}", label_name);
+    
+    let label_idx = ctx.graph.add_node(Node::new(
+        format!("Label: {}", label_name),
+        NodeType::Label,
+        get_line_number(&entity),
+    ));
+    
+    ctx.graph.add_edge(
+        parent_idx,
+        label_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+    
+    // The labelled statement belongs to the label so a jump leads straight to it
+    for child in entity.get_children() {
+        process_statement(child, label_idx, ctx);
+    }
+}
+
+pub fn process_goto_statement(
+    entity: Entity,
+    parent_idx: NodeIndex,
+    graph: &mut DiGraph<Node, Edge>,
+) {
+    // The target label is named by the goto's LabelRef child
+    let label_name = entity.get_children().iter()
+        .find(|c| c.get_kind() == EntityKind::LabelRef)
+        .and_then(|c| c.get_name())
+        .unwrap_or_default();
+    debug!("Processing goto: {}", label_name);
+    
+    let goto_idx = graph.add_node(Node::new(format!("Goto: {}", label_name), NodeType::Goto, get_line_number(&entity)));
+    
+    graph.add_edge(
+        parent_idx,
+        goto_idx,
+        Edge { kind: EdgeType::Contains, indirect: false },
+    );
+}
+
+// Move the `Returns` edges left by return statements onto the function. A return statement only
+// knows the block holding it, and branches and loops are attached to their parent after their
+// contents, so this runs once the body has been processed. Nested functions and blocks have
+// already claimed their own returns by then.
+pub fn link_returns(func_idx: NodeIndex, graph: &mut DiGraph<Node, Edge>) {
+    let mut returns: Vec<(EdgeIndex, NodeIndex)> = Vec::new();
+    
+    let mut stack = vec![func_idx];
+    let mut visited = HashSet::new();
+    while let Some(node_idx) = stack.pop() {
+        if !visited.insert(node_idx) {
+            continue;
+        }
+        
+        for edge in graph.edges(node_idx) {
+            match edge.weight().kind {
+                EdgeType::Returns if node_idx != func_idx => returns.push((edge.id(), edge.target())),
+                EdgeType::Contains if graph[edge.target()].kind != NodeType::Function => stack.push(edge.target()),
+                _ => {},
+            }
+        }
+    }
+    
+    // Removing an edge moves the last one into its slot, so go from the highest index down
+    returns.sort_by_key(|&(edge_idx, _)| Reverse(edge_idx));
+    for (edge_idx, value_idx) in returns {
+        graph.remove_edge(edge_idx);
+        let already_linked = graph.edges_connecting(func_idx, value_idx)
+            .any(|edge| edge.weight().kind == EdgeType::Returns);
+        if !already_linked {
+            graph.add_edge(
+                func_idx,
+                value_idx,
+                Edge { kind: EdgeType::Returns, indirect: false },
             );
         }
     }
+}
+
+// Connect every goto in a function to its target label. Labels are scoped to the whole function
+// and a goto may jump forward, so this runs once the body has been processed.
+pub fn link_jumps(func_idx: NodeIndex, graph: &mut DiGraph<Node, Edge>) {
+    let mut labels: HashMap<String, NodeIndex> = HashMap::new();
+    let mut gotos: Vec<(NodeIndex, String)> = Vec::new();
     
-    Some(loop_idx)
-} 
\ No newline at end of file
+    let mut stack = vec![func_idx];
+    let mut visited = HashSet::new();
+    while let Some(node_idx) = stack.pop() {
+        if !visited.insert(node_idx) {
+            continue;
+        }
+        
+        let node = &graph[node_idx];
+        if node.kind == NodeType::Label {
+            labels.insert(node.name["Label: ".len()..].to_string(), node_idx);
+        } else if node.kind == NodeType::Goto {
+            gotos.push((node_idx, node.name["Goto: ".len()..].to_string()));
+        }
+        
+        // A nested function or block has labels of its own, linked when its body was processed
+        stack.extend(graph.edges(node_idx)
+            .filter(|edge| edge.weight().kind == EdgeType::Contains)
+            .map(|edge| edge.target())
+            .filter(|&target| !matches!(graph[target].kind, NodeType::Function | NodeType::Main)));
+    }
+    
+    for (goto_idx, label_name) in gotos {
+        if let Some(&label_idx) = labels.get(&label_name) {
+            graph.add_edge(
+                goto_idx,
+                label_idx,
+                Edge { kind: EdgeType::Jumps, indirect: false },
+            );
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use petgraph::graph::NodeIndex;
+
+// Name lookup for graph nodes. Functions and globals live in the outermost table, and each
+// function pushes a scope for its parameters and locals so same-named symbols in different
+// functions resolve to their own nodes.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    globals: HashMap<String, NodeIndex>,
+    scopes: Vec<HashMap<String, NodeIndex>>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable::default()
+    }
+
+    // The innermost declaration wins, globals are the fallback
+    pub fn get(&self, name: &str) -> Option<&NodeIndex> {
+        self.scopes.iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .or_else(|| self.globals.get(name))
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    // Declare in the innermost open scope, or globally when no scope is open
+    pub fn insert(&mut self, name: String, node_idx: NodeIndex) {
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name, node_idx),
+            None => self.globals.insert(name, node_idx),
+        };
+    }
+
+    // The declaration a new `name` in the innermost scope would hide: one from an enclosing
+    // scope or a global. Redeclaring in the same scope, or at file scope, hides nothing.
+    pub fn shadowed(&self, name: &str) -> Option<NodeIndex> {
+        let (innermost, outer) = self.scopes.split_last()?;
+        if innermost.contains_key(name) {
+            return None;
+        }
+        outer.iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .or_else(|| self.globals.get(name))
+            .copied()
+    }
+
+    pub fn insert_global(&mut self, name: String, node_idx: NodeIndex) {
+        self.globals.insert(name, node_idx);
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+}
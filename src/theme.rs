@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+use crate::types::{EdgeType, NodeType};
+
+// DOT styling that replaces the built-in look of a node or edge kind. Unset fields keep the default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Style {
+    pub shape: Option<String>, // Nodes only
+    pub color: Option<String>, // Fill color for nodes, line color for edges
+    pub style: Option<String>,
+}
+
+// Per-kind style overrides, keyed by canonical kind name. Read from a TOML file such as
+//
+//     [nodes.unsafe_call]
+//     color = "#d55e00"
+//     shape = "octagon"
+//
+//     [edges.calls]
+//     color = "#0072b2"
+//     style = "bold"
+#[derive(Debug, Clone, Default)]
+pub struct Theme {
+    pub nodes: HashMap<String, Style>,
+    pub edges: HashMap<String, Style>,
+}
+
+impl Theme {
+    pub fn load(path: &Path) -> Result<Theme> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read theme: {:?}", path))?;
+        Theme::parse(&content).with_context(|| format!("Failed to parse theme: {:?}", path))
+    }
+
+    pub fn parse(content: &str) -> Result<Theme> {
+        let sections: HashMap<String, HashMap<String, Style>> = toml::from_str(content)?;
+        let mut theme = Theme::default();
+
+        for (section, styles) in sections {
+            for (kind, style) in styles {
+                let table = format!("[{}.{}]", section, kind);
+                let (entry, is_edge) = match section.as_str() {
+                    "nodes" => {
+                        let kind: NodeType = kind.parse().map_err(|e| anyhow!("{}: {}", table, e))?;
+                        (theme.nodes.entry(kind.as_str().to_string()).or_default(), false)
+                    },
+                    "edges" => {
+                        let kind: EdgeType = kind.parse().map_err(|e| anyhow!("{}: {}", table, e))?;
+                        (theme.edges.entry(kind.as_str().to_string()).or_default(), true)
+                    },
+                    other => return Err(anyhow!("{}: unknown section '{}' (expected nodes or edges)", table, other)),
+                };
+
+                if let Some(shape) = &style.shape {
+                    if is_edge {
+                        return Err(anyhow!("{}: unknown edge style key 'shape'", table));
+                    }
+                    // Shapes are written unquoted, so only plain identifiers are safe
+                    if shape.is_empty() || !shape.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                        return Err(anyhow!("{}: '{}' is not a Graphviz shape name", table, shape));
+                    }
+                }
+
+                // Spellings of the same kind share one entry, as long as they don't set the same key
+                for (key, value, slot) in [
+                    ("shape", style.shape, &mut entry.shape),
+                    ("color", style.color, &mut entry.color),
+                    ("style", style.style, &mut entry.style),
+                ] {
+                    if value.is_some() && slot.is_some() {
+                        return Err(anyhow!("{}: '{}' is already set for this kind", table, key));
+                    }
+                    if value.is_some() {
+                        *slot = value;
+                    }
+                }
+            }
+        }
+
+        Ok(theme)
+    }
+
+    pub fn node_style(&self, kind: &NodeType) -> Option<&Style> {
+        self.nodes.get(kind.as_str())
+    }
+
+    pub fn edge_style(&self, kind: &EdgeType) -> Option<&Style> {
+        self.edges.get(kind.as_str())
+    }
+}
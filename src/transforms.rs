@@ -0,0 +1,145 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+
+use crate::analysis::{find_function, used_from};
+use crate::types::{Edge, EdgeType, Node, NodeType};
+
+// Kept nodes reachable from `start` through `Contains` edges without passing another kept node
+fn kept_descendants(
+    graph: &DiGraph<Node, Edge>,
+    start: NodeIndex,
+    kept: &HashMap<NodeIndex, NodeIndex>,
+) -> Vec<NodeIndex> {
+    let mut found = Vec::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    visited.insert(start);
+    queue.push_back(start);
+
+    while let Some(node_idx) = queue.pop_front() {
+        for edge in graph.edges(node_idx) {
+            if edge.weight().kind != EdgeType::Contains || !visited.insert(edge.target()) {
+                continue;
+            }
+
+            if kept.contains_key(&edge.target()) {
+                found.push(edge.target());
+            } else {
+                queue.push_back(edge.target());
+            }
+        }
+    }
+
+    found
+}
+
+// Build a copy of the graph holding only the nodes `keep_node` accepts. Edges between kept nodes
+// survive when `keep_edge` accepts them, and a kept node whose container was removed is
+// re-attached with a `Contains` edge to its nearest kept ancestor so the structure doesn't fragment.
+fn contract_graph<N, E>(graph: &DiGraph<Node, Edge>, keep_node: N, keep_edge: E) -> DiGraph<Node, Edge>
+where
+    N: Fn(&Node) -> bool,
+    E: Fn(&Edge) -> bool,
+{
+    let mut contracted = DiGraph::new();
+    let mut index_map: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+
+    for node_idx in graph.node_indices() {
+        if keep_node(&graph[node_idx]) {
+            index_map.insert(node_idx, contracted.add_node(graph[node_idx].clone()));
+        }
+    }
+
+    for edge in graph.edge_references() {
+        match (index_map.get(&edge.source()), index_map.get(&edge.target())) {
+            (Some(&source), Some(&target)) if keep_edge(edge.weight()) => {
+                contracted.add_edge(source, target, edge.weight().clone());
+            },
+            (Some(&source), None) if edge.weight().kind == EdgeType::Contains => {
+                // Hoist the removed child's surviving descendants up to this node
+                for descendant in kept_descendants(graph, edge.target(), &index_map) {
+                    contracted.add_edge(
+                        source,
+                        index_map[&descendant],
+                        Edge { kind: EdgeType::Contains, indirect: false },
+                    );
+                }
+            },
+            _ => {}
+        }
+    }
+
+    contracted
+}
+
+// Build a copy of the graph holding only nodes whose kind is in `keep`, bridging removed containers
+pub fn prune_node_types(graph: &DiGraph<Node, Edge>, keep: &HashSet<NodeType>) -> DiGraph<Node, Edge> {
+    contract_graph(graph, |node| keep.contains(&node.kind), |_| true)
+}
+
+// Condense the graph to a call-graph view: functions, calls and memory operations, linked by
+// `Calls`/`References` edges, with each function's statement tree contracted to direct
+// `Contains` edges from the function to the calls it makes
+pub fn summarize(graph: &DiGraph<Node, Edge>) -> DiGraph<Node, Edge> {
+    contract_graph(
+        graph,
+        |node| matches!(
+            node.kind,
+            NodeType::Function
+                | NodeType::Main
+                | NodeType::Call
+                | NodeType::UnknownCall
+                | NodeType::UnsafeCall
+                | NodeType::DangerousSink
+                | NodeType::FormatString
+                | NodeType::MemoryOp
+        ),
+        |edge| matches!(edge.kind, EdgeType::Contains | EdgeType::Calls | EdgeType::References),
+    )
+}
+
+// Keep only nodes and edges of the given kinds, where an empty set keeps every kind. Unlike
+// `prune_node_types` nothing is re-attached: an edge survives only when both endpoints do.
+pub fn filter_kinds(
+    graph: &DiGraph<Node, Edge>,
+    node_kinds: &HashSet<NodeType>,
+    edge_kinds: &HashSet<EdgeType>,
+) -> DiGraph<Node, Edge> {
+    graph.filter_map(
+        |_, node| (node_kinds.is_empty() || node_kinds.contains(&node.kind)).then(|| node.clone()),
+        |_, edge| (edge_kinds.is_empty() || edge_kinds.contains(&edge.kind)).then(|| edge.clone()),
+    )
+}
+
+// Keep only the given nodes, along with the edges between them
+pub fn retain_nodes(graph: &DiGraph<Node, Edge>, keep: &HashSet<NodeIndex>) -> DiGraph<Node, Edge> {
+    graph.filter_map(
+        |idx, node| keep.contains(&idx).then(|| node.clone()),
+        |_, edge| Some(edge.clone()),
+    )
+}
+
+// Only the named function, its statements and whatever they call or use, transitively. Empty
+// when no function has that name.
+pub fn subgraph_from_function(graph: &DiGraph<Node, Edge>, name: &str) -> DiGraph<Node, Edge> {
+    match find_function(graph, name) {
+        Some(func_idx) => retain_nodes(graph, &used_from(graph, func_idx)),
+        None => DiGraph::new(),
+    }
+}
+
+// Drop repeated edges, keeping the first of each set with the same endpoints, kind and
+// directness. Several passes can record the same call or use more than once; edges of different
+// kinds between one pair of nodes are distinct and all stay.
+pub fn deduplicate_edges(graph: &DiGraph<Node, Edge>) -> DiGraph<Node, Edge> {
+    let mut seen = HashSet::new();
+    graph.filter_map(
+        |_, node| Some(node.clone()),
+        |edge_idx, edge| {
+            let (source, target) = graph.edge_endpoints(edge_idx)?;
+            seen.insert((source, target, edge.kind.clone(), edge.indirect)).then(|| edge.clone())
+        },
+    )
+}
@@ -1,15 +1,24 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
 // Node types represent the different kinds of entities in our graph
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub enum NodeType {
     Function,           // Function definition
     Main,               // Main function (special case)
     Parameter,          // Function parameter
     BufferParameter,    // Buffer parameter (security risk)
+    #[default]
     Variable,           // Variable declaration
     Pointer,            // Pointer variable
     Array,              // Array variable
+    StringLiteral,      // String literal passed to a call
     Call,               // Function call
+    UnknownCall,        // Call whose callee expression names no function, e.g. `table[i](x)`
     UnsafeCall,         // Call to unsafe function (security risk)
+    DangerousSink,      // Command execution call whose command is built from variables (security risk)
+    FormatString,       // printf-family call whose format string isn't a literal (security risk)
     BasicBlock,         // Code block
     IfStatement,        // If statement
     ForLoop,            // For loop
@@ -18,39 +27,282 @@ pub enum NodeType {
     MemoryOp,           // Memory operation (malloc/free)
     Dereference,        // Pointer dereference
     AddressOf,          // Address-of operation
+    PointerArith,       // New pointer position computed from a pointer, such as `p + i`, `p++` or `&a[i]`
     Cast,               // Type cast
+    UnsafeCast,         // Cast that drops const, reinterprets pointers or narrows (security risk)
     StructAccess,       // Struct field access
     ArrayAccess,        // Array access
+    OutOfBounds,        // Array access with a constant index past the end of the array (security risk)
+    SizeofPointer,      // `sizeof` of a pointer used as the size of a memory operation
+    SizeMisuse,         // Bounded string call sized by something other than its destination (security risk)
+    AllocationOverflow, // Allocation sized by a multiplication of run-time values that can overflow (security risk)
+    Label,              // Goto target label
+    Goto,               // Goto statement
+    EnumDecl,           // Enum type declaration
+    EnumConstant,       // Enumerator inside an enum declaration
+    Class,              // C++ class, linked to its methods by `Defines` edges
+    Namespace,          // C++ namespace, linked to its classes and functions by `Defines` edges
+    TranslationUnit,    // Source file, the root that `Defines` its top-level functions, classes and globals
+}
+
+impl NodeType {
+    pub const ALL: &'static [NodeType] = &[
+        NodeType::Function,
+        NodeType::Main,
+        NodeType::Parameter,
+        NodeType::BufferParameter,
+        NodeType::Variable,
+        NodeType::Pointer,
+        NodeType::Array,
+        NodeType::StringLiteral,
+        NodeType::Call,
+        NodeType::UnknownCall,
+        NodeType::UnsafeCall,
+        NodeType::DangerousSink,
+        NodeType::FormatString,
+        NodeType::BasicBlock,
+        NodeType::IfStatement,
+        NodeType::ForLoop,
+        NodeType::WhileLoop,
+        NodeType::Assignment,
+        NodeType::MemoryOp,
+        NodeType::Dereference,
+        NodeType::AddressOf,
+        NodeType::PointerArith,
+        NodeType::Cast,
+        NodeType::UnsafeCast,
+        NodeType::StructAccess,
+        NodeType::ArrayAccess,
+        NodeType::OutOfBounds,
+        NodeType::SizeofPointer,
+        NodeType::SizeMisuse,
+        NodeType::AllocationOverflow,
+        NodeType::Label,
+        NodeType::Goto,
+        NodeType::EnumDecl,
+        NodeType::EnumConstant,
+        NodeType::Class,
+        NodeType::Namespace,
+        NodeType::TranslationUnit,
+    ];
+
+    // Canonical snake_case name, used in output and accepted by `FromStr`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeType::Function => "function",
+            NodeType::Main => "main",
+            NodeType::Parameter => "parameter",
+            NodeType::BufferParameter => "buffer_parameter",
+            NodeType::Variable => "variable",
+            NodeType::Pointer => "pointer",
+            NodeType::Array => "array",
+            NodeType::StringLiteral => "string_literal",
+            NodeType::Call => "call",
+            NodeType::UnknownCall => "unknown_call",
+            NodeType::UnsafeCall => "unsafe_call",
+            NodeType::DangerousSink => "dangerous_sink",
+            NodeType::FormatString => "format_string",
+            NodeType::BasicBlock => "basic_block",
+            NodeType::IfStatement => "if_statement",
+            NodeType::ForLoop => "for_loop",
+            NodeType::WhileLoop => "while_loop",
+            NodeType::Assignment => "assignment",
+            NodeType::MemoryOp => "memory_op",
+            NodeType::Dereference => "dereference",
+            NodeType::AddressOf => "address_of",
+            NodeType::PointerArith => "pointer_arith",
+            NodeType::Cast => "cast",
+            NodeType::UnsafeCast => "unsafe_cast",
+            NodeType::StructAccess => "struct_access",
+            NodeType::ArrayAccess => "array_access",
+            NodeType::OutOfBounds => "out_of_bounds",
+            NodeType::SizeofPointer => "sizeof_pointer",
+            NodeType::SizeMisuse => "size_misuse",
+            NodeType::AllocationOverflow => "allocation_overflow",
+            NodeType::Label => "label",
+            NodeType::Goto => "goto",
+            NodeType::EnumDecl => "enum_decl",
+            NodeType::EnumConstant => "enum_constant",
+            NodeType::Class => "class",
+            NodeType::Namespace => "namespace",
+            NodeType::TranslationUnit => "translation_unit",
+        }
+    }
+}
+
+impl fmt::Display for NodeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Normalize kind names so `UnsafeCall`, `unsafe_call` and `unsafe-call` all match
+fn normalize_kind_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+// Look up a kind by its canonical name or its CamelCase variant name
+fn parse_kind<T: Clone + fmt::Debug>(
+    s: &str,
+    all: &[T],
+    name: fn(&T) -> &'static str,
+    what: &str,
+) -> Result<T, String> {
+    let wanted = normalize_kind_name(s);
+    all.iter()
+        .find(|kind| {
+            normalize_kind_name(name(kind)) == wanted
+                || normalize_kind_name(&format!("{:?}", kind)) == wanted
+        })
+        .cloned()
+        .ok_or_else(|| {
+            let valid: Vec<&str> = all.iter().map(name).collect();
+            format!("unknown {} type '{}' (expected one of: {})", what, s, valid.join(", "))
+        })
+}
+
+impl FromStr for NodeType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_kind(s, NodeType::ALL, NodeType::as_str, "node")
+    }
 }
 
 // Edge types represent the relationships between nodes
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum EdgeType {
     Contains,   // Parent contains child
     Calls,      // Function call relationship
     Controls,   // Control relationship (unsafe -> function call)
     Uses,       // Usage relationship
     References, // References (e.g., function pointer)
-    Assigns,    // Assignment relationship
+    Assigns,    // Call filling a buffer, such as `fgets`, stores into a variable
+    Writes,     // Assignment stores into its target variable
+    Reads,      // Assignment reads a variable's value
     Points,     // Pointer points to
     Casts,      // Type cast relationship
     Accesses,   // Access relationship (struct/array)
     Allocates,  // Memory allocation
     Frees,      // Memory free
-    Defines,    // Defines a function
+    Defines,    // Translation unit, class or namespace declares a member
+    Jumps,      // Goto jumps to a label
+    Shadows,    // Inner declaration hides an outer one of the same name
+    Returns,    // Function hands a variable's value back to its caller
 }
 
-// Encapsulate node information
-#[derive(Debug, Clone)]
+impl EdgeType {
+    pub const ALL: &'static [EdgeType] = &[
+        EdgeType::Contains,
+        EdgeType::Calls,
+        EdgeType::Controls,
+        EdgeType::Uses,
+        EdgeType::References,
+        EdgeType::Assigns,
+        EdgeType::Writes,
+        EdgeType::Reads,
+        EdgeType::Points,
+        EdgeType::Casts,
+        EdgeType::Accesses,
+        EdgeType::Allocates,
+        EdgeType::Frees,
+        EdgeType::Defines,
+        EdgeType::Jumps,
+        EdgeType::Shadows,
+        EdgeType::Returns,
+    ];
+
+    // Canonical snake_case name, used as the edge label in output and accepted by `FromStr`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeType::Contains => "contains",
+            EdgeType::Calls => "calls",
+            EdgeType::Controls => "controls",
+            EdgeType::Uses => "uses",
+            EdgeType::References => "references",
+            EdgeType::Assigns => "assigns",
+            EdgeType::Writes => "writes",
+            EdgeType::Reads => "reads",
+            EdgeType::Points => "points_to",
+            EdgeType::Casts => "casts",
+            EdgeType::Accesses => "accesses",
+            EdgeType::Allocates => "allocates",
+            EdgeType::Frees => "frees",
+            EdgeType::Defines => "defines",
+            EdgeType::Jumps => "jumps",
+            EdgeType::Shadows => "shadows",
+            EdgeType::Returns => "returns",
+        }
+    }
+}
+
+impl fmt::Display for EdgeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EdgeType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_kind(s, EdgeType::ALL, EdgeType::as_str, "edge")
+    }
+}
+
+// Encapsulate node information. Most nodes only set a name, kind and line; the rest default to
+// absent, zero or false.
+#[derive(Debug, Clone, Default)]
 pub struct Node {
     pub name: String,
     pub kind: NodeType,
     pub line: Option<usize>,
+    pub end_line: Option<usize>,   // Last line of a function, loop, if statement or block
     pub usr: Option<String>,
     pub type_info: Option<String>,
+    pub array_size: Option<usize>, // Element count of a constant-size array
+    pub library: bool,             // Call into, or function declared in, the C standard library or a system header
+    pub external: bool,            // Call to a function that is declared but has no body anywhere in the input
+    pub pointer_depth: u8,         // Levels of indirection of a pointer variable, 0 for everything else
+    pub is_const: bool,            // Pointer whose pointee is `const`, so it must only be read through
+    pub tainted: bool,             // Variable holding outside input, read into it or assigned from such a variable
+    pub signature: Option<Signature>, // Return and parameter types of a function
+    pub complexity: Option<u32>,   // Cyclomatic complexity of a function's body
+    pub arguments: Option<usize>,  // Number of arguments a call passes
+}
+
+impl Node {
+    pub fn new(name: String, kind: NodeType, line: Option<usize>) -> Node {
+        Node { name, kind, line, ..Node::default() }
+    }
 }
 
-#[derive(Debug)]
+// A function's prototype, in declaration order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Signature {
+    pub return_type: String,
+    pub parameters: Vec<SignatureParameter>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub variadic: bool, // Takes `...`, or is declared as `f()` without saying what it takes
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureParameter {
+    pub name: Option<String>, // Prototypes may leave parameters unnamed
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+#[derive(Debug, Clone)]
 pub struct Edge {
     pub kind: EdgeType,
+    pub indirect: bool, // Resolved through a pointer rather than by name, or a `Uses` carried across a call's return
 } 
\ No newline at end of file
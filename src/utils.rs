@@ -1,17 +1,26 @@
-use clang::Entity;
+use std::ops::Range;
+use std::time::Instant;
+use clang::{Entity, EntityKind, Type, TypeKind};
+use clang::token::TokenKind;
 use regex::Regex;
+use log::info;
 
+use crate::types::{Signature, SignatureParameter};
+
+// Key for the set of entities already analyzed. The kind and the position where the entity
+// starts tell apart unnamed entities, such as two anonymous enums, which would otherwise share
+// an id and have the second one skipped.
 pub fn get_entity_id(entity: &Entity) -> String {
-    if let Some(name) = entity.get_name() {
-        if let Some(loc) = entity.get_location() {
-            let file = loc.get_file_location();
-            format!("{}:{}:{}", name, file.line, file.column)
-        } else {
-            name
-        }
-    } else {
-        format!("{:?}", entity.get_kind())
-    }
+    let position = entity.get_range()
+        .map(|range| range.get_start())
+        .or_else(|| entity.get_location())
+        .map(|location| {
+            let file = location.get_file_location();
+            let path = file.file.map(|f| f.get_path().display().to_string()).unwrap_or_default();
+            format!("{}:{}:{}", path, file.line, file.column)
+        })
+        .unwrap_or_default();
+    format!("{:?} {}@{}", entity.get_kind(), entity.get_name().unwrap_or_default(), position)
 }
 
 pub fn is_system_entity(entity: &Entity) -> bool {
@@ -33,8 +42,7 @@ pub fn is_system_entity(entity: &Entity) -> bool {
 
 pub fn is_unsafe_function(name: &str) -> bool {
     let unsafe_functions = [
-        "strcpy", "strcat", "sprintf", "gets", "scanf", "vsprintf", "memcpy", "memmove", "strncpy",
-        "strncat",
+        "strcpy", "strcat", "sprintf", "gets", "scanf", "vsprintf", "memcpy", "memmove",
     ];
 
     unsafe_functions.contains(&name)
@@ -121,6 +129,509 @@ pub fn is_standard_library_function(name: &str) -> bool {
     std_functions.contains(&name)
 }
 
+// Command-injection sinks: functions that run another program named or described by their first
+// argument. Extend this list to have more functions checked.
+pub const COMMAND_SINKS: &[&str] = &[
+    "system", "popen", "execl", "execlp", "execle", "execv", "execvp", "execvpe", "execve",
+    "fexecve",
+];
+
+// Functions that run other programs. Calls to them are kept even when library calls are skipped.
+pub fn is_security_sensitive_function(name: &str) -> bool {
+    COMMAND_SINKS.contains(&name)
+}
+
+// The exec family takes the program's arguments and environment as well as its path, so each of
+// them can carry input into what runs. `system` and `popen` only take the command line.
+pub fn is_exec_function(name: &str) -> bool {
+    name.starts_with("exec") || name == "fexecve"
+}
+
+// Position of the format-string argument for printf-family functions
+pub fn format_argument_index(name: &str) -> Option<usize> {
+    match name {
+        "printf" | "vprintf" => Some(0),
+        "fprintf" | "vfprintf" | "sprintf" | "vsprintf" | "dprintf" | "vdprintf" | "syslog" => Some(1),
+        "snprintf" | "vsnprintf" => Some(2),
+        _ => None,
+    }
+}
+
+// Arguments an input function stores outside data into. The scanf family fills every argument
+// after its format; the others fill the one buffer they are given.
+pub fn input_arguments(name: &str, argument_count: usize) -> Range<usize> {
+    match name {
+        "scanf" => 1..argument_count,
+        "fscanf" | "sscanf" => 2..argument_count,
+        "gets" | "fgets" | "fread" | "getline" | "getdelim" => 0..1,
+        "read" | "recv" | "recvfrom" | "pread" => 1..2,
+        _ => 0..0,
+    }
+}
+
+// Name of a function or C++ method, qualified by its enclosing namespaces and classes such as
+// `geometry::Shape::area`. Plain C functions have no such parents and keep their bare name.
+pub fn qualified_function_name(entity: &Entity) -> Option<String> {
+    let mut name = entity.get_name()?;
+    let mut parent = entity.get_semantic_parent();
+    while let Some(scope) = parent {
+        match scope.get_kind() {
+            EntityKind::Namespace
+            | EntityKind::ClassDecl
+            | EntityKind::StructDecl
+            | EntityKind::UnionDecl
+            | EntityKind::ClassTemplate => {
+                let scope_name = scope.get_name().unwrap_or_else(|| "(anonymous)".to_string());
+                name = format!("{}::{}", scope_name, name);
+            },
+            _ => break,
+        }
+        parent = scope.get_semantic_parent();
+    }
+    Some(name)
+}
+
+// Whether an entity is a function body the graph models: a C function, or a C++ method,
+// constructor or destructor
+pub fn is_function_entity(entity: &Entity) -> bool {
+    matches!(
+        entity.get_kind(),
+        EntityKind::FunctionDecl | EntityKind::Method | EntityKind::Constructor | EntityKind::Destructor
+    )
+}
+
+// Position of the byte-count argument for allocation and memory-copy functions
+pub fn size_argument_index(name: &str) -> Option<usize> {
+    match name {
+        "malloc" => Some(0),
+        "calloc" | "realloc" => Some(1),
+        "memset" | "memcpy" | "memmove" => Some(2),
+        _ => None,
+    }
+}
+
+// The keyword of a `sizeof` or `_Alignof` expression, which libclang reports only as a UnaryExpr
+fn type_trait_keyword(entity: &Entity) -> Option<String> {
+    if entity.get_kind() != EntityKind::UnaryExpr {
+        return None;
+    }
+    entity.get_range()
+        .and_then(|range| range.tokenize().into_iter().next())
+        .map(|token| token.get_spelling())
+}
+
+fn is_sizeof(entity: &Entity) -> bool {
+    type_trait_keyword(entity).as_deref() == Some("sizeof")
+}
+
+// Whether the expression is `sizeof` or `_Alignof` of an operand that is never evaluated, so
+// `sizeof(buf)` reads nothing at run time. `sizeof` of a variable-length array is the exception:
+// its length has to be computed.
+pub fn is_unevaluated(entity: &Entity) -> bool {
+    let is_type_trait = type_trait_keyword(entity)
+        .is_some_and(|keyword| matches!(keyword.as_str(), "sizeof" | "_Alignof" | "alignof" | "__alignof__" | "__alignof"));
+    if !is_type_trait {
+        return false;
+    }
+    let is_variable_length = entity.get_children().into_iter()
+        .find(|child| child.is_expression())
+        .and_then(|operand| operand.get_type())
+        .is_some_and(|ty| ty.get_canonical_type().get_kind() == TypeKind::VariableArray);
+    !is_variable_length
+}
+
+// Whether an expression's value is a pointer, including an array decayed to one
+pub fn is_pointer_expr(entity: &Entity) -> bool {
+    entity.get_type()
+        .is_some_and(|ty| ty.get_canonical_type().get_kind() == TypeKind::Pointer)
+}
+
+// Whether an expression computes a new pointer position from a pointer: `p + i`, `i + p`, `p - q`,
+// `p++`, `--p` or `&a[i]`
+pub fn is_pointer_offset(entity: &Entity) -> bool {
+    let has_pointer_operand = || entity.get_children().iter().any(is_pointer_expr);
+    match entity.get_kind() {
+        EntityKind::BinaryOperator => {
+            matches!(get_binary_operator(entity).as_deref(), Some("+" | "-")) && has_pointer_operand()
+        },
+        EntityKind::UnaryOperator => match entity.get_display_name().as_deref() {
+            Some("++" | "--") => has_pointer_operand(),
+            Some("&") => entity.get_children().first()
+                .is_some_and(|&operand| unwrap_implicit_expr(operand).get_kind() == EntityKind::ArraySubscriptExpr),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// The operand of a `sizeof` expression. `sizeof` of a type name has no operand expression.
+pub fn sizeof_operand<'tu>(entity: &Entity<'tu>) -> Option<Entity<'tu>> {
+    if !is_sizeof(entity) {
+        return None;
+    }
+    entity.get_children().into_iter()
+        .find(|child| child.is_expression())
+        .map(unwrap_implicit_expr)
+}
+
+// The operand of a `sizeof` anywhere in the expression when that operand is a pointer, as in
+// `malloc(sizeof(p))` where `sizeof(*p)` was meant. `sizeof` of a type name has no operand
+// expression and is never reported.
+pub fn sizeof_pointer_operand(entity: Entity) -> Option<Entity> {
+    if let Some(operand) = sizeof_operand(&entity) {
+        let is_pointer = operand.get_type()
+            .is_some_and(|ty| ty.get_canonical_type().get_kind() == TypeKind::Pointer);
+        return is_pointer.then_some(operand);
+    }
+
+    entity.get_children().into_iter().find_map(sizeof_pointer_operand)
+}
+
+// Whether an expression's value is only known at run time: it reads a variable or a field, or
+// calls a function. Literals, enumerators and `sizeof` are constant.
+fn is_runtime_value(entity: &Entity) -> bool {
+    let entity = unwrap_implicit_expr(*entity);
+    match entity.get_kind() {
+        EntityKind::CallExpr | EntityKind::MemberRefExpr => true,
+        EntityKind::DeclRefExpr => entity.get_reference()
+            .is_some_and(|decl| matches!(decl.get_kind(), EntityKind::VarDecl | EntityKind::ParmDecl)),
+        _ if is_sizeof(&entity) => false,
+        _ => entity.get_children().iter().any(is_runtime_value),
+    }
+}
+
+// The first multiplication of two run-time values in a size expression, as in
+// `malloc(count * size)`, whose product can wrap around to a small allocation. A constant
+// factor, as in `malloc(count * sizeof(int))`, is not reported.
+pub fn runtime_multiplication<'tu>(entity: Entity<'tu>) -> Option<Entity<'tu>> {
+    if is_sizeof(&entity) {
+        return None;
+    }
+    if entity.get_kind() == EntityKind::BinaryOperator && get_binary_operator(&entity).as_deref() == Some("*") {
+        let operands = entity.get_children();
+        if operands.len() == 2 && operands.iter().all(is_runtime_value) {
+            return Some(entity);
+        }
+    }
+
+    entity.get_children().into_iter().find_map(runtime_multiplication)
+}
+
+// Positions of the destination and size arguments of a bounded string function
+pub fn bounded_size_arguments(name: &str) -> Option<(usize, usize)> {
+    match name {
+        "strncpy" | "strncat" => Some((0, 2)),
+        "snprintf" | "vsnprintf" => Some((0, 1)),
+        _ => None,
+    }
+}
+
+// The first `strlen(x)` or `sizeof x` in a size expression whose `x` names none of the
+// destination's variables, as in `strncpy(dst, src, strlen(src))` or
+// `snprintf(buf, sizeof(name), ...)`. Measuring the destination, as the
+// `strncat(dst, src, sizeof(dst) - strlen(dst) - 1)` idiom does, is never reported.
+pub fn foreign_size_measure<'tu>(entity: Entity<'tu>, destination: &[String]) -> Option<Entity<'tu>> {
+    let measured = if entity.get_kind() == EntityKind::CallExpr && entity.get_name().as_deref() == Some("strlen") {
+        entity.get_arguments().unwrap_or_default().first().copied()
+    } else {
+        sizeof_operand(&entity)
+    };
+    if let Some(measured) = measured {
+        let variables = referenced_variables(&measured);
+        let is_foreign = !variables.is_empty() && !variables.iter().any(|name| destination.contains(name));
+        return is_foreign.then_some(entity);
+    }
+
+    entity.get_children().into_iter().find_map(|child| foreign_size_measure(child, destination))
+}
+
+// Names of the variables and parameters an expression reads, in source order without repeats.
+// Operands of `sizeof` aren't read.
+pub fn referenced_variables(entity: &Entity) -> Vec<String> {
+    let mut names = Vec::new();
+    collect_referenced_variables(entity, &mut names);
+    names
+}
+
+fn collect_referenced_variables(entity: &Entity, names: &mut Vec<String>) {
+    if is_unevaluated(entity) {
+        return;
+    }
+    if entity.get_kind() == EntityKind::DeclRefExpr {
+        let is_variable = entity.get_reference()
+            .is_some_and(|decl| matches!(decl.get_kind(), EntityKind::VarDecl | EntityKind::ParmDecl));
+        if let Some(name) = entity.get_name().filter(|name| is_variable && !names.contains(name)) {
+            names.push(name);
+        }
+    }
+
+    for child in entity.get_children() {
+        collect_referenced_variables(&child, names);
+    }
+}
+
+// Whether the source scan and call reconnection should consider a call to this function
+pub fn keep_library_call(name: &str, keep_stdlib: bool) -> bool {
+    keep_stdlib || !is_standard_library_function(name) || is_security_sensitive_function(name)
+}
+
+// Return type and ordered parameters of a function declaration
+pub fn function_signature(entity: &Entity) -> Option<Signature> {
+    let function_type = entity.get_type()?;
+    let parameters = entity.get_arguments()
+        .unwrap_or_default()
+        .iter()
+        .map(|param| SignatureParameter {
+            name: param.get_name(),
+            type_name: param.get_type().map(|t| t.get_display_name()).unwrap_or_default(),
+        })
+        .collect();
+
+    // A declaration without a prototype leaves the parameters open; a K&R-style definition still
+    // names them
+    let unprototyped = function_type.get_kind() == TypeKind::FunctionNoPrototype && !entity.is_definition();
+
+    Some(Signature {
+        return_type: function_type.get_result_type()?.get_display_name(),
+        parameters,
+        variadic: function_type.is_variadic() || unprototyped,
+    })
+}
+
+// Count pointer levels down to the underlying type, looking through arrays so `char *names[4]`
+// is one level deep like `char *`
+fn strip_indirection<'tu>(ty: &Type<'tu>) -> (u8, Type<'tu>) {
+    let mut depth = 0;
+    let mut current = ty.get_canonical_type();
+    loop {
+        let next = match current.get_kind() {
+            TypeKind::Pointer => {
+                depth += 1;
+                current.get_pointee_type()
+            },
+            TypeKind::ConstantArray
+            | TypeKind::IncompleteArray
+            | TypeKind::VariableArray
+            | TypeKind::DependentSizedArray => current.get_element_type(),
+            _ => None,
+        };
+        match next {
+            Some(inner) => current = inner.get_canonical_type(),
+            None => return (depth, current),
+        }
+    }
+}
+
+pub fn pointer_depth(ty: &Type) -> u8 {
+    strip_indirection(ty).0
+}
+
+// Whether what the pointer points to is `const`, as for `const char *`, or for an array whether
+// its elements are, as for `const char name[]`. A `char *const` pointer can't be reassigned but
+// still writes through.
+pub fn points_to_const(ty: &Type) -> bool {
+    let ty = ty.get_canonical_type();
+    ty.get_pointee_type()
+        .or_else(|| ty.get_element_type())
+        .is_some_and(|target| target.is_const_qualified())
+}
+
+// Pointers to characters at any depth: `char *`, `char **argv` and `char *names[4]`
+pub fn is_char_buffer(ty: &Type) -> bool {
+    let (depth, base) = strip_indirection(ty);
+    depth > 0 && matches!(base.get_kind(), TypeKind::CharS | TypeKind::CharU | TypeKind::SChar | TypeKind::UChar)
+}
+
+// Label marker for multi-level pointers, so `int **pp` reads `Pointer(**)`; single pointers have none
+pub fn pointer_marker(depth: u8) -> String {
+    if depth > 1 {
+        format!("({})", "*".repeat(depth as usize))
+    } else {
+        String::new()
+    }
+}
+
+// Canonical type name with cv-qualifiers removed, so `const int` and `int` compare equal
+fn unqualified_type_name(ty: &Type) -> String {
+    ty.get_canonical_type()
+        .get_display_name()
+        .replace("const ", "")
+        .replace("volatile ", "")
+}
+
+fn is_floating_type(ty: &Type) -> bool {
+    matches!(
+        ty.get_canonical_type().get_kind(),
+        TypeKind::Float | TypeKind::Double | TypeKind::LongDouble | TypeKind::Float128
+    )
+}
+
+// Casts that commonly hide bugs: dropping `const`, reinterpreting one pointee type as another,
+// converting between pointers and integers, and narrowing arithmetic conversions
+pub fn is_dangerous_cast(source: &Type, target: &Type) -> bool {
+    let source = source.get_canonical_type();
+    let target = target.get_canonical_type();
+    let source_is_pointer = source.get_kind() == TypeKind::Pointer;
+    let target_is_pointer = target.get_kind() == TypeKind::Pointer;
+
+    if source_is_pointer && target_is_pointer {
+        if let (Some(from), Some(to)) = (source.get_pointee_type(), target.get_pointee_type()) {
+            if from.is_const_qualified() && !to.is_const_qualified() {
+                return true;
+            }
+
+            // `void *` is how C spells a generic pointer, so converting through it is expected
+            if from.get_canonical_type().get_kind() == TypeKind::Void
+                || to.get_canonical_type().get_kind() == TypeKind::Void
+            {
+                return false;
+            }
+
+            return unqualified_type_name(&from) != unqualified_type_name(&to);
+        }
+        return false;
+    }
+
+    if source_is_pointer || target_is_pointer {
+        return source.is_integer() || target.is_integer();
+    }
+
+    let source_is_arithmetic = source.is_integer() || is_floating_type(&source);
+    let target_is_arithmetic = target.is_integer() || is_floating_type(&target);
+    if source_is_arithmetic && target_is_arithmetic {
+        if is_floating_type(&source) && target.is_integer() {
+            return true;
+        }
+        if let (Ok(from_size), Ok(to_size)) = (source.get_sizeof(), target.get_sizeof()) {
+            return to_size < from_size;
+        }
+    }
+
+    false
+}
+
+// Block literals (`^{ ... }`) inside an expression, leaving out blocks nested in other blocks
+pub fn block_literals<'tu>(entity: &Entity<'tu>) -> Vec<Entity<'tu>> {
+    if entity.get_kind() == EntityKind::BlockExpr {
+        return vec![*entity];
+    }
+    entity.get_children().iter().flat_map(block_literals).collect()
+}
+
+// Look through implicit conversions and parentheses to the expression underneath
+pub fn unwrap_implicit_expr(entity: Entity) -> Entity {
+    let mut inner = entity;
+    while inner.get_kind() == EntityKind::UnexposedExpr || inner.get_kind() == EntityKind::ParenExpr {
+        match inner.get_children().first() {
+            Some(&child) => inner = child,
+            None => break,
+        }
+    }
+    inner
+}
+
+// Like `unwrap_implicit_expr`, but also looks through explicit casts, so `(void *)name` is `name`
+pub fn unwrap_casts(entity: Entity) -> Entity {
+    let mut inner = unwrap_implicit_expr(entity);
+    while inner.get_kind() == EntityKind::CStyleCastExpr {
+        match inner.get_children().into_iter().rev().find(|child| child.is_expression()) {
+            Some(operand) => inner = unwrap_implicit_expr(operand),
+            None => break,
+        }
+    }
+    inner
+}
+
+// Spelling of a binary or compound-assignment operator. Some libclang versions leave the display
+// name of operator expressions empty, so fall back to the first punctuation token after the LHS.
+pub fn get_binary_operator(entity: &Entity) -> Option<String> {
+    if let Some(name) = entity.get_display_name().filter(|name| !name.is_empty()) {
+        return Some(name);
+    }
+    
+    let lhs_end = entity.get_children().first()?.get_range()?.get_end().get_file_location().offset;
+    entity.get_range()?
+        .tokenize()
+        .into_iter()
+        .find(|token| {
+            token.get_kind() == TokenKind::Punctuation
+                && token.get_location().get_file_location().offset >= lhs_end
+        })
+        .map(|token| token.get_spelling())
+}
+
+// Whether `decl` is the implicit declaration C89 rules give a function called without a
+// prototype. Clang places such a declaration on the call that introduced it.
+pub fn is_implicit_declaration(decl: &Entity, call: &Entity) -> bool {
+    let (Some(location), Some(range)) = (decl.get_location(), call.get_range()) else {
+        return false;
+    };
+    let location = location.get_file_location();
+    let (start, end) = (range.get_start().get_file_location(), range.get_end().get_file_location());
+    location.file == start.file && (start.offset..=end.offset).contains(&location.offset)
+}
+
+// The variable named by `*p`, looking through implicit conversions and parentheses on either side
+// of the `*`. The operator is read from the tokens since the display name can be empty.
+pub fn dereferenced_variable(entity: Entity) -> Option<String> {
+    let entity = unwrap_implicit_expr(entity);
+    if entity.get_kind() != EntityKind::UnaryOperator {
+        return None;
+    }
+    let operator = entity.get_range()?.tokenize().into_iter().next()?.get_spelling();
+    if operator != "*" {
+        return None;
+    }
+    let operand = unwrap_implicit_expr(*entity.get_children().first()?);
+    if operand.get_kind() == EntityKind::DeclRefExpr {
+        operand.get_name()
+    } else {
+        None
+    }
+}
+
+// The variable at the base of `a[i]`, or of `a[i][j]` for a nested subscript
+pub fn subscripted_variable(entity: Entity) -> Option<String> {
+    let entity = unwrap_implicit_expr(entity);
+    match entity.get_kind() {
+        EntityKind::DeclRefExpr => entity.get_name(),
+        EntityKind::ArraySubscriptExpr => subscripted_variable(*entity.get_children().first()?),
+        _ => None,
+    }
+}
+
+// Value of an integer literal such as `10`, `0x1f` or `8u`. The crate is built without the
+// libclang features that expose `evaluate`, so the literal is read from its token.
+pub fn integer_literal_value(entity: Entity) -> Option<u64> {
+    let entity = unwrap_implicit_expr(entity);
+    if entity.get_kind() != EntityKind::IntegerLiteral {
+        return None;
+    }
+    let spelling = entity.get_range()?.tokenize().into_iter().next()?.get_spelling();
+    let digits = spelling.trim_end_matches(['u', 'U', 'l', 'L']).replace('\'', "");
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        u64::from_str_radix(binary, 2).ok()
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8).ok()
+    } else {
+        digits.parse().ok()
+    }
+}
+
+pub fn is_compound_assignment(operator: &str) -> bool {
+    matches!(operator, "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>=")
+}
+
+// Log the start of a phase, with the time since the run began, when progress
+// reporting is on. Stdout stays reserved for the graph.
+pub fn report_progress(start: Option<Instant>, phase: &str) {
+    if let Some(start) = start {
+        info!("[{:>8.3}s] {}", start.elapsed().as_secs_f64(), phase);
+    }
+}
+
 pub fn get_line_number(entity: &Entity) -> Option<usize> {
     entity.get_location().map(|loc| {
         let file_loc = loc.get_file_location();
@@ -128,56 +639,95 @@ pub fn get_line_number(entity: &Entity) -> Option<usize> {
     })
 }
 
+// Line of the last character of the entity's extent, such as a block's closing brace
+pub fn get_end_line(entity: &Entity) -> Option<usize> {
+    entity.get_range().map(|range| range.get_end().get_file_location().line as usize)
+}
+
+// Start of a function definition in column 0, up to its name: the return type as words and `*`s,
+// which may run over several lines, as in `struct buf *` on the line above the name
+const FUNCTION_HEAD: &str = r"(?m)^(?:\w+[\s*]+)+";
+
+// Offset of the `}` closing a block whose body starts at `start`. Braces inside string and
+// character literals or comments don't count.
+fn closing_brace(source: &str, start: usize) -> Option<usize> {
+    let mut depth = 1;
+    let mut chars = source[start..].char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start + i);
+                }
+            },
+            '"' | '\'' => {
+                // Skip to the closing quote, stepping over escaped characters
+                while let Some((_, inner)) = chars.next() {
+                    if inner == '\\' {
+                        chars.next();
+                    } else if inner == c {
+                        break;
+                    }
+                }
+            },
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '/') => {
+                chars.find(|&(_, inner)| inner == '\n');
+            },
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '*') => {
+                chars.next();
+                let mut previous = ' ';
+                for (_, inner) in chars.by_ref() {
+                    if previous == '*' && inner == '/' {
+                        break;
+                    }
+                    previous = inner;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    None
+}
+
 // Extract function calls directly from the source code as a fallback mechanism
-pub fn extract_function_calls_from_source(source_code: &str) -> Vec<(String, String)> {
+pub fn extract_function_calls_from_source(source_code: &str, keep_stdlib: bool) -> Vec<(String, String)> {
     let mut calls = Vec::new();
 
     // First identify all functions
-    let func_regex = Regex::new(r"(?m)^(?:\w+\s+)+(\w+)\s*\([^)]*\)\s*\{").unwrap();
+    let func_regex = Regex::new(&format!(r"{}(\w+)\s*\([^)]*\)\s*\{{", FUNCTION_HEAD)).unwrap();
     let func_names: Vec<String> = func_regex
         .captures_iter(source_code)
         .filter_map(|cap| cap.get(1).map(|m| m.as_str().to_string()))
         .collect();
 
     // Then for each function, find all function calls within it
+    let call_regex = Regex::new(r"(\w+)\s*\(").unwrap();
     for func_name in &func_names {
         // Find the function body
         let func_pattern = format!(
-            r"(?m)^(?:\w+\s+)+{}\s*\([^)]*\)\s*\{{",
+            r"{}{}\s*\([^)]*\)\s*\{{",
+            FUNCTION_HEAD,
             regex::escape(func_name)
         );
         let func_body_regex = Regex::new(&func_pattern).unwrap();
 
         if let Some(func_match) = func_body_regex.find(source_code) {
             let start_pos = func_match.end();
-            let mut depth = 1;
-            let mut end_pos = start_pos;
-
-            // Find the matching closing brace
-            for (i, c) in source_code[start_pos..].char_indices() {
-                if c == '{' {
-                    depth += 1;
-                } else if c == '}' {
-                    depth -= 1;
-                    if depth == 0 {
-                        end_pos = start_pos + i;
-                        break;
-                    }
-                }
-            }
+            let end_pos = closing_brace(source_code, start_pos).unwrap_or(start_pos);
 
             // Extract the function body
             let body = &source_code[start_pos..end_pos];
 
-            // Find function calls in the body
-            let call_regex = Regex::new(r"(\w+)\s*\(").unwrap();
-
             for cap in call_regex.captures_iter(body) {
                 if let Some(callee) = cap.get(1) {
                     let callee_name = callee.as_str().to_string();
 
                     // Skip if the call is to a standard C function that we're not interested in
-                    if is_standard_library_function(&callee_name) {
+                    if !keep_library_call(&callee_name, keep_stdlib) {
                         continue;
                     }
 
@@ -196,64 +746,88 @@ pub fn extract_function_calls_from_source(source_code: &str) -> Vec<(String, Str
     calls
 }
 
-// Specialized function to extract pthread_create handler assignments
-pub fn extract_pthread_assignments(source_code: &str) -> Vec<(String, String)> {
-    let mut assignments = Vec::new();
+// Arguments of the call whose `(` ends just before `start`, split at top-level commas and trimmed.
+// Stops at the closing parenthesis, or at the end of the source for an unterminated call.
+fn call_arguments(source: &str, start: usize) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut argument_start = start;
+    let mut chars = source[start..].char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth > 0 => depth -= 1,
+            ')' => {
+                arguments.push(source[argument_start..start + i].trim());
+                break;
+            },
+            ',' if depth == 0 => {
+                arguments.push(source[argument_start..start + i].trim());
+                argument_start = start + i + 1;
+            },
+            '"' | '\'' => {
+                while let Some((_, inner)) = chars.next() {
+                    if inner == '\\' {
+                        chars.next();
+                    } else if inner == c {
+                        break;
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    arguments
+}
+
+// Calls that hand one of the file's functions to a callback registrar such as `pthread_create` or
+// `qsort`, as `(caller, registrar, handler)`. `registrars` pairs each registering function with
+// the index of its callback argument; the handler may be written as `name` or `&name`.
+pub fn extract_callback_registrations(source_code: &str, registrars: &[(String, usize)]) -> Vec<(String, String, String)> {
+    let mut registrations = Vec::new();
 
     // First identify all functions
-    let func_regex = Regex::new(r"(?m)^(?:\w+\s+)+(\w+)\s*\([^)]*\)\s*\{").unwrap();
+    let func_regex = Regex::new(&format!(r"{}(\w+)\s*\([^)]*\)\s*\{{", FUNCTION_HEAD)).unwrap();
     let func_names: Vec<String> = func_regex
         .captures_iter(source_code)
         .filter_map(|cap| cap.get(1).map(|m| m.as_str().to_string()))
         .collect();
 
-    // Then for each function, look specifically for pthread_create calls
+    // Then for each function, look for calls to the registrars
     for func_name in &func_names {
         // Find the function body
         let func_pattern = format!(
-            r"(?m)^(?:\w+\s+)+{}\s*\([^)]*\)\s*\{{",
+            r"{}{}\s*\([^)]*\)\s*\{{",
+            FUNCTION_HEAD,
             regex::escape(func_name)
         );
         let func_body_regex = Regex::new(&func_pattern).unwrap();
 
         if let Some(func_match) = func_body_regex.find(source_code) {
             let start_pos = func_match.end();
-            let mut depth = 1;
-            let mut end_pos = start_pos;
-
-            // Find the matching closing brace
-            for (i, c) in source_code[start_pos..].char_indices() {
-                if c == '{' {
-                    depth += 1;
-                } else if c == '}' {
-                    depth -= 1;
-                    if depth == 0 {
-                        end_pos = start_pos + i;
-                        break;
-                    }
-                }
-            }
+            let end_pos = closing_brace(source_code, start_pos).unwrap_or(start_pos);
 
             // Extract the function body
             let body = &source_code[start_pos..end_pos];
 
-            // Look specifically for pthread_create with function handlers
-            // Pattern matches: pthread_create(..., handler_func, ...)
-            let pthread_regex =
-                Regex::new(r"pthread_create\s*\([^,]+,\s*[^,]*,\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*,")
-                    .unwrap();
+            for (registrar, argument_index) in registrars {
+                let call_regex = Regex::new(&format!(r"\b{}\s*\(", regex::escape(registrar))).unwrap();
+
+                for call in call_regex.find_iter(body) {
+                    let arguments = call_arguments(body, call.end());
+                    let handler = arguments.get(*argument_index)
+                        .map(|argument| argument.trim_start_matches('&').trim());
 
-            for cap in pthread_regex.captures_iter(body) {
-                if let Some(handler_func) = cap.get(1) {
-                    let handler_name = handler_func.as_str().to_string();
                     // Make sure this is a known function name
-                    if func_names.contains(&handler_name) {
-                        assignments.push((func_name.clone(), handler_name));
+                    if let Some(handler) = handler.filter(|handler| func_names.iter().any(|name| name == handler)) {
+                        registrations.push((func_name.clone(), registrar.clone(), handler.to_string()));
                     }
                 }
             }
         }
     }
 
-    assignments
+    registrations
 }
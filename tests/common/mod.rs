@@ -0,0 +1,104 @@
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use cparser::graph_builder::{build_graph, build_graph_from_files, build_graph_from_source, AnalysisOptions};
+use cparser::types::{Edge, EdgeType, Node, NodeType};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+
+// libclang allows a single `Clang` instance per process and tests run on parallel threads
+static CLANG_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn repo_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+pub fn build_fixture(relative: &str) -> DiGraph<Node, Edge> {
+    build_fixture_with(relative, &AnalysisOptions::default())
+}
+
+pub fn build_fixture_with(relative: &str, options: &AnalysisOptions) -> DiGraph<Node, Edge> {
+    try_build_fixture(relative, options)
+        .unwrap_or_else(|e| panic!("failed to build graph for {}: {:?}", relative, e))
+}
+
+pub fn try_build_fixture(relative: &str, options: &AnalysisOptions) -> anyhow::Result<DiGraph<Node, Edge>> {
+    let _guard = CLANG_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    build_graph(&repo_path(relative), options)
+}
+
+// Several fixtures analyzed together as one program
+pub fn build_fixtures(relatives: &[&str]) -> DiGraph<Node, Edge> {
+    let _guard = CLANG_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let paths: Vec<PathBuf> = relatives.iter().map(|relative| repo_path(relative)).collect();
+    build_graph_from_files(&paths, &AnalysisOptions::default())
+        .unwrap_or_else(|e| panic!("failed to build graph for {:?}: {:?}", relatives, e))
+}
+
+pub fn build_source(name: &str, content: &str) -> DiGraph<Node, Edge> {
+    let _guard = CLANG_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    build_graph_from_source(Path::new(name), content, &AnalysisOptions::default())
+        .unwrap_or_else(|e| panic!("failed to build graph for {}: {:?}", name, e))
+}
+
+// Compare output against tests/golden/<name>. A missing golden file fails the test; set
+// UPDATE_GOLDEN=1 to write it, or to overwrite existing ones after an intended change.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = repo_path("tests/golden").join(name);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap_or_else(|e| panic!("failed to write {:?}: {}", path, e));
+        eprintln!("wrote golden file {:?}", path);
+        return;
+    }
+    assert!(path.exists(), "missing golden file {:?}; run with UPDATE_GOLDEN=1 to record it", path);
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| panic!("failed to read {:?}: {}", path, e));
+    assert!(
+        expected == actual,
+        "output differs from {:?}; rerun with UPDATE_GOLDEN=1 if the change is intended\n--- expected\n{}\n--- actual\n{}",
+        path, expected, actual
+    );
+}
+
+// Index of the only node with this exact name
+pub fn node_named(graph: &DiGraph<Node, Edge>, name: &str) -> NodeIndex {
+    let matches: Vec<NodeIndex> = graph.node_indices().filter(|&idx| graph[idx].name == name).collect();
+    assert_eq!(matches.len(), 1, "expected exactly one node named {:?}, found {}", name, matches.len());
+    matches[0]
+}
+
+pub fn nodes_named(graph: &DiGraph<Node, Edge>, name: &str) -> Vec<NodeIndex> {
+    graph.node_indices().filter(|&idx| graph[idx].name == name).collect()
+}
+
+pub fn count_kind(graph: &DiGraph<Node, Edge>, kind: NodeType) -> usize {
+    graph.node_weights().filter(|node| node.kind == kind).count()
+}
+
+// Whether any node named `from` has an edge of `kind` to any node named `to`
+pub fn has_edge(graph: &DiGraph<Node, Edge>, from: &str, kind: EdgeType, to: &str) -> bool {
+    graph.edge_references().any(|edge| {
+        edge.weight().kind == kind
+            && graph[edge.source()].name == from
+            && graph[edge.target()].name == to
+    })
+}
+
+// Hand-built graphs for the tests that exercise analyses without going through libclang
+pub fn add(graph: &mut DiGraph<Node, Edge>, name: &str, kind: NodeType) -> NodeIndex {
+    graph.add_node(Node::new(name.to_string(), kind, None))
+}
+
+pub fn add_at(graph: &mut DiGraph<Node, Edge>, name: &str, kind: NodeType, line: usize) -> NodeIndex {
+    graph.add_node(Node::new(name.to_string(), kind, Some(line)))
+}
+
+pub fn link(graph: &mut DiGraph<Node, Edge>, from: NodeIndex, to: NodeIndex, kind: EdgeType) {
+    graph.add_edge(from, to, Edge { kind, indirect: false });
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::PathBuf;
+
+use cparser::compile_commands::{find_compile_command, load_compile_commands, CompileCommand};
+
+// A scratch project directory holding a source file and its compilation database
+fn project(name: &str, database: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cparser-compile-commands-{}-{}", name, std::process::id()));
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/main.c"), "int main(void) { return 0; }\n").unwrap();
+    fs::write(dir.join("compile_commands.json"), database.replace("$DIR", &dir.display().to_string())).unwrap();
+    dir
+}
+
+#[test]
+fn argument_lists_drop_the_compiler_source_and_output() {
+    let dir = project("arguments", r#"[
+        {
+            "directory": "$DIR",
+            "file": "src/main.c",
+            "arguments": ["cc", "-Iinclude", "-DDEBUG=1", "-c", "src/main.c", "-o", "build/main.o"]
+        }
+    ]"#);
+
+    let commands = load_compile_commands(&dir.join("compile_commands.json")).unwrap();
+    let command = find_compile_command(&commands, &dir.join("src/../src/main.c")).unwrap();
+    assert_eq!(command.clang_args(), [
+        format!("-working-directory={}", dir.display()),
+        "-Iinclude".to_string(),
+        "-DDEBUG=1".to_string(),
+    ]);
+
+    assert!(find_compile_command(&commands, &dir.join("src/other.c")).is_none());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn command_strings_are_split_like_a_shell() {
+    let command = CompileCommand {
+        directory: PathBuf::from("/work"),
+        file: PathBuf::from("/work/a.c"),
+        arguments: None,
+        command: Some(r#"gcc -DNAME="\"my app\"" -I'dir with space' -std=c99 -c a.c -obuild/a.o"#.to_string()),
+    };
+
+    assert_eq!(command.clang_args(), [
+        "-working-directory=/work",
+        "-DNAME=\"my app\"",
+        "-Idir with space",
+        "-std=c99",
+    ]);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{add, link};
+use cparser::analysis::{complexity_table, cyclomatic_complexity};
+use cparser::types::{EdgeType, NodeType};
+use petgraph::graph::DiGraph;
+
+#[test]
+fn complexity_counts_branches_and_loops_in_the_body_only() {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let helper = add(&mut graph, "helper", NodeType::Function);
+    let block = add(&mut graph, "BasicBlock: entry", NodeType::BasicBlock);
+    let loop_idx = add(&mut graph, "ForLoop", NodeType::ForLoop);
+    let branch = add(&mut graph, "IfStatement", NodeType::IfStatement);
+    let call = add(&mut graph, "Call: helper", NodeType::Call);
+    let helper_branch = add(&mut graph, "IfStatement", NodeType::IfStatement);
+
+    link(&mut graph, main, block, EdgeType::Contains);
+    link(&mut graph, block, loop_idx, EdgeType::Contains);
+    link(&mut graph, loop_idx, branch, EdgeType::Contains);
+    link(&mut graph, branch, call, EdgeType::Contains);
+    link(&mut graph, helper, helper_branch, EdgeType::Contains);
+    // The callee's branches belong to the callee
+    link(&mut graph, call, helper, EdgeType::Calls);
+
+    assert_eq!(cyclomatic_complexity(&graph, main), 3);
+    assert_eq!(cyclomatic_complexity(&graph, helper), 2);
+}
+
+#[test]
+fn complexity_table_lists_the_most_complex_functions_first() {
+    let mut graph = DiGraph::new();
+    let simple = add(&mut graph, "simple", NodeType::Function);
+    let branchy = add(&mut graph, "branchy", NodeType::Function);
+    let also_simple = add(&mut graph, "also_simple", NodeType::Function);
+    add(&mut graph, "prototype_only", NodeType::Function);
+    graph[simple].complexity = Some(1);
+    graph[branchy].complexity = Some(4);
+    graph[also_simple].complexity = Some(1);
+
+    assert_eq!(complexity_table(&graph), [(branchy, 4), (also_simple, 1), (simple, 1)]);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{add, add_at, link};
+use cparser::analysis::{def_use_chain, def_use_chains};
+use cparser::types::{EdgeType, NodeType};
+use petgraph::graph::DiGraph;
+
+#[test]
+fn each_read_pairs_with_the_latest_write_before_it() {
+    let mut graph = DiGraph::new();
+    let x = add_at(&mut graph, "Var: x", NodeType::Variable, 2);
+    let first = add_at(&mut graph, "Assignment", NodeType::Assignment, 3);
+    let read_a = add_at(&mut graph, "Assignment", NodeType::Assignment, 4);
+    let update = add_at(&mut graph, "Assignment: +=", NodeType::Assignment, 5);
+    let read_b = add_at(&mut graph, "Call: printf", NodeType::Call, 6);
+    let unplaced = add(&mut graph, "Call: log", NodeType::Call);
+
+    // Inserted out of order to show the chains follow lines, not graph order
+    link(&mut graph, read_b, x, EdgeType::Uses);
+    link(&mut graph, update, x, EdgeType::Writes);
+    link(&mut graph, update, x, EdgeType::Reads);
+    link(&mut graph, first, x, EdgeType::Writes);
+    link(&mut graph, read_a, x, EdgeType::Reads);
+    link(&mut graph, unplaced, x, EdgeType::Uses);
+
+    assert_eq!(def_use_chains(&graph, x), [(first, read_a), (first, update), (update, read_b)]);
+}
+
+#[test]
+fn reads_before_any_write_have_no_definition() {
+    let mut graph = DiGraph::new();
+    let x = add_at(&mut graph, "Param: x (int)", NodeType::Parameter, 1);
+    let read = add_at(&mut graph, "Assignment", NodeType::Assignment, 2);
+    link(&mut graph, read, x, EdgeType::Reads);
+
+    assert!(def_use_chains(&graph, x).is_empty());
+}
+
+#[test]
+fn chain_by_name_keeps_a_shadowing_local_apart_from_the_parameter() {
+    let mut graph = DiGraph::new();
+    let f = add_at(&mut graph, "f", NodeType::Function, 1);
+    let param = add_at(&mut graph, "Param: x (int)", NodeType::Parameter, 1);
+    let param_read = add_at(&mut graph, "Call: use", NodeType::Call, 2);
+    let param_write = add_at(&mut graph, "Assignment", NodeType::Assignment, 3);
+    let local = add_at(&mut graph, "Var: x (int)", NodeType::Variable, 5);
+    let local_write = add_at(&mut graph, "Assignment", NodeType::Assignment, 6);
+    let local_read = add_at(&mut graph, "Call: use", NodeType::Call, 7);
+    let param_late_read = add_at(&mut graph, "Call: use", NodeType::Call, 9);
+    for node in [param, param_read, param_write, local, local_write, local_read, param_late_read] {
+        link(&mut graph, f, node, EdgeType::Contains);
+    }
+    link(&mut graph, param_read, param, EdgeType::Uses);
+    link(&mut graph, param_write, param, EdgeType::Writes);
+    link(&mut graph, local_write, local, EdgeType::Writes);
+    link(&mut graph, local_read, local, EdgeType::Uses);
+    link(&mut graph, param_late_read, param, EdgeType::Reads);
+
+    // Another function's `x` is not part of `f`'s chain
+    let g = add_at(&mut graph, "g", NodeType::Function, 11);
+    let other = add_at(&mut graph, "Var: x (int)", NodeType::Variable, 12);
+    let other_write = add_at(&mut graph, "Assignment", NodeType::Assignment, 13);
+    let other_read = add_at(&mut graph, "Call: use", NodeType::Call, 14);
+    for node in [other, other_write, other_read] {
+        link(&mut graph, g, node, EdgeType::Contains);
+    }
+    link(&mut graph, other_write, other, EdgeType::Writes);
+    link(&mut graph, other_read, other, EdgeType::Uses);
+
+    assert_eq!(def_use_chain(&graph, "x", "f"), [(local_write, local_read), (param_write, param_late_read)]);
+    assert_eq!(def_use_chain(&graph, "x", "g"), [(other_write, other_read)]);
+    assert!(def_use_chain(&graph, "x", "missing").is_empty());
+    assert!(def_use_chain(&graph, "y", "f").is_empty());
+}
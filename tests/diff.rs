@@ -0,0 +1,86 @@
+mod common;
+
+use common::add;
+use cparser::analysis::{diff_cpg, CpgDiff};
+use cparser::types::{Edge, EdgeType, Node, NodeType};
+use petgraph::graph::DiGraph;
+
+// `main` calling each of `callees`, which are defined as functions, plus one `strcpy` per unsafe copy
+fn program(callees: &[&str], unsafe_copies: usize) -> DiGraph<Node, Edge> {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    for &callee in callees {
+        let func = add(&mut graph, callee, NodeType::Function);
+        let call = add(&mut graph, &format!("Call: {}", callee), NodeType::Call);
+        graph.add_edge(main, call, Edge { kind: EdgeType::Contains, indirect: false });
+        graph.add_edge(call, func, Edge { kind: EdgeType::Calls, indirect: false });
+    }
+    for _ in 0..unsafe_copies {
+        let strcpy = add(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall);
+        graph.add_edge(main, strcpy, Edge { kind: EdgeType::Contains, indirect: false });
+    }
+    graph
+}
+
+#[test]
+fn identical_graphs_have_an_empty_diff() {
+    let graph = program(&["parse", "report"], 1);
+    assert!(diff_cpg(&graph, &graph).is_empty());
+}
+
+#[test]
+fn diff_reports_functions_calls_and_new_unsafe_calls() {
+    let old = program(&["parse", "report"], 1);
+    let new = program(&["parse", "validate"], 3);
+
+    assert_eq!(diff_cpg(&old, &new), CpgDiff {
+        added_functions: vec!["validate".to_string()],
+        removed_functions: vec!["report".to_string()],
+        added_calls: vec![("main".to_string(), "validate".to_string())],
+        removed_calls: vec![("main".to_string(), "report".to_string())],
+        new_unsafe_calls: vec![("main".to_string(), "Unsafe: strcpy".to_string()); 2],
+        node_kinds: vec![("unsafe_call".to_string(), 2)],
+        edge_kinds: vec![("contains".to_string(), 2)],
+        gained: vec![
+            ("main".to_string(), "Call: validate".to_string()),
+            ("main".to_string(), "Unsafe: strcpy".to_string()),
+            ("main".to_string(), "Unsafe: strcpy".to_string()),
+        ],
+        lost: vec![("main".to_string(), "Call: report".to_string())],
+    });
+
+    // Dropping unsafe calls is not reported as new ones
+    assert!(diff_cpg(&new, &old).new_unsafe_calls.is_empty());
+}
+
+#[test]
+fn functions_with_the_same_name_but_different_usrs_are_different_functions() {
+    let old = program(&["helper"], 0);
+    let mut new = program(&["helper"], 0);
+    let helper = new.node_indices().find(|&idx| new[idx].name == "helper").unwrap();
+    new[helper].usr = Some("c:util.c@F@helper".to_string());
+
+    let diff = diff_cpg(&old, &new);
+    assert_eq!(diff.added_functions, ["helper"]);
+    assert_eq!(diff.removed_functions, ["helper"]);
+}
+
+#[test]
+fn diff_counts_kinds_and_the_operations_each_function_gains() {
+    let old = program(&["parse"], 0);
+    let mut new = program(&["parse"], 0);
+    let parse = new.node_indices().find(|&idx| new[idx].name == "parse").unwrap();
+    let malloc = add(&mut new, "MemoryOp: malloc", NodeType::MemoryOp);
+    new.add_edge(parse, malloc, Edge { kind: EdgeType::Contains, indirect: false });
+
+    let diff = diff_cpg(&old, &new);
+    assert_eq!(diff.node_kinds, [("memory_op".to_string(), 1)]);
+    assert_eq!(diff.edge_kinds, [("contains".to_string(), 1)]);
+    assert_eq!(diff.gained, [("parse".to_string(), "MemoryOp: malloc".to_string())]);
+    assert!(diff.lost.is_empty());
+    assert!(diff.added_calls.is_empty());
+
+    let reverse = diff_cpg(&new, &old);
+    assert_eq!(reverse.node_kinds, [("memory_op".to_string(), -1)]);
+    assert_eq!(reverse.lost, [("parse".to_string(), "MemoryOp: malloc".to_string())]);
+}
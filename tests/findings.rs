@@ -0,0 +1,380 @@
+mod common;
+
+use common::{add, add_at, link};
+use cparser::analysis::{collect_findings, find_arity_mismatches, find_format_string_risks, find_leaks, find_taint_flows, tainted_variables, write_findings_jsonl};
+use cparser::types::{Edge, EdgeType, NodeType, Signature, SignatureParameter};
+use petgraph::graph::{DiGraph, NodeIndex};
+use serde_json::Value;
+
+#[test]
+fn findings_are_written_one_json_object_per_line() {
+    let mut graph = DiGraph::new();
+    let buf = add_at(&mut graph, "Var: buf", NodeType::Pointer, 3);
+    let realloc = add_at(&mut graph, "MemoryOp: realloc", NodeType::MemoryOp, 9);
+    let strcpy = add_at(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall, 5);
+    graph.add_edge(buf, realloc, Edge { kind: EdgeType::Allocates, indirect: false });
+    graph.add_edge(realloc, buf, Edge { kind: EdgeType::Frees, indirect: false });
+
+    let findings = collect_findings(&graph, "grow.c");
+    let mut output = Vec::new();
+    write_findings_jsonl(&findings, &mut output).unwrap();
+
+    let lines: Vec<Value> = String::from_utf8(output).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+
+    assert_eq!(lines[0]["rule"], "unsafe-call");
+    assert_eq!(lines[0]["file"], "grow.c");
+    assert_eq!(lines[0]["line"], 5);
+    assert_eq!(lines[0]["nodes"], serde_json::json!([strcpy.index()]));
+    assert!(lines[0]["message"].as_str().unwrap().contains("strcpy"));
+
+    assert_eq!(lines[1]["rule"], "realloc-self-assignment");
+    assert_eq!(lines[1]["line"], 9);
+    assert_eq!(lines[1]["nodes"], serde_json::json!([realloc.index(), buf.index()]));
+}
+
+#[test]
+fn command_sinks_report_the_variables_that_build_the_command() {
+    let mut graph = DiGraph::new();
+    let cmd = add_at(&mut graph, "Pointer: cmd (char *)", NodeType::Pointer, 4);
+    let host = add_at(&mut graph, "Param: host (const char *)", NodeType::Parameter, 2);
+    let sink = add_at(&mut graph, "Sink: system", NodeType::DangerousSink, 6);
+    graph.add_edge(sink, cmd, Edge { kind: EdgeType::Uses, indirect: false });
+    graph.add_edge(sink, host, Edge { kind: EdgeType::Uses, indirect: false });
+
+    let findings = collect_findings(&graph, "ping.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "command-injection");
+    assert_eq!(findings[0].line, Some(6));
+    assert_eq!(findings[0].message, "command passed to 'system' is built from cmd, host");
+    assert_eq!(findings[0].nodes, [sink.index(), cmd.index(), host.index()]);
+}
+
+#[test]
+fn format_string_risks_are_reported_by_line_and_function() {
+    let mut graph = DiGraph::new();
+    let fprintf = add_at(&mut graph, "FormatString: fprintf", NodeType::FormatString, 12);
+    add_at(&mut graph, "FormatString: printf", NodeType::FormatString, 4);
+    add_at(&mut graph, "Call: printf", NodeType::Call, 7);
+
+    assert_eq!(find_format_string_risks(&graph), [
+        (Some(4), "printf".to_string()),
+        (Some(12), "fprintf".to_string()),
+    ]);
+
+    let findings = collect_findings(&graph, "log.c");
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[1].rule, "format-string");
+    assert_eq!(findings[1].message, "format string passed to 'fprintf' is not a literal");
+    assert_eq!(findings[1].nodes, [fprintf.index()]);
+}
+
+#[test]
+fn out_of_bounds_accesses_report_the_array() {
+    let mut graph = DiGraph::new();
+    let array = add_at(&mut graph, "Array: a (int[10])", NodeType::Array, 2);
+    let access = add_at(&mut graph, "OutOfBounds: a[10] (size 10)", NodeType::OutOfBounds, 8);
+    graph.add_edge(access, array, Edge { kind: EdgeType::Accesses, indirect: false });
+
+    let findings = collect_findings(&graph, "bounds.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "out-of-bounds");
+    assert_eq!(findings[0].line, Some(8));
+    assert_eq!(findings[0].message, "out-of-bounds access a[10] (size 10)");
+    assert_eq!(findings[0].nodes, [access.index(), array.index()]);
+}
+
+#[test]
+fn sizeof_pointer_names_the_memory_operation() {
+    let mut graph = DiGraph::new();
+    let memset = add_at(&mut graph, "Call: memset", NodeType::Call, 9);
+    let sizeof = add_at(&mut graph, "SizeofPointer: sizeof(buf)", NodeType::SizeofPointer, 9);
+    graph.add_edge(memset, sizeof, Edge { kind: EdgeType::Contains, indirect: false });
+
+    let findings = collect_findings(&graph, "reset.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "sizeof-pointer");
+    assert_eq!(
+        findings[0].message,
+        "size passed to 'memset' is sizeof(buf), the size of a pointer rather than of what it points to"
+    );
+    assert_eq!(findings[0].nodes, [sizeof.index()]);
+}
+
+#[test]
+fn shadowing_reports_both_declarations() {
+    let mut graph = DiGraph::new();
+    let outer = add_at(&mut graph, "Param: count (int)", NodeType::Variable, 3);
+    let inner = add_at(&mut graph, "Var: count", NodeType::Variable, 7);
+    graph.add_edge(inner, outer, Edge { kind: EdgeType::Shadows, indirect: false });
+
+    let findings = collect_findings(&graph, "loop.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "shadowing");
+    assert_eq!(findings[0].line, Some(7));
+    assert_eq!(findings[0].message, "'count' declared at line 7 shadows the declaration at line 3");
+    assert_eq!(findings[0].nodes, [inner.index(), outer.index()]);
+}
+
+#[test]
+fn size_misuse_names_the_bounded_call() {
+    let mut graph = DiGraph::new();
+    let strncpy = add_at(&mut graph, "Call: strncpy", NodeType::Call, 7);
+    let misuse = add_at(&mut graph, "SizeMisuse: strlen(src)", NodeType::SizeMisuse, 7);
+    graph.add_edge(strncpy, misuse, Edge { kind: EdgeType::Contains, indirect: false });
+
+    let findings = collect_findings(&graph, "copy.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "bounded-size");
+    assert_eq!(
+        findings[0].message,
+        "size passed to 'strncpy' is strlen(src), which measures something other than the destination"
+    );
+}
+
+#[test]
+fn allocation_overflow_suggests_the_checked_allocator() {
+    let mut graph = DiGraph::new();
+    let malloc = add_at(&mut graph, "MemoryOp: malloc", NodeType::MemoryOp, 9);
+    let product = add_at(&mut graph, "AllocationOverflow: count*size", NodeType::AllocationOverflow, 9);
+    graph.add_edge(malloc, product, Edge { kind: EdgeType::Contains, indirect: false });
+    let realloc = add_at(&mut graph, "Call: realloc", NodeType::Call, 11);
+    let grown = add_at(&mut graph, "AllocationOverflow: count*2*size", NodeType::AllocationOverflow, 11);
+    graph.add_edge(realloc, grown, Edge { kind: EdgeType::Contains, indirect: false });
+
+    let findings = collect_findings(&graph, "load.c");
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[0].rule, "allocation-overflow");
+    assert_eq!(findings[0].message, "size passed to 'malloc' is count*size, which can overflow; calloc checks the multiplication");
+    assert_eq!(findings[1].message, "size passed to 'realloc' is count*2*size, which can overflow; reallocarray checks the multiplication");
+}
+
+#[test]
+fn const_pointers_written_through_or_freed_are_reported() {
+    let mut graph = DiGraph::new();
+    let name = add_at(&mut graph, "BufferParam: name (const char *)", NodeType::BufferParameter, 3);
+    graph[name].is_const = true;
+
+    // name[0] = 'x'
+    let store = add_at(&mut graph, "Assignment", NodeType::Assignment, 4);
+    let access = add_at(&mut graph, "ArrayAccess: name[0]", NodeType::ArrayAccess, 4);
+    graph.add_edge(store, name, Edge { kind: EdgeType::Writes, indirect: false });
+    graph.add_edge(store, access, Edge { kind: EdgeType::Contains, indirect: false });
+    graph.add_edge(access, name, Edge { kind: EdgeType::Accesses, indirect: false });
+
+    // name = other
+    let rebind = add_at(&mut graph, "Assignment", NodeType::Assignment, 5);
+    graph.add_edge(rebind, name, Edge { kind: EdgeType::Writes, indirect: false });
+
+    let free = add_at(&mut graph, "MemoryOp: free", NodeType::MemoryOp, 6);
+    graph.add_edge(free, name, Edge { kind: EdgeType::Frees, indirect: false });
+
+    let findings = collect_findings(&graph, "names.c");
+    let reported: Vec<(&str, Option<usize>, &str)> = findings.iter()
+        .map(|finding| (finding.rule, finding.line, finding.message.as_str()))
+        .collect();
+    assert_eq!(reported, [
+        ("const-write", Some(4), "'name' points to const but is written through"),
+        ("const-write", Some(6), "'name' points to const but is freed"),
+    ]);
+}
+
+#[test]
+fn stores_into_const_arrays_are_reported() {
+    let mut graph = DiGraph::new();
+    let banner = add_at(&mut graph, "Array: banner (const char[3])", NodeType::Array, 1);
+    graph[banner].is_const = true;
+
+    // ((char *)banner)[0] = 'V'
+    let store = add_at(&mut graph, "Assignment", NodeType::Assignment, 4);
+    let access = add_at(&mut graph, "ArrayAccess: banner[0]", NodeType::ArrayAccess, 4);
+    graph.add_edge(store, banner, Edge { kind: EdgeType::Writes, indirect: false });
+    graph.add_edge(store, access, Edge { kind: EdgeType::Contains, indirect: false });
+    graph.add_edge(access, banner, Edge { kind: EdgeType::Accesses, indirect: false });
+
+    let findings = collect_findings(&graph, "banner.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "const-write");
+    assert_eq!(findings[0].message, "'banner' is const but is written");
+}
+
+#[test]
+fn arity_mismatches_skip_variadic_functions() {
+    let parameter = |name: &str| SignatureParameter { name: Some(name.to_string()), type_name: "int".to_string() };
+    let mut graph = DiGraph::new();
+    let add_fn = add_at(&mut graph, "Function: add", NodeType::Function, 1);
+    graph[add_fn].signature = Some(Signature {
+        return_type: "int".to_string(),
+        parameters: vec![parameter("a"), parameter("b")],
+        variadic: false,
+    });
+    let log_fn = add_at(&mut graph, "Function: log_msg", NodeType::Function, 3);
+    graph[log_fn].signature = Some(Signature {
+        return_type: "void".to_string(),
+        parameters: vec![parameter("level")],
+        variadic: true,
+    });
+
+    let bad = add_at(&mut graph, "Call: add", NodeType::Call, 8);
+    graph[bad].arguments = Some(3);
+    let good = add_at(&mut graph, "Call: add", NodeType::Call, 9);
+    graph[good].arguments = Some(2);
+    let logged = add_at(&mut graph, "Call: log_msg", NodeType::Call, 10);
+    graph[logged].arguments = Some(4);
+    graph.add_edge(bad, add_fn, Edge { kind: EdgeType::Calls, indirect: false });
+    graph.add_edge(good, add_fn, Edge { kind: EdgeType::Calls, indirect: false });
+    graph.add_edge(logged, log_fn, Edge { kind: EdgeType::Calls, indirect: false });
+
+    assert_eq!(find_arity_mismatches(&graph), [(Some(8), "add".to_string(), 2, 3)]);
+
+    let findings = collect_findings(&graph, "math.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "arity-mismatch");
+    assert_eq!(findings[0].message, "'add' takes 2 arguments but is called with 3");
+    assert_eq!(findings[0].nodes, [bad.index(), add_fn.index()]);
+}
+
+#[test]
+fn input_reaching_an_unsafe_call_is_reported_with_its_source() {
+    let mut graph = DiGraph::new();
+    let line = add_at(&mut graph, "BufferParam: line (char[64])", NodeType::BufferParameter, 3);
+    let copy = add_at(&mut graph, "BufferParam: copy (char *)", NodeType::BufferParameter, 4);
+    let length = add_at(&mut graph, "Var: length", NodeType::Variable, 5);
+    let safe = add_at(&mut graph, "BufferParam: safe (char[8])", NodeType::BufferParameter, 6);
+
+    // fgets(line, sizeof line, stdin)
+    let fgets = add_at(&mut graph, "Call: fgets", NodeType::Call, 7);
+    graph.add_edge(fgets, line, Edge { kind: EdgeType::Assigns, indirect: false });
+    graph.add_edge(fgets, line, Edge { kind: EdgeType::Uses, indirect: false });
+
+    // char *copy = line;
+    graph.add_edge(copy, line, Edge { kind: EdgeType::Uses, indirect: false });
+
+    // length = strlen(copy);
+    let assign = add_at(&mut graph, "Assignment", NodeType::Assignment, 9);
+    let strlen = add_at(&mut graph, "Call: strlen", NodeType::Call, 9);
+    graph.add_edge(assign, length, Edge { kind: EdgeType::Writes, indirect: false });
+    graph.add_edge(assign, strlen, Edge { kind: EdgeType::Contains, indirect: false });
+    graph.add_edge(strlen, copy, Edge { kind: EdgeType::Uses, indirect: false });
+
+    // strcpy(out, copy); strcpy(out, safe);
+    let tainted_copy = add_at(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall, 10);
+    graph.add_edge(tainted_copy, copy, Edge { kind: EdgeType::Uses, indirect: false });
+    let clean_copy = add_at(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall, 11);
+    graph.add_edge(clean_copy, safe, Edge { kind: EdgeType::Uses, indirect: false });
+
+    // system(length) stands in for any sink reached through an assignment
+    let sink = add_at(&mut graph, "Sink: system", NodeType::DangerousSink, 12);
+    graph.add_edge(sink, length, Edge { kind: EdgeType::Uses, indirect: false });
+
+    let origins = tainted_variables(&graph);
+    let mut tainted: Vec<NodeIndex> = origins.keys().copied().collect();
+    tainted.sort();
+    assert_eq!(tainted, [line, copy, length]);
+    assert!(origins.values().all(|&origin| origin == fgets));
+
+    let reported: Vec<(Option<usize>, String)> = collect_findings(&graph, "echo.c").into_iter()
+        .filter(|finding| finding.rule == "tainted-sink")
+        .map(|finding| (finding.line, finding.message))
+        .collect();
+    assert_eq!(reported, [
+        (Some(10), "'copy' reaches 'strcpy' with input read by 'fgets' at line 7".to_string()),
+        (Some(12), "'length' reaches 'system' with input read by 'fgets' at line 7".to_string()),
+    ]);
+}
+
+#[test]
+fn an_unsafe_input_call_does_not_reach_itself() {
+    let mut graph = DiGraph::new();
+    let buf = add_at(&mut graph, "BufferParam: buf (char[16])", NodeType::BufferParameter, 2);
+    let gets = add_at(&mut graph, "Unsafe: gets", NodeType::UnsafeCall, 3);
+    graph.add_edge(gets, buf, Edge { kind: EdgeType::Assigns, indirect: false });
+    graph.add_edge(gets, buf, Edge { kind: EdgeType::Uses, indirect: false });
+
+    assert!(find_taint_flows(&graph).is_empty());
+}
+
+#[test]
+fn frees_under_a_cleanup_label_reached_by_goto_are_not_leaks() {
+    // char *buf = malloc(n); if (!buf) goto out; ... out: free(buf);
+    let mut graph = DiGraph::new();
+    let setup = add_at(&mut graph, "setup", NodeType::Function, 3);
+    let buf = add_at(&mut graph, "Pointer: buf (char *)", NodeType::Pointer, 4);
+    let malloc = add_at(&mut graph, "MemoryOp: malloc", NodeType::MemoryOp, 4);
+    let goto = add_at(&mut graph, "Goto: out", NodeType::Goto, 6);
+    let out = add_at(&mut graph, "Label: out", NodeType::Label, 9);
+    let free = add_at(&mut graph, "Call: free", NodeType::Call, 10);
+    link(&mut graph, setup, buf, EdgeType::Contains);
+    link(&mut graph, buf, malloc, EdgeType::Allocates);
+    link(&mut graph, setup, goto, EdgeType::Contains);
+    link(&mut graph, goto, out, EdgeType::Jumps);
+    link(&mut graph, setup, out, EdgeType::Contains);
+    link(&mut graph, out, free, EdgeType::Contains);
+    link(&mut graph, free, buf, EdgeType::Uses);
+    assert!(find_leaks(&graph).is_empty());
+
+    // Without the cleanup the block is lost
+    graph.remove_node(free);
+    assert_eq!(find_leaks(&graph), [(malloc, buf)]);
+
+    let findings = collect_findings(&graph, "setup.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "memory-leak");
+    assert_eq!(findings[0].line, Some(4));
+    assert_eq!(findings[0].message, "'buf' is allocated by malloc but never freed");
+}
+
+#[test]
+fn returned_allocations_are_handed_on_but_borrowed_ones_are_not() {
+    let mut graph = DiGraph::new();
+    let make = add(&mut graph, "make", NodeType::Function);
+    let name = add(&mut graph, "Pointer: name (char *)", NodeType::Pointer);
+    let malloc = add_at(&mut graph, "MemoryOp: malloc", NodeType::MemoryOp, 2);
+    let strcpy = add(&mut graph, "Call: strcpy", NodeType::Call);
+    graph[strcpy].library = true;
+    link(&mut graph, make, name, EdgeType::Contains);
+    link(&mut graph, name, malloc, EdgeType::Allocates);
+    link(&mut graph, make, strcpy, EdgeType::Contains);
+    link(&mut graph, strcpy, name, EdgeType::Uses);
+    assert_eq!(find_leaks(&graph), [(malloc, name)]);
+
+    link(&mut graph, make, name, EdgeType::Returns);
+    assert!(find_leaks(&graph).is_empty());
+}
+
+#[test]
+fn the_node_controlling_an_unsafe_call_is_not_reported_again() {
+    let mut graph = DiGraph::new();
+    let call = add_at(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall, 4);
+    let controller = add(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall);
+    link(&mut graph, controller, call, EdgeType::Controls);
+
+    let findings = collect_findings(&graph, "copy.c");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].line, Some(4));
+    assert_eq!(findings[0].nodes, [call.index()]);
+}
+
+#[test]
+fn allocations_stored_into_a_member_or_through_a_pointer_are_handed_on() {
+    // char *p = malloc(n); s->buf = p;   and   char *q = malloc(n); *out = q;
+    let mut graph = DiGraph::new();
+    let init = add(&mut graph, "init", NodeType::Function);
+    for (name, target) in [("Pointer: p (char *)", "StructAccess: buf"), ("Pointer: q (char *)", "Pointer: out (char **)")] {
+        let ptr = add(&mut graph, name, NodeType::Pointer);
+        let malloc = add(&mut graph, "MemoryOp: malloc", NodeType::MemoryOp);
+        let store = add(&mut graph, "Assignment", NodeType::Assignment);
+        let target_kind = if target.starts_with("StructAccess") { NodeType::StructAccess } else { NodeType::Pointer };
+        let target = add(&mut graph, target, target_kind);
+        link(&mut graph, init, ptr, EdgeType::Contains);
+        link(&mut graph, ptr, malloc, EdgeType::Allocates);
+        link(&mut graph, init, store, EdgeType::Contains);
+        link(&mut graph, store, target, EdgeType::Contains);
+        link(&mut graph, store, ptr, EdgeType::Reads);
+    }
+
+    assert!(find_leaks(&graph).is_empty());
+}
@@ -0,0 +1,16 @@
+#include <stdlib.h>
+
+struct record {
+    int id;
+    char name[32];
+};
+
+struct record *load(size_t count, size_t size) {
+    char *raw = malloc(count * size);
+    struct record *records = malloc(count * sizeof(struct record));
+    records = realloc(records, count * 2 * size);
+    int *ids = calloc(count, size);
+    free(ids);
+    free(raw);
+    return records;
+}
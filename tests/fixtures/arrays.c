@@ -0,0 +1,15 @@
+#define NAME_LEN 32
+
+typedef int row_t[4];
+
+void fill(int n) {
+    char name[NAME_LEN];
+    row_t row;
+    int matrix[3][5];
+    int scratch[n];
+
+    name[0] = '\0';
+    row[0] = 0;
+    matrix[0][0] = 0;
+    scratch[0] = 0;
+}
@@ -0,0 +1,14 @@
+int retry(int x) {
+    int (^check)(int) = ^(int v) {
+        if (v < 0)
+            goto out;
+        return v;
+    out:
+        return 0;
+    };
+    if (x < 0)
+        goto out;
+    return check(x);
+out:
+    return -1;
+}
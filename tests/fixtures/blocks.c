@@ -0,0 +1,16 @@
+static int clamp(int x) {
+    return x < 0 ? 0 : x;
+}
+
+static int apply(int (^op)(int), int value) {
+    return op(value);
+}
+
+int scale(int factor) {
+    int (^times)(int) = ^(int x) {
+        return clamp(x) * factor;
+    };
+    int doubled;
+    doubled = times(2);
+    return apply(^(int x) { return clamp(x + 1); }, doubled);
+}
@@ -0,0 +1,12 @@
+#include <stdio.h>
+#include <string.h>
+
+void copy_name(const char *src) {
+    char dst[16];
+    char other[64];
+    strncpy(dst, src, strlen(src));
+    strncpy(dst, src, sizeof(dst) - 1);
+    strncat(dst, src, sizeof(dst) - strlen(dst) - 1);
+    snprintf(dst, sizeof(other), "%s", src);
+    snprintf(dst, sizeof(dst), "%s", src);
+}
@@ -0,0 +1,11 @@
+int main(void) {
+    int a[10];
+    int i = 3;
+    int last;
+
+    a[i] = 1;
+    a[9] = 2;
+    a[10] = 3;
+    last = a[0x0c];
+    return last;
+}
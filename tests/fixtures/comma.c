@@ -0,0 +1,11 @@
+int comma(int a, int b) {
+    int x;
+    x = (a++, b);
+    return x;
+}
+
+int statement_expression(int c, int d) {
+    int y;
+    y = ({ int t = c; t + d; });
+    return y;
+}
@@ -0,0 +1,12 @@
+#include <stdio.h>
+#include <stdlib.h>
+
+void ping(const char *host) {
+    char cmd[128];
+    snprintf(cmd, sizeof(cmd), "ping -c 1 %s", host);
+    system(cmd);
+}
+
+void list_tmp(void) {
+    system("ls /tmp");
+}
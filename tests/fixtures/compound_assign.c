@@ -0,0 +1,12 @@
+int accumulate(int count) {
+    int total = 0;
+    int flags = 0;
+
+    for (int i = 0; i < count; i++) {
+        total += i;
+        flags |= 1 << i;
+    }
+
+    total = count;
+    return total + flags;
+}
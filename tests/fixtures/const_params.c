@@ -0,0 +1,12 @@
+#include <stdlib.h>
+
+const char banner[] = "v1";
+char scratchpad[8];
+const char *labels[2];
+
+void release(const char *name, char *scratch, char *const fixed) {
+    scratch[0] = name[0];
+    fixed[1] = 'x';
+    free((void *)name);
+    name = scratch;
+}
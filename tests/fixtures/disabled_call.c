@@ -0,0 +1,10 @@
+static int helper(int value) {
+    return value * 2;
+}
+
+int run(int value) {
+#if 0
+    value = helper(value);
+#endif
+    return value;
+}
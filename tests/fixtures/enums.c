@@ -0,0 +1,17 @@
+enum state { IDLE, RUNNING = 4, DONE };
+
+enum state step(enum state current) {
+    switch (current) {
+    case IDLE:
+        return RUNNING;
+    case RUNNING:
+        return DONE;
+    default:
+        return IDLE;
+    }
+}
+
+int retries(void) {
+    enum { MAX_RETRIES = 3 };
+    return MAX_RETRIES;
+}
@@ -0,0 +1,12 @@
+#include <stdio.h>
+#include <unistd.h>
+
+void run_echo(void) {
+    char line[64];
+    char *args[3];
+    fgets(line, sizeof line, stdin);
+    args[0] = "echo";
+    args[1] = line;
+    args[2] = NULL;
+    execvp("echo", args);
+}
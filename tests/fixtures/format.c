@@ -0,0 +1,15 @@
+#include <stdio.h>
+
+void greet(const char *name) {
+    printf("hello %s\n", name);
+    printf(name);
+}
+
+void log_line(FILE *out, const char *message) {
+    fprintf(out, message);
+    fprintf(out, "%s\n", message);
+}
+
+void render(char *buf, size_t len, const char *fmt) {
+    snprintf(buf, len, fmt, 42);
+}
@@ -0,0 +1,21 @@
+typedef int (*handler_t)(int);
+
+static int twice(int x) {
+    return x * 2;
+}
+
+static int negate(int x) {
+    return -x;
+}
+
+void run(int x) {
+    handler_t h = twice;
+    h(x);
+    h = negate;
+    h(x);
+}
+
+int main(void) {
+    run(3);
+    return 0;
+}
@@ -0,0 +1,20 @@
+#include <stdlib.h>
+
+int setup(size_t size) {
+    char *first = malloc(size);
+    if (!first)
+        goto fail;
+
+    char *second = malloc(size);
+    if (!second)
+        goto free_first;
+
+    free(second);
+    free(first);
+    return 0;
+
+free_first:
+    free(first);
+fail:
+    return -1;
+}
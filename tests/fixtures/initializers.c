@@ -0,0 +1,11 @@
+struct point {
+    int x;
+    int y;
+    int *origin;
+};
+
+int build(int left, int right, int *base) {
+    struct point p = { .x = left, .y = right + 1, .origin = base };
+    int values[] = { left, right, left };
+    return p.x + values[0];
+}
@@ -0,0 +1,15 @@
+#include <stdlib.h>
+
+void first(void) {
+    char *buf = malloc(16);
+    free(buf);
+}
+
+void second(void) {
+    char *buf = malloc(32);
+    {
+        int buf = 0;
+        buf++;
+    }
+    free(buf);
+}
@@ -0,0 +1,18 @@
+#include <stdlib.h>
+
+int sum_squares(int count) {
+    int *values = malloc(count * sizeof(int));
+    int total = 0;
+
+    for (int i = 0; i < count; i++) {
+        values[i] = i * i;
+        total += values[i];
+    }
+
+    free(values);
+    return total;
+}
+
+int main(void) {
+    return sum_squares(4) > 0 ? 0 : 1;
+}
@@ -0,0 +1,7 @@
+int checksum(const char *data) {
+    int sum = 0;
+    while (*data) {
+        sum += *data++;
+    }
+    return sum;
+}
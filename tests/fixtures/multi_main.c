@@ -0,0 +1,8 @@
+#include <stdio.h>
+
+int checksum(const char *data);
+
+int main(void) {
+    printf("%d\n", checksum("abc"));
+    return 0;
+}
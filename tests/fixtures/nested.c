@@ -0,0 +1,13 @@
+void leaf(int value);
+
+void nested(int n) {
+    leaf(0);
+    for (int i = 0; i < n; i++) {
+        if (i % 2 == 0) {
+            while (n > 0) {
+                leaf(n);
+                n--;
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#include <stdio.h>
+#include <string.h>
+
+/* Copies caller-controlled input into a fixed-size stack buffer */
+void copy_name(const char *input) {
+    char name[16];
+    strcpy(name, input);
+    printf("%s\n", name);
+}
+
+int main(int argc, char **argv) {
+    if (argc > 1) {
+        copy_name(argv[1]);
+    }
+    return 0;
+}
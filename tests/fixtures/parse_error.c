@@ -0,0 +1,7 @@
+int scale(int value) {
+    return value * missing_factor;
+}
+
+int main(void) {
+    return scale(2);
+}
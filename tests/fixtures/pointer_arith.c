@@ -0,0 +1,11 @@
+int sum_after_first(const int *values, int count) {
+    const int *end = values + count;
+    const int *cursor = &values[1];
+    int total = 0;
+    while (cursor < end) {
+        total += *cursor;
+        cursor++;
+    }
+    long skipped = end - cursor;
+    return total + (int)skipped;
+}
@@ -0,0 +1,10 @@
+int main(void) {
+    int value = 0;
+    int other = 1;
+    int *p = &value;
+
+    *p = 42;
+    p = &other;
+    *(p) += 2;
+    return value + other;
+}
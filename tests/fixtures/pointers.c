@@ -0,0 +1,11 @@
+int main(int argc, char **argv) {
+    int value = argc;
+    int *p = &value;
+    int **pp = &p;
+    char *names[2];
+    int grid[2][10];
+    int (*row)[10] = grid;
+
+    names[0] = argv[0];
+    return **pp + (names[0] != 0) + row[0][0];
+}
@@ -0,0 +1,9 @@
+#include "prototype.h"
+
+int main(void) {
+    return square(3) == 9 ? 0 : 1;
+}
+
+int square(int x) {
+    return x * x;
+}
@@ -0,0 +1,14 @@
+#include <pthread.h>
+#include <stdio.h>
+
+void *worker(void *arg) {
+    printf("worker started\n");
+    return arg;
+}
+
+int main(void) {
+    pthread_t thread;
+    pthread_create(&thread, NULL, worker, NULL);
+    pthread_join(thread, NULL);
+    return 0;
+}
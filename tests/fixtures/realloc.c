@@ -0,0 +1,30 @@
+#include <stdlib.h>
+
+/* realloc into a separate pointer keeps the original block reachable on failure */
+char *grow_safely(char *buf, size_t size) {
+    char *resized = realloc(buf, size * 2);
+    if (resized == NULL) {
+        free(buf);
+        return NULL;
+    }
+    return resized;
+}
+
+/* If realloc fails here, the only pointer to the original block is overwritten */
+char *grow_unsafely(char *buf, size_t size) {
+    buf = realloc(buf, size * 2);
+    return buf;
+}
+
+int main(void) {
+    char *data = malloc(16);
+    data = grow_safely(data, 16);
+    data = grow_unsafely(data, 32);
+    free(data);
+    return 0;
+}
+
+/* The caller receives the new block, and the old one is released either way */
+char *grow_for_caller(char *buf, size_t size) {
+    return realloc(buf, size);
+}
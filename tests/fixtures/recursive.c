@@ -0,0 +1,10 @@
+unsigned long factorial(unsigned int n) {
+    if (n <= 1) {
+        return 1;
+    }
+    return n * factorial(n - 1);
+}
+
+int main(void) {
+    return factorial(5) == 120 ? 0 : 1;
+}
@@ -0,0 +1,18 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+char *read_name(void) {
+    char *name = malloc(32);
+    fgets(name, 32, stdin);
+    return name;
+}
+
+int main(void) {
+    char *user;
+    char greeting[64];
+    user = read_name();
+    strcpy(greeting, user);
+    free(user);
+    return 0;
+}
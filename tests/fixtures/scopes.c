@@ -0,0 +1,9 @@
+int main(void) {
+    int x = 1;
+    {
+        int x = 2;
+        x += 3;
+    }
+    x += 4;
+    return x;
+}
@@ -0,0 +1,9 @@
+int counter = 0;
+
+int scaled(int counter) {
+    return counter * 2;
+}
+
+void bump(void) {
+    counter++;
+}
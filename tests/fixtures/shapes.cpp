@@ -0,0 +1,30 @@
+namespace geometry {
+
+class Shape {
+public:
+    explicit Shape(int side) : side_(side) {}
+    ~Shape() {}
+
+    int area() const;
+
+private:
+    int side_;
+};
+
+int Shape::area() const {
+    return side_ * side_;
+}
+
+int unit() {
+    return 1;
+}
+
+}
+
+int main() {
+    geometry::Shape square(3);
+    int total = 0;
+    total = square.area();
+    total += geometry::unit();
+    return total;
+}
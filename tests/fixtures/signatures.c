@@ -0,0 +1,18 @@
+struct buffer {
+    char *data;
+};
+
+char *name_of(struct buffer *buf) {
+    return trim(buf->data);
+}
+
+struct buffer *
+make_buffer(void)
+{
+    return allocate_buffer();
+}
+
+static const char **names(int count,
+                          int offset) {
+    return lookup(count + offset);
+}
@@ -0,0 +1,14 @@
+#include <stdlib.h>
+#include <string.h>
+
+void reset(char *buf, char local[16]) {
+    char array[16];
+    char *copy = malloc(sizeof(buf));
+    int *counts = calloc(4, sizeof *counts);
+
+    memset(buf, 0, sizeof(buf));
+    memset(local, 0, sizeof(local));
+    memset(array, 0, sizeof(array));
+    memcpy(copy, buf, 4 * sizeof(char *));
+    free(counts);
+}
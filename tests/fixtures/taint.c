@@ -0,0 +1,19 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+int main(void) {
+    char line[64];
+    char command[64];
+    char label[16] = "ready";
+    char *copy;
+    int count;
+
+    fgets(line, sizeof line, stdin);
+    scanf("%d", &count);
+    copy = line;
+    strcpy(command, copy);
+    system(command);
+    printf("%s %d\n", label, count);
+    return 0;
+}
@@ -0,0 +1,13 @@
+typedef int (*handler_t)(int);
+
+int twice(int x) { return 2 * x; }
+int thrice(int x) { return 3 * x; }
+
+int dispatch(int i, int flag, int value) {
+    handler_t table[2] = { twice, thrice };
+    int a, b;
+
+    a = table[i](value);
+    b = (flag ? twice : thrice)(value);
+    return a + b;
+}
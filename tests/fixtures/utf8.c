@@ -0,0 +1,13 @@
+#include <stdio.h>
+
+/* Greets visitors of the café */
+void greet(const char *name) {
+    const char *message = "Bienvenue au café, \"%s\" ☕🦀\n";
+    printf(message, name);
+}
+
+int main(void) {
+    int café = 1;
+    greet("naïve");
+    return café - 1;
+}
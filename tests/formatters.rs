@@ -0,0 +1,396 @@
+mod common;
+
+use common::{add_at, link};
+use cparser::analysis::Finding;
+use cparser::formatters::{
+    dot_to_ascii, format_graph_as_compact_json, format_graph_as_csv, format_graph_as_dot, format_graph_as_flat_dot,
+    format_graph_as_html, format_graph_as_json, format_graph_as_text, format_graph_as_themed_dot, json_to_ascii, render_report,
+    JsonGraph, RankDir,
+};
+use cparser::theme::Theme;
+use cparser::types::{Edge, EdgeType, Node, NodeType, Signature, SignatureParameter};
+use petgraph::graph::{DiGraph, NodeIndex};
+use serde_json::Value;
+
+const FUNCTION_NAME: &str = "café";
+const VARIABLE_NAME: &str = "Var: message \"naïve\" \\ ☕🦀\nnext";
+
+fn utf8_graph() -> DiGraph<Node, Edge> {
+    let mut graph = DiGraph::new();
+    let func = graph.add_node(Node {
+        name: FUNCTION_NAME.to_string(),
+        kind: NodeType::Function,
+        line: Some(4),
+        type_info: Some("void".to_string()),
+        ..Node::default()
+    });
+    let var = graph.add_node(Node::new(VARIABLE_NAME.to_string(), NodeType::Variable, Some(5)));
+    graph.add_edge(func, var, Edge { kind: EdgeType::Contains, indirect: false });
+    graph
+}
+
+#[test]
+fn dot_labels_escape_quotes_backslashes_and_newlines() {
+    let dot = format_graph_as_dot(&utf8_graph());
+
+    assert!(dot.contains("label=\"café [void]\""));
+    assert!(dot.contains("label=\"Var: message \\\"naïve\\\" \\\\ ☕🦀\\nnext\""));
+
+    // Every statement stays on one line with balanced, unescaped quotes
+    for line in dot.lines().filter(|line| line.contains("label=")) {
+        let unescaped_quotes = line.replace("\\\\", "").replace("\\\"", "").matches('"').count();
+        assert_eq!(unescaped_quotes % 2, 0, "unbalanced quotes in: {}", line);
+    }
+}
+
+#[test]
+fn json_labels_round_trip_utf8() {
+    let json: Value = serde_json::from_str(&format_graph_as_json(&utf8_graph())).unwrap();
+    let labels: Vec<&str> = json["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| node["label"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(labels, vec!["café [void]", VARIABLE_NAME]);
+}
+
+#[test]
+fn ascii_encoding_escapes_every_non_ascii_character() {
+    let graph = utf8_graph();
+
+    let dot = dot_to_ascii(&format_graph_as_dot(&graph));
+    assert!(dot.is_ascii());
+    assert!(dot.contains("caf&#233; [void]"));
+    assert!(dot.contains("&#9749;&#129408;"));
+
+    let json = json_to_ascii(&format_graph_as_json(&graph));
+    assert!(json.is_ascii());
+    assert!(json.contains("caf\\u00e9"));
+    assert!(json.contains("\\u2615\\ud83e\\udd80"));
+
+    // Escaping must not change the decoded content
+    let ascii: Value = serde_json::from_str(&json).unwrap();
+    let utf8: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    assert_eq!(ascii, utf8);
+}
+
+#[test]
+fn json_includes_array_size_only_for_sized_arrays() {
+    let mut graph = utf8_graph();
+    graph.add_node(Node {
+        name: "Array: buf (char [16])".to_string(),
+        kind: NodeType::Array,
+        line: Some(6),
+        type_info: Some("char [16]".to_string()),
+        array_size: Some(16),
+        ..Node::default()
+    });
+
+    let json: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    let nodes = json["nodes"].as_array().unwrap();
+    assert_eq!(nodes[2]["array_size"], 16);
+    assert!(nodes[0].get("array_size").is_none());
+}
+
+#[test]
+fn json_includes_pointer_depth_only_for_pointers() {
+    let mut graph = utf8_graph();
+    graph.add_node(Node {
+        name: "Pointer(**): pp (int **)".to_string(),
+        kind: NodeType::Pointer,
+        line: Some(7),
+        type_info: Some("int **".to_string()),
+        pointer_depth: 2,
+        ..Node::default()
+    });
+
+    let json: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    let nodes = json["nodes"].as_array().unwrap();
+    assert_eq!(nodes[2]["pointer_depth"], 2);
+    assert!(nodes[1].get("pointer_depth").is_none());
+}
+
+#[test]
+fn library_calls_are_styled_apart_from_user_calls() {
+    let mut graph = DiGraph::new();
+    for (name, library) in [("Call: helper", false), ("Call: printf", true)] {
+        graph.add_node(Node {
+            name: name.to_string(),
+            kind: NodeType::Call,
+            library,
+            ..Node::default()
+        });
+    }
+
+    let dot = format_graph_as_dot(&graph);
+    assert!(dot.contains("label=\"Call: helper\", shape=ellipse, fillcolor=\"purple\", style=\"filled\""));
+    assert!(dot.contains("label=\"Call: printf\", shape=ellipse, fillcolor=\"plum\", style=\"filled,dashed\""));
+
+    let json: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    assert_eq!(json["nodes"][0]["group"], "call");
+    assert!(json["nodes"][0].get("library").is_none());
+    assert_eq!(json["nodes"][1]["group"], "library_call");
+    assert_eq!(json["nodes"][1]["library"], true);
+}
+
+#[test]
+fn json_output_deserializes_into_the_typed_schema() {
+    let output = format_graph_as_json(&utf8_graph());
+    let parsed: JsonGraph = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(parsed.nodes.len(), 2);
+    assert_eq!(parsed.nodes[0].id, "function_0");
+    assert_eq!(parsed.nodes[0].label, "café [void]");
+    assert_eq!(parsed.nodes[0].group, "function");
+    assert_eq!(parsed.nodes[0].array_size, None);
+    assert!(!parsed.nodes[0].library);
+
+    assert_eq!(parsed.edges.len(), 1);
+    assert_eq!(parsed.edges[0].from, "function_0");
+    assert_eq!(parsed.edges[0].to, "variable_1");
+    assert_eq!(parsed.edges[0].label, "contains");
+    assert!(!parsed.edges[0].dashes);
+
+    // Keys keep their established order and optional fields stay absent
+    let node_keys: Vec<String> = serde_json::from_str::<Value>(&output).unwrap()["nodes"][0]
+        .as_object().unwrap().keys().cloned().collect();
+    assert_eq!(node_keys, ["group", "id", "label"]);
+    assert_eq!(serde_json::to_string_pretty(&parsed).unwrap(), output);
+}
+
+#[test]
+fn html_embeds_the_json_graph_and_a_legend() {
+    let mut graph = utf8_graph();
+    graph[petgraph::graph::NodeIndex::new(1)].name = "Var: </script>".to_string();
+    let html = format_graph_as_html(&graph);
+
+    assert!(html.contains("vis.min.js"));
+    assert_eq!(html.matches("</script>").count(), 2);
+    assert!(html.contains("style=\"background-color: lightblue\"></span>function</div>"));
+    assert!(html.contains("style=\"background-color: green\"></span>variable</div>"));
+
+    let embedded = html.split_once("const graph = ").unwrap().1.split_once(";\n").unwrap().0;
+    let embedded: Value = serde_json::from_str(embedded).unwrap();
+    let json: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    assert_eq!(embedded, json);
+}
+
+#[test]
+fn compact_json_holds_the_same_document_on_one_line() {
+    let graph = utf8_graph();
+    let compact = format_graph_as_compact_json(&graph);
+
+    assert!(!compact.contains('\n'));
+    assert!(compact.len() < format_graph_as_json(&graph).len());
+    let compact: Value = serde_json::from_str(&compact).unwrap();
+    let pretty: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    assert_eq!(compact, pretty);
+}
+
+#[test]
+fn json_signature_lists_parameters_in_order() {
+    let mut graph = utf8_graph();
+    let func = graph.node_indices().next().unwrap();
+    graph[func].signature = Some(Signature {
+        return_type: "void".to_string(),
+        parameters: vec![
+            SignatureParameter { name: Some("dst".to_string()), type_name: "char *".to_string() },
+            SignatureParameter { name: None, type_name: "int".to_string() },
+        ],
+        variadic: false,
+    });
+
+    let json: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    assert_eq!(json["nodes"][0]["signature"], serde_json::json!({
+        "return_type": "void",
+        "parameters": [
+            { "name": "dst", "type": "char *" },
+            { "name": null, "type": "int" },
+        ],
+    }));
+    assert!(json["nodes"][1].get("signature").is_none());
+
+    // The DOT output is unaffected
+    assert!(!format_graph_as_dot(&graph).contains("dst"));
+}
+
+#[test]
+fn json_includes_complexity_only_for_functions() {
+    let mut graph = utf8_graph();
+    let func = graph.node_indices().next().unwrap();
+    graph[func].complexity = Some(3);
+
+    let json: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    assert_eq!(json["nodes"][0]["complexity"], 3);
+    assert!(json["nodes"][1].get("complexity").is_none());
+}
+
+#[test]
+fn json_includes_start_and_end_only_for_ranges() {
+    let mut graph = utf8_graph();
+    let func = graph.node_indices().next().unwrap();
+    graph[func].end_line = Some(9);
+
+    let json: Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    assert_eq!(json["nodes"][0]["start"], 4);
+    assert_eq!(json["nodes"][0]["end"], 9);
+    assert!(json["nodes"][1].get("start").is_none());
+    assert!(json["nodes"][1].get("end").is_none());
+}
+
+#[test]
+fn dot_groups_function_statements_in_a_cluster() {
+    let graph = utf8_graph();
+    let dot = format_graph_as_dot(&graph);
+
+    let cluster_start = dot.find("subgraph cluster_0 {").expect("function cluster");
+    let cluster_end = cluster_start + dot[cluster_start..].find("\n    }\n").unwrap();
+    let cluster = &dot[cluster_start..cluster_end];
+    assert!(cluster.contains(&format!("label=\"{}\";", FUNCTION_NAME)));
+    assert!(cluster.contains("\n        0 [label="));
+    assert!(cluster.contains("\n        1 [label="));
+    // Edges stay outside the cluster
+    assert!(!cluster.contains("->"));
+    assert!(dot.contains("0 -> 1"));
+}
+
+#[test]
+fn flat_dot_has_no_clusters() {
+    let graph = utf8_graph();
+    let dot = format_graph_as_flat_dot(&graph);
+
+    assert!(!dot.contains("subgraph"));
+    assert!(dot.contains("\n    0 [label="));
+    assert!(dot.contains("\n    1 [label="));
+}
+
+#[test]
+fn csv_lists_nodes_and_edges_with_quoted_fields() {
+    let mut graph = utf8_graph();
+    let func = graph.node_indices().next().unwrap();
+    graph[func].type_info = Some("int (int, char *)".to_string());
+
+    let (nodes, edges) = format_graph_as_csv(&graph);
+
+    assert_eq!(nodes, format!(
+        "id,kind,name,line,type_info\n0,function,{},4,\"int (int, char *)\"\n1,variable,\"{}\",5,\n",
+        FUNCTION_NAME,
+        VARIABLE_NAME.replace('"', "\"\"")
+    ));
+    assert_eq!(edges, "source,target,kind\n0,1,contains\n");
+}
+
+#[test]
+fn themed_dot_overrides_only_what_the_theme_sets() {
+    let graph = utf8_graph();
+    let theme = Theme::parse("[nodes.variable]\ncolor = \"#009e73\"\n\n[edges.contains]\nstyle = \"dotted\"\n").unwrap();
+
+    let dot = format_graph_as_themed_dot(&graph, &theme, true, RankDir::default());
+    assert!(dot.contains("shape=ellipse, fillcolor=\"#009e73\", style=\"filled\""));
+    assert!(dot.contains("0 -> 1 [label=\"contains\", color=\"gray\", style=\"dotted\", weight=1, penwidth=1];"));
+    // Kinds the theme leaves out keep their built-in look
+    assert!(dot.contains("shape=ellipse, fillcolor=\"lightblue\", style=\"filled\""));
+    assert_eq!(format_graph_as_themed_dot(&graph, &Theme::default(), true, RankDir::default()), format_graph_as_dot(&graph));
+}
+
+#[test]
+fn dot_and_json_weigh_edges_alike() {
+    let mut graph = utf8_graph();
+    let func = graph.node_indices().next().unwrap();
+    let var = graph.node_indices().nth(1).unwrap();
+    graph.add_edge(func, var, Edge { kind: EdgeType::Assigns, indirect: false });
+    graph.add_edge(func, func, Edge { kind: EdgeType::Calls, indirect: true });
+    graph.add_edge(func, var, Edge { kind: EdgeType::Writes, indirect: false });
+
+    let dot = format_graph_as_dot(&graph);
+    assert!(dot.contains("0 -> 1 [label=\"contains\", color=\"gray\", weight=1, penwidth=1];"));
+    assert!(dot.contains("0 -> 1 [label=\"assigns\", color=\"black\", weight=2, penwidth=1.5];"));
+    assert!(dot.contains("0 -> 0 [label=\"calls (indirect)\", color=\"blue\", style=\"dashed\", weight=2, penwidth=2];"));
+    assert!(dot.contains("0 -> 1 [label=\"writes\", color=\"firebrick\", weight=2, penwidth=1.5];"));
+
+    let parsed: JsonGraph = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    let weights: Vec<(&str, f64)> = parsed.edges.iter().map(|edge| (edge.label.as_str(), edge.weight)).collect();
+    assert_eq!(weights, [("contains", 1.0), ("assigns", 1.5), ("calls", 2.0), ("writes", 1.5)]);
+}
+
+#[test]
+fn text_lists_edges_by_name_and_disambiguates_by_line() {
+    let mut graph = DiGraph::new();
+    let helper = add_at(&mut graph, "helper", NodeType::Function, 2);
+    let main = add_at(&mut graph, "main", NodeType::Main, 6);
+    let first = add_at(&mut graph, "Call: helper", NodeType::Call, 7);
+    let second = add_at(&mut graph, "Call: helper", NodeType::Call, 12);
+    add_at(&mut graph, "unused", NodeType::Function, 20);
+    link(&mut graph, main, second, EdgeType::Contains);
+    link(&mut graph, main, first, EdgeType::Contains);
+    link(&mut graph, first, helper, EdgeType::Calls);
+    graph.add_edge(second, helper, Edge { kind: EdgeType::Calls, indirect: true });
+
+    assert_eq!(
+        format_graph_as_text(&graph),
+        "Call: helper @L12 --calls (indirect)--> helper\n\
+         Call: helper @L7 --calls--> helper\n\
+         main --contains--> Call: helper @L12\n\
+         main --contains--> Call: helper @L7\n\
+         unused\n"
+    );
+}
+
+#[test]
+fn rankdir_sets_the_layout_direction() {
+    let graph = utf8_graph();
+    assert!(format_graph_as_dot(&graph).contains("rankdir=TB,"));
+
+    let rankdir: RankDir = "lr".parse().unwrap();
+    let dot = format_graph_as_themed_dot(&graph, &Theme::default(), true, rankdir);
+    assert!(dot.contains("graph [fontname=\"Arial\", rankdir=LR, splines=true];"));
+    assert!("sideways".parse::<RankDir>().is_err());
+}
+
+#[test]
+fn report_groups_operations_and_findings_under_their_function() {
+    let mut graph = DiGraph::new();
+    let main = add_at(&mut graph, "main", NodeType::Main, 10);
+    let copy = add_at(&mut graph, "copy_name", NodeType::Function, 2);
+    let block = add_at(&mut graph, "BasicBlock: entry", NodeType::BasicBlock, 2);
+    let strcpy = add_at(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall, 4);
+    let buf = add_at(&mut graph, "Pointer: buf (char *)", NodeType::Pointer, 3);
+    let malloc = add_at(&mut graph, "MemoryOp: malloc", NodeType::MemoryOp, 3);
+    let free = add_at(&mut graph, "MemoryOp: free", NodeType::MemoryOp, 12);
+    let global = add_at(&mut graph, "Var: count", NodeType::Variable, 1);
+    add_at(&mut graph, "puts", NodeType::Function, 0);
+    link(&mut graph, copy, block, EdgeType::Contains);
+    link(&mut graph, block, strcpy, EdgeType::Contains);
+    link(&mut graph, block, buf, EdgeType::Contains);
+    link(&mut graph, buf, malloc, EdgeType::Allocates);
+    link(&mut graph, main, free, EdgeType::Contains);
+
+    let finding = |rule: &'static str, message: &str, line: usize, node: NodeIndex| Finding {
+        rule,
+        message: message.to_string(),
+        file: "copy.c".to_string(),
+        line: Some(line),
+        nodes: vec![node.index()],
+    };
+    let findings = [
+        finding("shadowing", "'count' declared at line 1 shadows the declaration at line 1", 1, global),
+        finding("unsafe-call", "call to unsafe function 'strcpy'", 4, strcpy),
+    ];
+
+    // Functions without a body, like the library's puts, are left out
+    assert_eq!(
+        render_report(&graph, &findings),
+        "copy_name (line 2)\n\
+         \x20   line 3: MemoryOp: malloc\n\
+         \x20   line 4: Unsafe: strcpy\n\
+         \x20   issues:\n\
+         \x20       line 4: unsafe-call: call to unsafe function 'strcpy'\n\
+         main (line 10)\n\
+         \x20   line 12: MemoryOp: free\n\
+         Outside functions\n\
+         \x20   issues:\n\
+         \x20       line 1: shadowing: 'count' declared at line 1 shadows the declaration at line 1\n"
+    );
+}
@@ -0,0 +1,980 @@
+mod common;
+
+use std::path::Path;
+
+use common::{assert_golden, build_fixture, build_fixture_with, build_fixtures, build_source, count_kind, has_edge, node_named, nodes_named, repo_path, try_build_fixture};
+use cparser::analysis::{collect_findings, find_const_violations, find_format_string_risks, find_shadowing, find_taint_flows, function_subgraph, Finding};
+use cparser::formatters::format_graph_as_json;
+use cparser::graph_builder::{is_cpp_source, AnalysisOptions};
+use cparser::types::{Edge, EdgeType, Node, NodeType};
+use petgraph::graph::DiGraph;
+use petgraph::Direction;
+use petgraph::visit::EdgeRef;
+
+fn memory_tracking() -> AnalysisOptions {
+    AnalysisOptions { memory_tracking: true, ..AnalysisOptions::default() }
+}
+
+#[test]
+fn overflow_flags_strcpy_into_stack_buffer() {
+    let graph = build_fixture("tests/fixtures/overflow.c");
+
+    assert_eq!(count_kind(&graph, NodeType::Main), 1);
+    assert_eq!(count_kind(&graph, NodeType::Function), 1);
+    assert!(count_kind(&graph, NodeType::UnsafeCall) >= 1);
+    assert!(!nodes_named(&graph, "Unsafe: strcpy").is_empty());
+    assert!(has_edge(&graph, "Call: copy_name", EdgeType::Calls, "copy_name"));
+
+    assert_golden("overflow.json", &format_graph_as_json(&graph));
+}
+
+#[test]
+fn malloc_free_tracks_the_allocation() {
+    let graph = build_fixture_with("tests/fixtures/malloc_free.c", &memory_tracking());
+
+    let malloc = node_named(&graph, "MemoryOp: malloc");
+    assert!(graph.edges_directed(malloc, Direction::Incoming)
+        .any(|edge| edge.weight().kind == EdgeType::Allocates));
+    assert!(!nodes_named(&graph, "MemoryOp: free").is_empty());
+    assert!(has_edge(&graph, "Call: sum_squares", EdgeType::Calls, "sum_squares"));
+
+    assert_golden("malloc_free.json", &format_graph_as_json(&graph));
+}
+
+#[test]
+fn pthread_create_references_its_start_routine() {
+    let graph = build_fixture("tests/fixtures/pthread.c");
+
+    node_named(&graph, "worker");
+    assert!(has_edge(&graph, "Call: pthread_create", EdgeType::References, "worker"));
+
+    assert_golden("pthread.json", &format_graph_as_json(&graph));
+}
+
+#[test]
+fn recursive_call_connects_back_to_its_function() {
+    let graph = build_fixture("tests/fixtures/recursive.c");
+
+    assert_eq!(count_kind(&graph, NodeType::Function), 1);
+    assert_eq!(count_kind(&graph, NodeType::Main), 1);
+
+    let factorial = node_named(&graph, "factorial");
+    let callers: Vec<_> = graph.edges_directed(factorial, Direction::Incoming)
+        .filter(|edge| edge.weight().kind == EdgeType::Calls)
+        .collect();
+    // One call from main and one from factorial itself
+    assert!(callers.len() >= 2, "expected at least 2 calls into factorial, found {}", callers.len());
+
+    // Every call node naming factorial is connected to it
+    for call in nodes_named(&graph, "Call: factorial") {
+        assert!(graph.edges(call).any(|edge| edge.weight().kind == EdgeType::Calls && edge.target() == factorial));
+    }
+
+    assert_golden("recursive.json", &format_graph_as_json(&graph));
+}
+
+#[test]
+fn realloc_into_the_same_pointer_is_reported() {
+    let graph = build_fixture_with("tests/fixtures/realloc.c", &memory_tracking());
+
+    let findings = cparser::analysis::find_realloc_self_assignments(&graph);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(graph[findings[0].0].line, Some(15));
+
+    // Every realloc frees its argument, whether or not its result is assigned
+    let reallocs = nodes_named(&graph, "MemoryOp: realloc");
+    assert_eq!(reallocs.len(), 3);
+    for realloc in reallocs {
+        assert_eq!(graph.edges(realloc).filter(|edge| edge.weight().kind == EdgeType::Frees).count(), 1);
+    }
+}
+
+#[test]
+fn calls_through_a_function_pointer_follow_its_latest_assignment() {
+    let graph = build_fixture("tests/fixtures/function_pointer.c");
+
+    let twice = node_named(&graph, "twice");
+    let negate = node_named(&graph, "negate");
+
+    // Each call through `h` resolves to whatever `h` held at that point
+    let mut targets: Vec<_> = nodes_named(&graph, "Call: h").into_iter()
+        .flat_map(|call| graph.edges(call)
+            .filter(|edge| edge.weight().kind == EdgeType::Calls)
+            .map(|edge| (edge.target(), edge.weight().indirect))
+            .collect::<Vec<_>>())
+        .collect();
+    targets.sort();
+    assert_eq!(targets, vec![(twice, true), (negate, true)]);
+
+    // The pointer variable itself is never treated as a call target
+    let h = node_named(&graph, "Var: h");
+    assert!(graph.edges_directed(h, Direction::Incoming).all(|edge| edge.weight().kind != EdgeType::Calls));
+}
+
+#[test]
+fn gotos_jump_to_their_labels() {
+    let graph = build_fixture("tests/fixtures/goto.c");
+
+    assert_eq!(count_kind(&graph, NodeType::Label), 2);
+    assert_eq!(count_kind(&graph, NodeType::Goto), 2);
+    assert!(has_edge(&graph, "Goto: fail", EdgeType::Jumps, "Label: fail"));
+    assert!(has_edge(&graph, "Goto: free_first", EdgeType::Jumps, "Label: free_first"));
+
+    // The cleanup call under the label is reachable from the jump
+    let free_first = node_named(&graph, "Label: free_first");
+    assert!(graph.edges(free_first).any(|edge| {
+        edge.weight().kind == EdgeType::Contains && graph[edge.target()].name == "Call: free"
+    }));
+
+    // Both blocks are freed on the way out, whether inline or under the cleanup labels
+    assert!(cparser::analysis::find_leaks(&graph).is_empty());
+}
+
+#[test]
+fn compound_assignments_both_read_and_write_their_target() {
+    let graph = build_fixture("tests/fixtures/compound_assign.c");
+
+    for (operator, target) in [("+=", "Var: total"), ("|=", "Var: flags")] {
+        let assignment = node_named(&graph, &format!("Assignment: {}", operator));
+        let kinds: Vec<EdgeType> = graph.edges(assignment)
+            .filter(|edge| graph[edge.target()].name == target)
+            .map(|edge| edge.weight().kind.clone())
+            .collect();
+        assert!(kinds.contains(&EdgeType::Writes), "{} does not write {}", operator, target);
+        assert!(kinds.contains(&EdgeType::Reads), "{} does not read {}", operator, target);
+    }
+
+    // A plain assignment only writes its target and reads the right-hand side
+    let plain = node_named(&graph, "Assignment");
+    assert!(has_edge(&graph, "Assignment", EdgeType::Writes, "Var: total"));
+    assert!(has_edge(&graph, "Assignment", EdgeType::Reads, "Param: count (int)"));
+    assert!(graph.edges(plain).all(|edge| {
+        !(edge.weight().kind == EdgeType::Reads && graph[edge.target()].name == "Var: total")
+    }));
+}
+
+#[test]
+fn arrays_record_their_constant_element_count() {
+    let graph = build_fixture("tests/fixtures/arrays.c");
+
+    let size_of = |name: &str| {
+        let idx = graph.node_indices()
+            .find(|&idx| graph[idx].kind == NodeType::Array && graph[idx].name.starts_with(&format!("Array: {} ", name)))
+            .unwrap_or_else(|| panic!("no array node for {}", name));
+        graph[idx].array_size
+    };
+
+    assert_eq!(size_of("name"), Some(32));
+    assert_eq!(size_of("matrix"), Some(3));
+    // Variable-length arrays have no constant size
+    assert_eq!(size_of("scratch"), None);
+}
+
+#[test]
+fn fatal_parse_errors_fail_only_when_asked() {
+    // By default a partial graph is still produced
+    assert!(try_build_fixture("tests/fixtures/missing_header.c", &AnalysisOptions::default()).is_ok());
+
+    let strict = AnalysisOptions { fail_on_error: true, ..AnalysisOptions::default() };
+    let err = try_build_fixture("tests/fixtures/missing_header.c", &strict).unwrap_err();
+    assert!(err.to_string().contains("error(s)"), "unexpected error: {}", err);
+}
+
+#[test]
+fn ordinary_parse_errors_fail_only_when_asked() {
+    // The undeclared identifier is an error but not a fatal one
+    let graph = try_build_fixture("tests/fixtures/parse_error.c", &AnalysisOptions::default()).unwrap();
+    assert!(has_edge(&graph, "Call: scale", EdgeType::Calls, "scale"));
+
+    let strict = AnalysisOptions { fail_on_error: true, ..AnalysisOptions::default() };
+    let err = try_build_fixture("tests/fixtures/parse_error.c", &strict).unwrap_err();
+    assert!(err.to_string().contains("1 error(s)"), "unexpected error: {}", err);
+}
+
+#[test]
+fn max_depth_collapses_deep_statements_into_one_placeholder() {
+    let full = build_fixture("tests/fixtures/nested.c");
+    assert_eq!(count_kind(&full, NodeType::WhileLoop), 1);
+
+    let shallow = build_fixture_with(
+        "tests/fixtures/nested.c",
+        &AnalysisOptions { max_depth: Some(1), ..AnalysisOptions::default() },
+    );
+    assert!(shallow.node_count() < full.node_count());
+
+    // The top-level call and loop survive, everything inside the loop is summarized
+    assert_eq!(nodes_named(&shallow, "Call: leaf").len(), 1);
+    assert_eq!(count_kind(&shallow, NodeType::ForLoop), 1);
+    assert_eq!(count_kind(&shallow, NodeType::IfStatement), 0);
+    assert_eq!(count_kind(&shallow, NodeType::WhileLoop), 0);
+    assert_eq!(nodes_named(&shallow, "BasicBlock: ... (depth limit)").len(), 1);
+}
+
+#[test]
+fn max_nodes_truncates_to_a_consistent_partial_graph() {
+    let full = build_fixture("tests/fixtures/nested.c");
+    let limit = 6;
+    let truncated = build_fixture_with(
+        "tests/fixtures/nested.c",
+        &AnalysisOptions { max_nodes: Some(limit), ..AnalysisOptions::default() },
+    );
+
+    assert!(truncated.node_count() >= limit);
+    assert!(truncated.node_count() < full.node_count());
+    // Statements past the limit are dropped, the functions found up front are kept
+    assert_eq!(count_kind(&truncated, NodeType::WhileLoop), 0);
+    node_named(&truncated, "nested");
+    node_named(&truncated, "leaf");
+    assert!(format_graph_as_json(&truncated).starts_with('{'));
+}
+
+// Names of everything the nodes inside `function` have a Uses edge to
+fn uses_inside(graph: &DiGraph<Node, Edge>, function: &str) -> Vec<String> {
+    let func_idx = node_named(graph, function);
+    let mut used: Vec<String> = function_subgraph(graph, func_idx).into_iter()
+        .flat_map(|idx| graph.edges(idx).collect::<Vec<_>>())
+        .filter(|edge| edge.weight().kind == EdgeType::Uses)
+        .map(|edge| graph[edge.target()].name.clone())
+        .collect();
+    used.sort();
+    used.dedup();
+    used
+}
+
+#[test]
+fn parameters_do_not_leak_into_other_functions() {
+    let graph = build_fixture("tests/fixtures/shadowing.c");
+
+    // The parameter shadows the global inside `scaled` only
+    assert_eq!(uses_inside(&graph, "scaled"), ["Param: counter (int)"]);
+    assert_eq!(uses_inside(&graph, "bump"), ["Var: counter"]);
+}
+
+#[test]
+fn same_named_locals_stay_in_their_own_function() {
+    let graph = build_fixture_with("tests/fixtures/locals.c", &memory_tracking());
+
+    for function in ["first", "second"] {
+        let body = function_subgraph(&graph, node_named(&graph, function));
+        let frees: Vec<_> = body.iter()
+            .filter(|&&idx| graph[idx].name == "MemoryOp: free")
+            .flat_map(|&idx| graph.edges(idx).collect::<Vec<_>>())
+            .filter(|edge| edge.weight().kind == EdgeType::Frees)
+            .collect();
+        assert_eq!(frees.len(), 1, "{function} should free exactly one pointer");
+        // The pointer freed is this function's own `buf`, not the other one or the inner int
+        assert!(body.contains(&frees[0].target()));
+        assert_eq!(graph[frees[0].target()].name, "BufferParam: buf (char *)");
+    }
+}
+
+#[test]
+fn calls_resolve_to_the_definition_rather_than_the_prototype() {
+    let graph = build_fixture("tests/fixtures/prototype.c");
+
+    let square = node_named(&graph, "square");
+    assert_eq!(graph[square].line, Some(7));
+    assert!(has_edge(&graph, "Call: square", EdgeType::Calls, "square"));
+    assert!(has_edge(&graph, "square", EdgeType::Contains, "BasicBlock: entry"));
+}
+
+#[test]
+fn calls_into_libc_are_marked_as_library_calls() {
+    let graph = build_fixture("tests/fixtures/overflow.c");
+
+    let printf = node_named(&graph, "Call: printf");
+    assert!(graph[printf].library);
+    let copy_name = node_named(&graph, "Call: copy_name");
+    assert!(!graph[copy_name].library);
+}
+
+#[test]
+fn commands_built_from_variables_are_dangerous_sinks() {
+    let graph = build_fixture("tests/fixtures/command.c");
+
+    // Only the call whose command comes from a variable is a sink; the literal one stays a call
+    assert_eq!(count_kind(&graph, NodeType::DangerousSink), 1);
+    let sink = node_named(&graph, "Sink: system");
+    assert!(graph.edges(sink)
+        .any(|edge| edge.weight().kind == EdgeType::Uses && graph[edge.target()].name.starts_with("Array: cmd ")));
+    assert!(function_subgraph(&graph, node_named(&graph, "ping")).contains(&sink));
+    assert!(function_subgraph(&graph, node_named(&graph, "list_tmp")).contains(&node_named(&graph, "Call: system")));
+}
+
+#[test]
+fn exec_sinks_check_the_argument_vector_as_well_as_the_path() {
+    let graph = build_fixture("tests/fixtures/exec_argv.c");
+
+    // The path is a literal, but the arguments carry the line read from stdin
+    let sink = node_named(&graph, "Sink: execvp");
+    assert!(graph.edges(sink)
+        .any(|edge| edge.weight().kind == EdgeType::Uses && graph[edge.target()].name.starts_with("Array: args ")));
+    assert!(graph[node_named(&graph, "Array: args (char *[3])")].tainted);
+
+    let flows: Vec<(Option<usize>, Option<usize>, String)> = find_taint_flows(&graph).into_iter()
+        .map(|(origin, sink, var_idx)| (graph[origin].line, graph[sink].line, graph[var_idx].name.clone()))
+        .collect();
+    assert_eq!(flows, [(Some(7), Some(11), "Array: args (char *[3])".to_string())]);
+}
+
+#[test]
+fn source_held_in_memory_is_parsed_without_a_file_on_disk() {
+    let graph = build_source("buffer.c", "int twice(int x) { return x * 2; }\nint main(void) { return twice(2); }\n");
+
+    assert!(!repo_path("buffer.c").exists());
+    assert!(has_edge(&graph, "Call: twice", EdgeType::Calls, "twice"));
+}
+
+#[test]
+fn translation_unit_root_defines_top_level_declarations() {
+    let graph = build_source(
+        "root.c",
+        "int counter = 0;\nstatic int bump(void) { int step = 1; counter += step; return counter; }\nint main(void) { return bump(); }\n",
+    );
+
+    assert_eq!(count_kind(&graph, NodeType::TranslationUnit), 1);
+    let root = node_named(&graph, "root.c");
+    assert_eq!(graph[root].kind, NodeType::TranslationUnit);
+
+    let mut defined: Vec<&str> = graph.edges(root)
+        .filter(|edge| edge.weight().kind == EdgeType::Defines)
+        .map(|edge| graph[edge.target()].name.as_str())
+        .collect();
+    defined.sort();
+    // Locals belong to their function, not the file
+    assert_eq!(defined, ["Var: counter", "bump", "main"]);
+}
+
+#[test]
+fn several_files_share_one_graph_and_calls_cross_between_them() {
+    let graph = build_fixtures(&["tests/fixtures/multi_main.c", "tests/fixtures/multi_checksum.c"]);
+
+    // One root per file
+    assert_eq!(count_kind(&graph, NodeType::TranslationUnit), 2);
+    node_named(&graph, &repo_path("tests/fixtures/multi_main.c").display().to_string());
+    node_named(&graph, &repo_path("tests/fixtures/multi_checksum.c").display().to_string());
+
+    // The prototype in one file and the definition in the other are the same function, with the
+    // body from the definition and the call from main linked to it
+    let checksum = node_named(&graph, "checksum");
+    assert_eq!(graph[checksum].line, Some(1));
+    assert!(graph[checksum].complexity.is_some());
+    assert!(has_edge(&graph, "Call: checksum", EdgeType::Calls, "checksum"));
+
+    // Defined in the other file, so not external
+    assert!(!graph[node_named(&graph, "Call: checksum")].external);
+}
+
+#[test]
+fn calls_to_declared_only_functions_are_external() {
+    let graph = build_source(
+        "external.c",
+        "int remote(int x);\n\
+         int local(int x) { return x + 1; }\n\
+         int main(void) { return remote(1) + local(2) + missing(3); }\n",
+    );
+
+    assert!(graph[node_named(&graph, "Call: remote")].external);
+    assert!(!graph[node_named(&graph, "Call: local")].external);
+
+    // An implicit declaration is a typo as far as we know, not an external call
+    assert!(!graph[node_named(&graph, "Call: missing")].external);
+
+    let json: serde_json::Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    let external: Vec<&str> = json["nodes"].as_array().unwrap().iter()
+        .filter(|node| node["external"] == true)
+        .map(|node| node["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(external, ["Call: remote"]);
+}
+
+#[test]
+fn functions_return_their_variables() {
+    let graph = build_source(
+        "returns.c",
+        "#include <stdlib.h>\n\
+         char *dup(int n, char *fallback) {\n\
+             char *copy = malloc(n);\n\
+             if (!copy) { return (char *)fallback; }\n\
+             return copy;\n\
+         }\n\
+         int count(void) { return 3; }\n",
+    );
+
+    let returned = |function: &str| {
+        let func_idx = node_named(&graph, function);
+        let mut names: Vec<&str> = graph.edges(func_idx)
+            .filter(|edge| edge.weight().kind == EdgeType::Returns)
+            .map(|edge| graph[edge.target()].name.as_str())
+            .collect();
+        names.sort();
+        names
+    };
+
+    // Returns inside a branch and through a cast belong to the function, not the statements
+    // holding them
+    assert_eq!(returned("dup"), ["BufferParam: copy (char *)", "BufferParam: fallback (char *)"]);
+    assert!(returned("count").is_empty());
+    assert_eq!(graph.edge_weights().filter(|edge| edge.kind == EdgeType::Returns).count(), 2);
+}
+
+#[test]
+fn input_taints_the_variables_it_reaches() {
+    let graph = build_fixture("tests/fixtures/taint.c");
+
+    assert!(has_edge(&graph, "Call: fgets", EdgeType::Assigns, "Array: line (char[64])"));
+    assert!(has_edge(&graph, "Unsafe: scanf", EdgeType::Assigns, "Var: count"));
+
+    let tainted = |name: &str| graph[node_named(&graph, name)].tainted;
+    assert!(tainted("Array: line (char[64])"));
+    assert!(tainted("Var: count"));
+    assert!(tainted("BufferParam: copy (char *)"));
+    assert!(!tainted("Array: label (char[16])"));
+
+    // strcpy copies the input, but the copy doesn't carry taint on into `command`
+    let flows: Vec<(Option<usize>, Option<usize>, String)> = find_taint_flows(&graph).into_iter()
+        .map(|(origin, sink, var_idx)| (graph[origin].line, graph[sink].line, graph[var_idx].name.clone()))
+        .collect();
+    assert_eq!(flows, [(Some(12), Some(15), "BufferParam: copy (char *)".to_string())]);
+}
+
+#[test]
+fn assigned_call_results_use_what_the_callee_returns() {
+    let graph = build_fixture("tests/fixtures/return_flow.c");
+
+    let user = node_named(&graph, "BufferParam: user (char *)");
+    let name = node_named(&graph, "BufferParam: name (char *)");
+    assert!(graph.edges_connecting(user, name)
+        .any(|edge| edge.weight().kind == EdgeType::Uses && edge.weight().indirect));
+
+    // The input read inside `read_name` reaches the caller's strcpy through the return value
+    assert!(graph[user].tainted);
+    let flows: Vec<(Option<usize>, Option<usize>, String)> = find_taint_flows(&graph).into_iter()
+        .map(|(origin, sink, var_idx)| (graph[origin].line, graph[sink].line, graph[var_idx].name.clone()))
+        .collect();
+    assert_eq!(flows, [(Some(7), Some(15), "BufferParam: user (char *)".to_string())]);
+}
+
+#[test]
+fn initializer_calls_get_a_node_and_use_what_the_callee_returns() {
+    let graph = build_source(
+        "init_return.c",
+        "int *make(void) {\n    static int cell;\n    int *slot = &cell;\n    return slot;\n}\n\nint main(void) {\n    int *p = make();\n    return *p;\n}\n",
+    );
+
+    // `T *p = f();` holds the call the way an assignment would
+    assert!(has_edge(&graph, "Pointer: p (int *)", EdgeType::Contains, "Call: make"));
+    assert!(has_edge(&graph, "Call: make", EdgeType::Calls, "make"));
+
+    let p = node_named(&graph, "Pointer: p (int *)");
+    let slot = node_named(&graph, "Pointer: slot (int *)");
+    assert!(graph.edges_connecting(p, slot)
+        .any(|edge| edge.weight().kind == EdgeType::Uses && edge.weight().indirect));
+}
+
+#[test]
+fn unbraced_loop_bodies_are_processed() {
+    let graph = build_source(
+        "unbraced_loops.c",
+        "#include <stdlib.h>\n\nint drain(char **a, int n, int x) {\n    for (int i = 0; i < n; i++)\n        free(a[i]);\n    while (x)\n        goto out;\n    return 0;\nout:\n    return 1;\n}\n",
+    );
+
+    assert!(has_edge(&graph, "For loop", EdgeType::Contains, "BasicBlock: loop body"));
+    assert!(has_edge(&graph, "While loop", EdgeType::Contains, "BasicBlock: loop body"));
+    assert!(has_edge(&graph, "BasicBlock: loop body", EdgeType::Contains, "Call: free"));
+    assert!(has_edge(&graph, "BasicBlock: loop body", EdgeType::Contains, "Goto: out"));
+    assert!(has_edge(&graph, "Goto: out", EdgeType::Jumps, "Label: out"));
+}
+
+#[test]
+fn sizeof_operands_are_not_read() {
+    let graph = build_source(
+        "sizeof_uses.c",
+        "int measure(int extra) {\n    char buf[16];\n    int n = sizeof(buf);\n    int total = extra + sizeof buf;\n    return n + total;\n}\n",
+    );
+
+    let uses = |name: &str| -> Vec<&str> {
+        graph.edges(node_named(&graph, name))
+            .filter(|edge| edge.weight().kind == EdgeType::Uses)
+            .map(|edge| graph[edge.target()].name.as_str())
+            .collect()
+    };
+    assert!(uses("Var: n").is_empty(), "n uses {:?}", uses("Var: n"));
+    assert_eq!(uses("Var: total"), ["Param: extra (int)"]);
+}
+
+#[test]
+fn pointers_to_const_are_marked_and_frees_flagged() {
+    let graph = build_fixture_with("tests/fixtures/const_params.c", &memory_tracking());
+
+    // Only the pointee's qualifier counts, not the pointer's own
+    assert!(graph[node_named(&graph, "BufferParam: name (const char *)")].is_const);
+    assert!(!graph[node_named(&graph, "BufferParam: scratch (char *)")].is_const);
+    assert!(!graph[node_named(&graph, "BufferParam: fixed (char *const)")].is_const);
+
+    // Freeing it is flagged; pointing it somewhere else is not
+    let flagged: Vec<(Option<usize>, String)> = find_const_violations(&graph).into_iter()
+        .map(|(op_idx, ptr_idx)| (graph[op_idx].line, graph[ptr_idx].name.clone()))
+        .collect();
+    assert_eq!(flagged, [(Some(10), "BufferParam: name (const char *)".to_string())]);
+
+    // Arrays are const when their elements are; an array of pointers to const is not
+    assert!(graph[node_named(&graph, "Array: banner (const char[3])")].is_const);
+    assert!(!graph[node_named(&graph, "Array: scratchpad (char[8])")].is_const);
+    assert!(!graph[node_named(&graph, "Array: labels (const char *[2])")].is_const);
+}
+
+#[test]
+fn block_bodies_become_nested_functions() {
+    let mut options = AnalysisOptions::default();
+    options.clang_args.push("-fblocks".to_string());
+    let graph = build_fixture_with("tests/fixtures/blocks.c", &options);
+
+    let stored = node_named(&graph, "<block at line 10>");
+    let passed = node_named(&graph, "<block at line 15>");
+    let calls_clamp = |block| function_subgraph(&graph, block).into_iter().any(|idx| graph[idx].name == "Call: clamp");
+    assert!(calls_clamp(stored));
+    assert!(calls_clamp(passed));
+
+    // Both sit inside the function that wrote them
+    let scale = function_subgraph(&graph, node_named(&graph, "scale"));
+    assert!(scale.contains(&stored) && scale.contains(&passed));
+
+    // A call through the variable holding a block reaches it
+    assert!(has_edge(&graph, "Call: times", EdgeType::Calls, "<block at line 10>"));
+}
+
+#[test]
+fn gotos_stay_within_their_own_block_or_function() {
+    let mut options = AnalysisOptions::default();
+    options.clang_args.push("-fblocks".to_string());
+    let graph = build_fixture_with("tests/fixtures/block_labels.c", &options);
+
+    // Both bodies have an `out:` label; each goto reaches only its own
+    let mut jumps: Vec<(Option<usize>, Option<usize>)> = graph.edge_references()
+        .filter(|edge| edge.weight().kind == EdgeType::Jumps)
+        .map(|edge| (graph[edge.source()].line, graph[edge.target()].line))
+        .collect();
+    jumps.sort();
+    assert_eq!(jumps, [(Some(4), Some(6)), (Some(10), Some(12))]);
+}
+
+#[test]
+fn unnamed_siblings_are_each_processed() {
+    let graph = build_source(
+        "unnamed.c",
+        "enum { RED, GREEN };\nenum { SMALL, LARGE };\nvoid scopes(void) {\n    { int first = RED; }\n    { int second = SMALL; }\n}\n",
+    );
+
+    for name in ["EnumConstant: RED = 0", "EnumConstant: LARGE = 1", "Var: first", "Var: second"] {
+        assert_eq!(nodes_named(&graph, name).len(), 1, "{}", name);
+    }
+}
+
+#[test]
+fn non_literal_format_strings_are_flagged() {
+    let graph = build_fixture("tests/fixtures/format.c");
+
+    assert_eq!(find_format_string_risks(&graph), [
+        (Some(5), "printf".to_string()),
+        (Some(9), "fprintf".to_string()),
+        (Some(14), "snprintf".to_string()),
+    ]);
+    // Calls with a literal format stay ordinary calls
+    assert_eq!(nodes_named(&graph, "Call: printf").len(), 1);
+    assert_eq!(nodes_named(&graph, "Call: fprintf").len(), 1);
+
+    // ...and their literal is a node of its own, which the flagged calls lack
+    assert!(has_edge(&graph, "Call: printf", EdgeType::Contains, "String: \"hello %s\\n\""));
+    assert!(has_edge(&graph, "Call: fprintf", EdgeType::Contains, "String: \"%s\\n\""));
+    assert_eq!(count_kind(&graph, NodeType::StringLiteral), 2);
+}
+
+#[test]
+fn pointer_levels_are_counted_and_labelled() {
+    let graph = build_fixture("tests/fixtures/pointers.c");
+
+    let depth_of = |name: &str| graph[node_named(&graph, name)].pointer_depth;
+    assert_eq!(depth_of("BufferParam(**): argv (char **)"), 2);
+    assert_eq!(depth_of("Pointer: p (int *)"), 1);
+    assert_eq!(depth_of("Pointer(**): pp (int **)"), 2);
+    // An array of strings is an array whose elements are one level deep
+    assert_eq!(depth_of("Array: names (char *[2])"), 1);
+    assert_eq!(depth_of("Var: value"), 0);
+
+    // The outermost type decides between array and pointer
+    let names = node_named(&graph, "Array: names (char *[2])");
+    assert_eq!(graph[names].kind, NodeType::Array);
+    assert_eq!(graph[names].array_size, Some(2));
+    let row = node_named(&graph, "Pointer: row (int (*)[10])");
+    assert_eq!(graph[row].kind, NodeType::Pointer);
+    assert_eq!(graph[row].array_size, None);
+    assert_eq!(graph[node_named(&graph, "Array: grid (int[2][10])")].array_size, Some(2));
+}
+
+#[test]
+fn pointer_arithmetic_uses_its_base_and_offset() {
+    let graph = build_fixture("tests/fixtures/pointer_arith.c");
+
+    let mut arithmetic: Vec<(Option<usize>, &str)> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::PointerArith)
+        .map(|idx| (graph[idx].line, graph[idx].name.as_str()))
+        .collect();
+    arithmetic.sort();
+    assert_eq!(arithmetic, [
+        (Some(2), "PointerArith: values+count"),
+        (Some(3), "PointerArith: &values[1]"),
+        (Some(7), "PointerArith: cursor++"),
+        (Some(9), "PointerArith: end-cursor"),
+    ]);
+
+    assert!(has_edge(&graph, "PointerArith: values+count", EdgeType::Uses, "Pointer: values (const int *)"));
+    assert!(has_edge(&graph, "PointerArith: values+count", EdgeType::Uses, "Param: count (int)"));
+    assert!(has_edge(&graph, "PointerArith: &values[1]", EdgeType::Uses, "Pointer: values (const int *)"));
+    assert!(has_edge(&graph, "PointerArith: cursor++", EdgeType::Uses, "Pointer: cursor (const int *)"));
+    assert!(has_edge(&graph, "PointerArith: end-cursor", EdgeType::Uses, "Pointer: end (const int *)"));
+
+    // Taking the address of an element doesn't read it
+    assert_eq!(count_kind(&graph, NodeType::ArrayAccess), 0);
+}
+
+#[test]
+fn enums_contain_their_constants_and_references_resolve_to_them() {
+    let graph = build_fixture("tests/fixtures/enums.c");
+
+    assert!(has_edge(&graph, "Enum: state", EdgeType::Contains, "EnumConstant: IDLE = 0"));
+    assert!(has_edge(&graph, "Enum: state", EdgeType::Contains, "EnumConstant: RUNNING = 4"));
+    assert!(has_edge(&graph, "Enum: state", EdgeType::Contains, "EnumConstant: DONE = 5"));
+
+    // Every constant the state machine mentions links back to its declaration
+    let step = function_subgraph(&graph, node_named(&graph, "step"));
+    for constant in ["EnumConstant: IDLE = 0", "EnumConstant: RUNNING = 4", "EnumConstant: DONE = 5"] {
+        let constant_idx = node_named(&graph, constant);
+        assert!(
+            graph.edges_directed(constant_idx, Direction::Incoming)
+                .any(|edge| edge.weight().kind == EdgeType::Uses && step.contains(&edge.source())),
+            "{} is not used inside step",
+            constant
+        );
+    }
+
+    // An enum declared inside a function belongs to it
+    let retries = function_subgraph(&graph, node_named(&graph, "retries"));
+    assert!(retries.contains(&node_named(&graph, "Enum: (anonymous)")));
+    assert!(retries.contains(&node_named(&graph, "EnumConstant: MAX_RETRIES = 3")));
+}
+
+#[test]
+fn function_nodes_carry_their_signature() {
+    let graph = build_fixture("tests/fixtures/pointers.c");
+
+    let json: serde_json::Value = serde_json::from_str(&format_graph_as_json(&graph)).unwrap();
+    let main = json["nodes"].as_array().unwrap().iter()
+        .find(|node| node["label"].as_str().unwrap().starts_with("main"))
+        .unwrap();
+    assert_eq!(main["signature"], serde_json::json!({
+        "return_type": "int",
+        "parameters": [
+            { "name": "argc", "type": "int" },
+            { "name": "argv", "type": "char **" },
+        ],
+    }));
+
+    // Only functions have one
+    let argv = node_named(&graph, "BufferParam(**): argv (char **)");
+    assert!(graph[argv].signature.is_none());
+}
+
+#[test]
+fn include_system_adds_header_functions_as_library_nodes() {
+    let default = build_fixture("tests/fixtures/overflow.c");
+    assert!(nodes_named(&default, "printf").is_empty());
+
+    let graph = build_fixture_with(
+        "tests/fixtures/overflow.c",
+        &AnalysisOptions { include_system: true, ..AnalysisOptions::default() },
+    );
+    let printf = node_named(&graph, "printf");
+    assert_eq!(graph[printf].kind, NodeType::Function);
+    assert!(graph[printf].library);
+    assert!(has_edge(&graph, "Call: printf", EdgeType::Calls, "printf"));
+    // Calls into the headers are classified the same way as before
+    assert!(!nodes_named(&graph, "Unsafe: strcpy").is_empty());
+    assert!(!graph[node_named(&graph, "copy_name")].library);
+}
+
+#[test]
+fn writes_through_a_pointer_reach_the_pointee() {
+    let graph = build_fixture("tests/fixtures/pointer_write.c");
+
+    // `*p = 42` writes `value` through `p`, and `*(p) += 2` does the same to `other` once `p` moves
+    assert!(has_edge(&graph, "Assignment", EdgeType::Writes, "Var: value"));
+    assert!(has_edge(&graph, "Assignment", EdgeType::Reads, "Pointer: p (int *)"));
+    assert!(has_edge(&graph, "Assignment: +=", EdgeType::Writes, "Var: other"));
+    assert!(has_edge(&graph, "Assignment: +=", EdgeType::Reads, "Var: other"));
+    assert!(!has_edge(&graph, "Assignment: +=", EdgeType::Writes, "Var: value"));
+
+    let writes: Vec<&Edge> = graph.edge_references()
+        .filter(|edge| edge.weight().kind == EdgeType::Writes)
+        .map(|edge| edge.weight())
+        .collect();
+    assert_eq!(writes.iter().filter(|edge| edge.indirect).count(), 2);
+}
+
+#[test]
+fn array_stores_write_the_array_and_constant_indexes_are_bounds_checked() {
+    let graph = build_fixture("tests/fixtures/bounds.c");
+    let array = "Array: a (int[10])";
+
+    // Every store through a subscript writes the array; reads only access it
+    let writers: Vec<usize> = graph.edge_references()
+        .filter(|edge| edge.weight().kind == EdgeType::Writes && graph[edge.target()].name == array)
+        .filter_map(|edge| graph[edge.source()].line)
+        .collect();
+    assert_eq!(writers, [6, 7, 8]);
+
+    let out_of_bounds: Vec<&str> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::OutOfBounds)
+        .map(|idx| graph[idx].name.as_str())
+        .collect();
+    assert_eq!(out_of_bounds, ["OutOfBounds: a[10] (size 10)", "OutOfBounds: a[12] (size 10)"]);
+    assert!(has_edge(&graph, "OutOfBounds: a[12] (size 10)", EdgeType::Accesses, array));
+    assert_eq!(count_kind(&graph, NodeType::ArrayAccess), 2);
+}
+
+#[test]
+fn inner_block_variables_shadow_only_inside_their_block() {
+    let graph = build_fixture("tests/fixtures/scopes.c");
+
+    // Both `x` declarations exist side by side; each `+=` writes the one in scope at that line
+    let mut writes: Vec<(Option<usize>, Option<usize>)> = graph.edge_references()
+        .filter(|edge| edge.weight().kind == EdgeType::Writes && graph[edge.target()].name == "Var: x")
+        .map(|edge| (graph[edge.source()].line, graph[edge.target()].line))
+        .collect();
+    writes.sort();
+    assert_eq!(writes, [(Some(5), Some(4)), (Some(7), Some(2))]);
+    assert_eq!(nodes_named(&graph, "Var: x").len(), 2);
+}
+
+#[test]
+fn inner_declarations_shadow_outer_ones() {
+    let graph = build_fixture("tests/fixtures/scopes.c");
+    let shadowing: Vec<(Option<usize>, Option<usize>)> = find_shadowing(&graph).into_iter()
+        .map(|(inner, outer)| (graph[inner].line, graph[outer].line))
+        .collect();
+    assert_eq!(shadowing, [(Some(4), Some(2))]);
+
+    // A parameter hides a global; the function that uses the global shadows nothing
+    let graph = build_fixture("tests/fixtures/shadowing.c");
+    let shadowing: Vec<(String, String)> = find_shadowing(&graph).into_iter()
+        .map(|(inner, outer)| (graph[inner].name.clone(), graph[outer].name.clone()))
+        .collect();
+    assert_eq!(shadowing, [("Param: counter (int)".to_string(), "Var: counter".to_string())]);
+}
+
+#[test]
+fn defined_functions_carry_their_cyclomatic_complexity() {
+    let graph = build_fixture("tests/fixtures/nested.c");
+
+    // One for, one if and one while on top of the single entry path
+    assert_eq!(graph[node_named(&graph, "nested")].complexity, Some(4));
+    // `leaf` is only declared, so there is no body to measure
+    assert_eq!(graph[node_named(&graph, "leaf")].complexity, None);
+}
+
+#[test]
+fn functions_loops_and_blocks_carry_their_line_range() {
+    let graph = build_fixture("tests/fixtures/nested.c");
+    let range = |name: &str| {
+        let node = &graph[node_named(&graph, name)];
+        (node.line, node.end_line)
+    };
+
+    assert_eq!(range("nested"), (Some(3), Some(13)));
+    assert_eq!(range("For loop"), (Some(5), Some(12)));
+    assert_eq!(range("If statement"), (Some(6), Some(11)));
+    assert_eq!(range("While loop"), (Some(7), Some(10)));
+    // A declaration has no body to span
+    assert_eq!(range("leaf"), (Some(1), None));
+}
+
+#[test]
+fn sizeof_of_a_pointer_as_a_memory_size_is_flagged() {
+    let graph = build_fixture_with("tests/fixtures/sizeof.c", &memory_tracking());
+
+    let mut flagged: Vec<(Option<usize>, &str)> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::SizeofPointer)
+        .map(|idx| (graph[idx].line, graph[idx].name.as_str()))
+        .collect();
+    flagged.sort();
+    // `sizeof *counts`, `sizeof(array)` and `sizeof(char *)` all size what was meant
+    assert_eq!(flagged, [
+        (Some(6), "SizeofPointer: sizeof(buf)"),
+        (Some(9), "SizeofPointer: sizeof(buf)"),
+        (Some(10), "SizeofPointer: sizeof(local)"),
+    ]);
+
+    // The array parameter has decayed to a pointer, which the flag links back to
+    assert!(has_edge(&graph, "SizeofPointer: sizeof(local)", EdgeType::Uses, "BufferParam: local (char *)"));
+    assert!(has_edge(&graph, "Call: malloc", EdgeType::Contains, "SizeofPointer: sizeof(buf)"));
+}
+
+#[test]
+fn bounded_string_calls_sized_by_another_buffer_are_flagged() {
+    let graph = build_fixture("tests/fixtures/bounded.c");
+
+    let mut flagged: Vec<(Option<usize>, &str)> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::SizeMisuse)
+        .map(|idx| (graph[idx].line, graph[idx].name.as_str()))
+        .collect();
+    flagged.sort();
+    // Sizes measured from the destination, including the strncat idiom, are left alone
+    assert_eq!(flagged, [
+        (Some(7), "SizeMisuse: strlen(src)"),
+        (Some(10), "SizeMisuse: sizeof(other)"),
+    ]);
+
+    // strncpy is no longer unsafe by name alone
+    assert_eq!(count_kind(&graph, NodeType::UnsafeCall), 0);
+    assert!(has_edge(&graph, "Call: strncpy", EdgeType::Contains, "SizeMisuse: strlen(src)"));
+}
+
+#[test]
+fn allocations_sized_by_a_runtime_product_are_flagged() {
+    let graph = build_fixture("tests/fixtures/alloc_overflow.c");
+
+    let mut flagged: Vec<(Option<usize>, &str)> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::AllocationOverflow)
+        .map(|idx| (graph[idx].line, graph[idx].name.as_str()))
+        .collect();
+    flagged.sort();
+    // A `sizeof` factor is constant, and calloc checks its own product
+    assert_eq!(flagged, [
+        (Some(9), "AllocationOverflow: count*size"),
+        (Some(11), "AllocationOverflow: count*2*size"),
+    ]);
+
+    // The initializer's allocation is flagged once, on its call node
+    assert_eq!(nodes_named(&graph, "AllocationOverflow: count*size").len(), 1);
+    assert!(has_edge(&graph, "Call: malloc", EdgeType::Contains, "AllocationOverflow: count*size"));
+    assert!(has_edge(&graph, "AllocationOverflow: count*size", EdgeType::Uses, "Param: size (size_t)"));
+}
+
+#[test]
+fn calls_through_unnamed_callees_are_kept() {
+    let graph = build_fixture("tests/fixtures/unknown_call.c");
+
+    let unknown: Vec<&str> = graph.node_indices()
+        .filter(|&idx| graph[idx].kind == NodeType::UnknownCall)
+        .map(|idx| graph[idx].name.as_str())
+        .collect();
+    assert!(unknown.contains(&"UnknownCall: table[i]"), "unknown calls: {:?}", unknown);
+    // The arguments and the index still count as reads
+    assert!(has_edge(&graph, "UnknownCall: table[i]", EdgeType::Uses, "Param: value (int)"));
+    assert!(has_edge(&graph, "UnknownCall: table[i]", EdgeType::Uses, "Param: i (int)"));
+}
+
+#[test]
+fn cpp_methods_become_functions_with_qualified_names() {
+    assert!(is_cpp_source(Path::new("src/shapes.cpp")));
+    assert!(is_cpp_source(Path::new("include/shapes.hpp")));
+    assert!(!is_cpp_source(Path::new("src/shapes.c")));
+
+    let options = AnalysisOptions { clang_args: AnalysisOptions::cpp_clang_args("c++17"), ..AnalysisOptions::default() };
+    let graph = build_fixture_with("tests/fixtures/shapes.cpp", &options);
+
+    for name in ["geometry::Shape::Shape", "geometry::Shape::~Shape", "geometry::Shape::area"] {
+        assert_eq!(graph[node_named(&graph, name)].kind, NodeType::Function, "{}", name);
+    }
+    // The out-of-line definition takes over the in-class declaration's node
+    assert_eq!(graph[node_named(&graph, "geometry::Shape::area")].line, Some(14));
+    // A call through a member expression resolves to the method by USR
+    assert!(has_edge(&graph, "Call: area", EdgeType::Calls, "geometry::Shape::area"));
+    // So does a qualified call whose value is assigned
+    assert!(has_edge(&graph, "Call: unit", EdgeType::Calls, "geometry::unit"));
+
+    // Classes and namespaces get nodes that define their members, once each
+    assert_eq!(graph[node_named(&graph, "geometry")].kind, NodeType::Namespace);
+    assert_eq!(graph[node_named(&graph, "geometry::Shape")].kind, NodeType::Class);
+    assert!(has_edge(&graph, "geometry", EdgeType::Defines, "geometry::Shape"));
+    let area = node_named(&graph, "geometry::Shape::area");
+    let definers: Vec<&str> = graph.edges_directed(area, Direction::Incoming)
+        .filter(|edge| edge.weight().kind == EdgeType::Defines)
+        .map(|edge| graph[edge.source()].name.as_str())
+        .collect();
+    assert_eq!(definers, ["geometry::Shape"]);
+}
+
+#[test]
+fn comma_operands_and_statement_expressions_keep_their_references() {
+    let graph = build_fixture("tests/fixtures/comma.c");
+
+    // The discarded `a++` is still evaluated, and `b` is the value
+    assert!(has_edge(&graph, "Assignment", EdgeType::Uses, "Param: a (int)"));
+    assert!(has_edge(&graph, "Assignment", EdgeType::Reads, "Param: b (int)"));
+
+    // Locals of a statement-expression resolve inside it, and its last statement is the value
+    assert!(has_edge(&graph, "Assignment", EdgeType::Contains, "Var: t"));
+    assert!(has_edge(&graph, "Var: t", EdgeType::Uses, "Param: c (int)"));
+    assert!(has_edge(&graph, "Assignment", EdgeType::Reads, "Var: t"));
+    assert!(has_edge(&graph, "Assignment", EdgeType::Reads, "Param: d (int)"));
+}
+
+#[test]
+fn aggregate_initializers_use_the_variables_they_name() {
+    let graph = build_fixture("tests/fixtures/initializers.c");
+
+    // Designated struct fields, including ones computed from an expression
+    assert!(has_edge(&graph, "Var: p", EdgeType::Uses, "Param: left (int)"));
+    assert!(has_edge(&graph, "Var: p", EdgeType::Uses, "Param: right (int)"));
+    assert!(has_edge(&graph, "Var: p", EdgeType::Uses, "Pointer: base (int *)"));
+
+    // Array elements, with a repeated element recorded once
+    let array = node_named(&graph, "Array: values (int[3])");
+    let left = node_named(&graph, "Param: left (int)");
+    assert_eq!(graph.edges_connecting(array, left).count(), 1);
+    assert!(has_edge(&graph, "Array: values (int[3])", EdgeType::Uses, "Param: right (int)"));
+}
+
+#[test]
+fn disabling_the_regex_fallback_keeps_only_calls_the_ast_saw() {
+    // The source scan doesn't know the preprocessor, so it sees the call under `#if 0`
+    let scanned = build_fixture("tests/fixtures/disabled_call.c");
+    assert!(has_edge(&scanned, "Call: helper", EdgeType::Calls, "helper"));
+
+    let ast_only = build_fixture_with(
+        "tests/fixtures/disabled_call.c",
+        &AnalysisOptions { fallback_regex: false, ..AnalysisOptions::default() },
+    );
+    assert!(nodes_named(&ast_only, "Call: helper").is_empty());
+    assert_eq!(count_kind(&ast_only, NodeType::Function), 2);
+}
+
+#[test]
+fn each_unsafe_call_is_reported_once_with_its_line() {
+    let graph = build_source(
+        "copy.c",
+        "#include <string.h>\n\nvoid copy(char *dst, const char *src) {\n    strcpy(dst, src);\n}\n",
+    );
+
+    let unsafe_calls: Vec<Finding> = collect_findings(&graph, "copy.c").into_iter()
+        .filter(|finding| finding.rule == "unsafe-call")
+        .collect();
+    assert_eq!(unsafe_calls.len(), 1);
+    assert_eq!(unsafe_calls[0].line, Some(4));
+}
+
+#[test]
+fn stores_into_members_and_through_pointers_read_the_stored_value() {
+    let graph = build_source(
+        "stores.c",
+        "#include <stdlib.h>\n\nstruct holder { char *buf; };\n\nvoid keep(struct holder *s, char **out) {\n    char *p = malloc(8);\n    char *q = malloc(8);\n    s->buf = p;\n    *out = q;\n}\n",
+    );
+
+    assert!(has_edge(&graph, "Assignment", EdgeType::Reads, "BufferParam: p (char *)"));
+    assert!(has_edge(&graph, "Assignment", EdgeType::Contains, "StructAccess: buf"));
+    assert!(has_edge(&graph, "Assignment", EdgeType::Reads, "BufferParam: q (char *)"));
+
+    // Both blocks are handed on, so neither is a leak
+    assert!(cparser::analysis::find_leaks(&graph).is_empty());
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::{add, link};
+use cparser::analysis::{
+    can_reach, find_dead_functions, find_recursion, find_unreachable_functions, find_unreachable_functions_from, shortest_call_path,
+};
+use cparser::types::{Edge, EdgeType, Node, NodeType};
+use petgraph::graph::{DiGraph, NodeIndex};
+
+// main -> handle_request -> run_command -> system, with a longer detour through log_request
+fn request_graph() -> DiGraph<Node, Edge> {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let handle = add(&mut graph, "handle_request", NodeType::Function);
+    let log = add(&mut graph, "log_request", NodeType::Function);
+    let run = add(&mut graph, "run_command", NodeType::Function);
+    add(&mut graph, "unused", NodeType::Function);
+
+    let block = add(&mut graph, "BasicBlock: entry", NodeType::BasicBlock);
+    let call_handle = add(&mut graph, "Call: handle_request", NodeType::Call);
+    link(&mut graph, main, block, EdgeType::Contains);
+    link(&mut graph, block, call_handle, EdgeType::Contains);
+    link(&mut graph, call_handle, handle, EdgeType::Calls);
+
+    let call_log = add(&mut graph, "Call: log_request", NodeType::Call);
+    let call_run = add(&mut graph, "Call: run_command", NodeType::Call);
+    link(&mut graph, handle, call_log, EdgeType::Contains);
+    link(&mut graph, handle, call_run, EdgeType::Contains);
+    link(&mut graph, call_log, log, EdgeType::Calls);
+    link(&mut graph, call_run, run, EdgeType::Calls);
+
+    let log_run = add(&mut graph, "Call: run_command", NodeType::Call);
+    link(&mut graph, log, log_run, EdgeType::Contains);
+    link(&mut graph, log_run, run, EdgeType::Calls);
+
+    // `system` is never defined, only called
+    let sink = add(&mut graph, "Sink: system", NodeType::DangerousSink);
+    link(&mut graph, run, sink, EdgeType::Contains);
+
+    graph
+}
+
+#[test]
+fn shortest_call_path_reaches_undefined_library_functions() {
+    let graph = request_graph();
+
+    assert_eq!(
+        shortest_call_path(&graph, "main", "system"),
+        Some(vec![
+            "main".to_string(),
+            "handle_request".to_string(),
+            "run_command".to_string(),
+            "system".to_string(),
+        ])
+    );
+    assert!(can_reach(&graph, "log_request", "system"));
+}
+
+#[test]
+fn calls_only_lead_one_way() {
+    let graph = request_graph();
+
+    assert!(!can_reach(&graph, "run_command", "main"));
+    assert!(!can_reach(&graph, "unused", "system"));
+    assert_eq!(shortest_call_path(&graph, "main", "missing"), None);
+    assert_eq!(shortest_call_path(&graph, "missing", "main"), None);
+}
+
+// A static function with a body, so only its USR says it can't be called from another file
+fn add_static(graph: &mut DiGraph<Node, Edge>, name: &str) -> NodeIndex {
+    let func = add(graph, name, NodeType::Function);
+    graph[func].usr = Some(format!("Some(Usr(\"c:util.c@F@{}\"))", name));
+    let body = add(graph, "BasicBlock: body", NodeType::BasicBlock);
+    link(graph, func, body, EdgeType::Contains);
+    func
+}
+
+#[test]
+fn unreachable_functions_skip_thread_handlers_and_exports() {
+    let mut graph = request_graph();
+    let main = graph.node_indices().find(|&idx| graph[idx].name == "main").unwrap();
+
+    // Started with pthread_create(&tid, NULL, worker, NULL): referenced, never called
+    let worker = add_static(&mut graph, "worker");
+    let spawn = add(&mut graph, "Call: pthread_create", NodeType::Call);
+    link(&mut graph, main, spawn, EdgeType::Contains);
+    link(&mut graph, spawn, worker, EdgeType::References);
+
+    add_static(&mut graph, "stale_helper");
+    let cleanup = add_static(&mut graph, "cleanup");
+
+    // Exported, so another translation unit may call it
+    let api = add(&mut graph, "public_api", NodeType::Function);
+    graph[api].usr = Some("Some(Usr(\"c:@F@public_api\"))".to_string());
+    let api_body = add(&mut graph, "BasicBlock: body", NodeType::BasicBlock);
+    link(&mut graph, api, api_body, EdgeType::Contains);
+
+    assert_eq!(
+        find_unreachable_functions(&graph),
+        vec!["cleanup".to_string(), "stale_helper".to_string()]
+    );
+
+    let call_cleanup = add(&mut graph, "Call: cleanup", NodeType::Call);
+    link(&mut graph, api, call_cleanup, EdgeType::Contains);
+    link(&mut graph, call_cleanup, cleanup, EdgeType::Calls);
+    assert_eq!(
+        find_unreachable_functions_from(&graph, &["main", "public_api"]),
+        vec!["stale_helper".to_string()]
+    );
+}
+
+#[test]
+fn dead_functions_are_those_no_other_code_calls() {
+    let mut graph = request_graph();
+    let main = graph.node_indices().find(|&idx| graph[idx].name == "main").unwrap();
+
+    // Referenced by address, so not dead even though nothing calls it by name
+    let worker = add_static(&mut graph, "worker");
+    let spawn = add(&mut graph, "Call: pthread_create", NodeType::Call);
+    link(&mut graph, main, spawn, EdgeType::Contains);
+    link(&mut graph, spawn, worker, EdgeType::References);
+
+    // Only calls itself
+    let countdown = add_static(&mut graph, "countdown");
+    graph[countdown].line = Some(30);
+    let body = graph.neighbors(countdown).next().unwrap();
+    let recurse = add(&mut graph, "Call: countdown", NodeType::Call);
+    link(&mut graph, body, recurse, EdgeType::Contains);
+    link(&mut graph, recurse, countdown, EdgeType::Calls);
+
+    // Called, but only from dead code: unreachable rather than dead
+    let stale = add_static(&mut graph, "stale_helper");
+    graph[stale].line = Some(12);
+    let formatter = add_static(&mut graph, "format_stale");
+    let call_format = add(&mut graph, "Call: format_stale", NodeType::Call);
+    link(&mut graph, stale, call_format, EdgeType::Contains);
+    link(&mut graph, call_format, formatter, EdgeType::Calls);
+
+    let dead: Vec<&str> = find_dead_functions(&graph).into_iter()
+        .map(|idx| graph[idx].name.as_str())
+        .collect();
+    assert_eq!(dead, ["stale_helper", "countdown"]);
+    assert_eq!(
+        find_unreachable_functions(&graph),
+        vec!["countdown".to_string(), "format_stale".to_string(), "stale_helper".to_string()]
+    );
+}
+
+#[test]
+fn recursion_reports_self_and_mutual_cycles() {
+    let mut graph = request_graph();
+    assert!(find_recursion(&graph).is_empty());
+
+    let named = |graph: &DiGraph<Node, Edge>, name: &str| graph.node_indices().find(|&idx| graph[idx].name == name).unwrap();
+    let handle = named(&graph, "handle_request");
+    let log = named(&graph, "log_request");
+    let run = named(&graph, "run_command");
+
+    // run_command retries itself, and log_request and handle_request call each other
+    for (caller, callee) in [(run, run), (log, handle)] {
+        let name = format!("Call: {}", graph[callee].name);
+        let call = add(&mut graph, &name, NodeType::Call);
+        link(&mut graph, caller, call, EdgeType::Contains);
+        link(&mut graph, call, callee, EdgeType::Calls);
+    }
+
+    assert_eq!(find_recursion(&graph), vec![vec![handle, log], vec![run]]);
+}
@@ -0,0 +1,16 @@
+mod common;
+
+use cparser::formatters::{format_graph_as_dot, format_graph_as_json};
+
+// Pins the output of the whole pipeline on the sample program so refactors can't change it silently
+#[test]
+fn sample_program_json_is_unchanged() {
+    let graph = common::build_fixture("test.c");
+    common::assert_golden("test.json", &format_graph_as_json(&graph));
+}
+
+#[test]
+fn sample_program_dot_is_unchanged() {
+    let graph = common::build_fixture("test.c");
+    common::assert_golden("test.dot", &format_graph_as_dot(&graph));
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fs;
+
+use cparser::callbacks::{default_callback_registrars, parse_callback_registrars};
+use cparser::graph_builder::fix_disconnected_calls;
+use cparser::symbols::SymbolTable;
+use cparser::types::{Edge, EdgeType, Node, NodeType};
+use cparser::utils::{extract_callback_registrations, extract_function_calls_from_source};
+use petgraph::graph::DiGraph;
+use petgraph::visit::EdgeRef;
+
+const SOURCE: &str = r#"
+void run(const char *cmd) {
+    printf("running %s\n", cmd);
+    system(cmd);
+    log_command(cmd);
+}
+"#;
+
+fn callees(keep_stdlib: bool) -> Vec<String> {
+    extract_function_calls_from_source(SOURCE, keep_stdlib)
+        .into_iter()
+        .map(|(_, callee)| callee)
+        .collect()
+}
+
+#[test]
+fn library_calls_are_skipped_except_ones_that_run_programs() {
+    assert_eq!(callees(false), ["system", "log_command"]);
+}
+
+#[test]
+fn keep_stdlib_keeps_every_library_call() {
+    assert_eq!(callees(true), ["printf", "system", "log_command"]);
+}
+
+#[test]
+fn pointer_struct_and_multiline_signatures_are_scanned() {
+    let source = fs::read_to_string("tests/fixtures/signatures.c").unwrap();
+    let calls = extract_function_calls_from_source(&source, false);
+
+    let expected = [("name_of", "trim"), ("make_buffer", "allocate_buffer"), ("names", "lookup")];
+    let expected: Vec<(String, String)> = expected.iter()
+        .map(|(caller, callee)| (caller.to_string(), callee.to_string()))
+        .collect();
+    assert_eq!(calls, expected);
+}
+
+#[test]
+fn braces_in_strings_and_comments_do_not_end_the_body() {
+    let source = r#"
+void report(int code) {
+    printf("}\n");
+    putchar('}');
+    // closing } in a comment
+    /* { unbalanced } } */
+    log_code(code);
+}
+
+int main(void) {
+    pthread_t thread;
+    const char *banner = "\"}";
+    pthread_create(&thread, NULL, report, NULL);
+    return 0;
+}
+"#;
+
+    let calls = extract_function_calls_from_source(source, false);
+    assert!(calls.contains(&("report".to_string(), "log_code".to_string())), "calls: {:?}", calls);
+    assert_eq!(
+        extract_callback_registrations(source, &default_callback_registrars()),
+        [("main".to_string(), "pthread_create".to_string(), "report".to_string())]
+    );
+}
+
+#[test]
+fn callback_registrars_come_from_the_table() {
+    let source = r#"
+static int compare(const void *a, const void *b) {
+    return 0;
+}
+
+static void cleanup(void) {
+}
+
+static void on_event(int fd, short what, void *arg) {
+}
+
+int main(void) {
+    int values[4];
+    qsort(values, 4, sizeof(int), compare);
+    atexit(&cleanup);
+    event_set(&ev, fd, EV_READ, on_event, NULL);
+    return 0;
+}
+"#;
+
+    let registrations = |registrars: &[(String, usize)]| -> Vec<(String, String)> {
+        extract_callback_registrations(source, registrars).into_iter()
+            .map(|(_, registrar, handler)| (registrar, handler))
+            .collect()
+    };
+
+    let mut registrars = default_callback_registrars();
+    assert_eq!(registrations(&registrars), [
+        ("qsort".to_string(), "compare".to_string()),
+        ("atexit".to_string(), "cleanup".to_string()),
+    ]);
+
+    registrars.extend(parse_callback_registrars("# libevent\nevent_set 3\n").unwrap());
+    assert!(registrations(&registrars).contains(&("event_set".to_string(), "on_event".to_string())));
+}
+
+#[test]
+fn callback_tables_reject_malformed_lines() {
+    assert_eq!(parse_callback_registrars("\n  g_thread_new 1  # GLib\n").unwrap(), [("g_thread_new".to_string(), 1)]);
+
+    let err = parse_callback_registrars("qsort three").unwrap_err();
+    assert!(err.to_string().contains("line 1"), "unexpected error: {}", err);
+    assert!(parse_callback_registrars("qsort").is_err());
+    assert!(parse_callback_registrars("qsort 3 extra").is_err());
+}
+
+#[test]
+fn disconnected_calls_resolve_by_usr_before_name() {
+    // Two files each define a static `helper`; the name alone picks the first one
+    let mut graph: DiGraph<Node, Edge> = DiGraph::new();
+    let mut function = |usr: &str| graph.add_node(Node {
+        name: "helper".to_string(),
+        kind: NodeType::Function,
+        usr: Some(usr.to_string()),
+        ..Node::default()
+    });
+    let first = function("c:a.c@F@helper");
+    let second = function("c:b.c@F@helper");
+    let call = graph.add_node(Node {
+        name: "Call: helper".to_string(),
+        kind: NodeType::Call,
+        usr: Some("c:b.c@F@helper".to_string()),
+        ..Node::default()
+    });
+
+    let mut node_map = SymbolTable::new();
+    node_map.insert_global("helper".to_string(), first);
+    let usr_map = HashMap::from([("c:a.c@F@helper".to_string(), first), ("c:b.c@F@helper".to_string(), second)]);
+
+    fix_disconnected_calls(&mut graph, &node_map, &usr_map, &[], &[], false);
+    let callees: Vec<_> = graph.edges(call)
+        .filter(|edge| edge.weight().kind == EdgeType::Calls)
+        .map(|edge| edge.target())
+        .collect();
+    assert_eq!(callees, [second]);
+}
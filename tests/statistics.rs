@@ -0,0 +1,64 @@
+mod common;
+
+use common::add;
+use cparser::analysis::graph_stats;
+use cparser::types::{Edge, EdgeType, NodeType};
+use petgraph::graph::DiGraph;
+
+#[test]
+fn statistics_count_each_kind_and_the_totals() {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let helper = add(&mut graph, "helper", NodeType::Function);
+    let call = add(&mut graph, "Call: helper", NodeType::Call);
+    let strcpy = add(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall);
+    graph.add_edge(main, call, Edge { kind: EdgeType::Contains, indirect: false });
+    graph.add_edge(main, strcpy, Edge { kind: EdgeType::Contains, indirect: false });
+    graph.add_edge(call, helper, Edge { kind: EdgeType::Calls, indirect: false });
+
+    let stats = graph_stats(&graph);
+    assert_eq!((stats.nodes, stats.edges), (4, 3));
+    let node_kinds: Vec<(&str, usize)> = stats.node_kinds.iter().map(|(kind, &count)| (kind.as_str(), count)).collect();
+    assert_eq!(node_kinds, [("call", 1), ("function", 1), ("main", 1), ("unsafe_call", 1)]);
+    let edge_kinds: Vec<(&str, usize)> = stats.edge_kinds.iter().map(|(kind, &count)| (kind.as_str(), count)).collect();
+    assert_eq!(edge_kinds, [("calls", 1), ("contains", 2)]);
+}
+
+#[test]
+fn stats_measure_call_depth_through_recursion() {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let parse = add(&mut graph, "parse", NodeType::Function);
+    let parse_item = add(&mut graph, "parse_item", NodeType::Function);
+    add(&mut graph, "unused", NodeType::Function);
+
+    // main -> parse <-> parse_item -> strcpy
+    for (caller, callee) in [(main, parse), (parse, parse_item), (parse_item, parse)] {
+        let name = format!("Call: {}", graph[callee].name);
+        let call = add(&mut graph, &name, NodeType::Call);
+        graph.add_edge(caller, call, Edge { kind: EdgeType::Contains, indirect: false });
+        graph.add_edge(call, callee, Edge { kind: EdgeType::Calls, indirect: false });
+    }
+    let strcpy = add(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall);
+    graph.add_edge(parse_item, strcpy, Edge { kind: EdgeType::Contains, indirect: false });
+
+    let stats = graph_stats(&graph);
+    assert_eq!(stats.functions, 4);
+    assert_eq!(stats.unsafe_calls, 1);
+    assert_eq!(stats.max_call_depth, 2);
+    assert_eq!(stats.components, 2);
+    assert_eq!(stats.node_kinds["call"], 3);
+    assert_eq!(stats.edge_kinds["calls"], 3);
+}
+
+#[test]
+fn stats_count_an_unsafe_call_once_despite_its_controller() {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let strcpy = add(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall);
+    let controller = add(&mut graph, "Unsafe: strcpy", NodeType::UnsafeCall);
+    graph.add_edge(main, strcpy, Edge { kind: EdgeType::Contains, indirect: false });
+    graph.add_edge(controller, strcpy, Edge { kind: EdgeType::Controls, indirect: false });
+
+    assert_eq!(graph_stats(&graph).unsafe_calls, 1);
+}
@@ -0,0 +1,46 @@
+use cparser::theme::{Style, Theme};
+use cparser::types::{EdgeType, NodeType};
+
+#[test]
+fn theme_tables_style_node_and_edge_kinds() {
+    let theme = Theme::parse(r##"
+# Colorblind-safe accents
+[nodes.UnsafeCall]
+color = "#d55e00"  # vermillion
+shape = "octagon"
+
+[nodes.unsafe-call]
+style = 'filled,bold'
+
+[edges.calls]
+color = "#0072b2"
+style = "bold"
+"##).unwrap();
+
+    // Spellings of the same kind share one entry
+    assert_eq!(theme.node_style(&NodeType::UnsafeCall), Some(&Style {
+        shape: Some("octagon".to_string()),
+        color: Some("#d55e00".to_string()),
+        style: Some("filled,bold".to_string()),
+    }));
+    assert_eq!(theme.edge_style(&EdgeType::Calls).unwrap().color.as_deref(), Some("#0072b2"));
+    assert_eq!(theme.node_style(&NodeType::Call), None);
+}
+
+#[test]
+fn theme_errors_name_the_offending_line() {
+    let cases = [
+        ("color = \"red\"", "line 1"),
+        ("[nodes.nonsense]", "unknown node type 'nonsense'"),
+        ("[colors.call]", "unknown section 'colors'"),
+        ("[edges.calls]\nshape = \"box\"", "unknown edge style key 'shape'"),
+        ("[nodes.call]\nshape = \"box; x=1\"", "not a Graphviz shape name"),
+        ("[nodes.call]\ncolor = red", "line 2, column 9"),
+        ("[nodes.call]\nlabel = \"x\"", "unknown field `label`"),
+        ("[nodes.call]\ncolor = \"red\"\n[nodes.Call]\ncolor = \"blue\"", "'color' is already set"),
+    ];
+    for (text, expected) in cases {
+        let err = Theme::parse(text).unwrap_err().to_string();
+        assert!(err.contains(expected), "{:?} gave {:?}", text, err);
+    }
+}
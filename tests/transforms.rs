@@ -0,0 +1,179 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::{add, link};
+use cparser::analysis::{find_function, reachable_from};
+use cparser::transforms::{deduplicate_edges, filter_kinds, retain_nodes, subgraph_from_function, summarize};
+use cparser::types::{Edge, EdgeType, Node, NodeType};
+use petgraph::graph::DiGraph;
+use petgraph::visit::EdgeRef;
+
+fn edge_names(graph: &DiGraph<Node, Edge>) -> Vec<(String, EdgeType, String)> {
+    let mut edges: Vec<_> = graph.edge_references()
+        .map(|edge| (
+            graph[edge.source()].name.clone(),
+            edge.weight().kind.clone(),
+            graph[edge.target()].name.clone(),
+        ))
+        .collect();
+    edges.sort_by(|a, b| (&a.0, &a.2).cmp(&(&b.0, &b.2)));
+    edges
+}
+
+#[test]
+fn summarize_keeps_the_call_graph_and_drops_statement_detail() {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let helper = add(&mut graph, "helper", NodeType::Function);
+    let block = add(&mut graph, "BasicBlock", NodeType::BasicBlock);
+    let branch = add(&mut graph, "If", NodeType::IfStatement);
+    let call = add(&mut graph, "Call: helper", NodeType::Call);
+    let var = add(&mut graph, "Var: buf", NodeType::Variable);
+    let malloc = add(&mut graph, "MemoryOp: malloc", NodeType::MemoryOp);
+
+    link(&mut graph, main, block, EdgeType::Contains);
+    link(&mut graph, block, branch, EdgeType::Contains);
+    link(&mut graph, branch, call, EdgeType::Contains);
+    link(&mut graph, block, var, EdgeType::Contains);
+    link(&mut graph, block, malloc, EdgeType::Contains);
+    link(&mut graph, call, helper, EdgeType::Calls);
+    link(&mut graph, var, malloc, EdgeType::Allocates);
+
+    let summary = summarize(&graph);
+
+    let mut names: Vec<&str> = summary.node_weights().map(|node| node.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["Call: helper", "MemoryOp: malloc", "helper", "main"]);
+
+    assert_eq!(edge_names(&summary), [
+        ("Call: helper".to_string(), EdgeType::Calls, "helper".to_string()),
+        ("main".to_string(), EdgeType::Contains, "Call: helper".to_string()),
+        ("main".to_string(), EdgeType::Contains, "MemoryOp: malloc".to_string()),
+    ]);
+}
+
+#[test]
+fn filter_kinds_drops_edges_whose_endpoints_are_removed() {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let helper = add(&mut graph, "helper", NodeType::Function);
+    let block = add(&mut graph, "BasicBlock", NodeType::BasicBlock);
+    let call = add(&mut graph, "Call: helper", NodeType::Call);
+
+    link(&mut graph, main, block, EdgeType::Contains);
+    link(&mut graph, block, call, EdgeType::Contains);
+    link(&mut graph, call, helper, EdgeType::Calls);
+    link(&mut graph, call, main, EdgeType::References);
+
+    let node_kinds: HashSet<NodeType> = [NodeType::Main, NodeType::Function, NodeType::Call].into_iter().collect();
+    let filtered = filter_kinds(&graph, &node_kinds, &HashSet::new());
+    assert_eq!(filtered.node_count(), 3);
+    // The block is gone and so are both Contains edges touching it
+    assert_eq!(edge_names(&filtered), [
+        ("Call: helper".to_string(), EdgeType::Calls, "helper".to_string()),
+        ("Call: helper".to_string(), EdgeType::References, "main".to_string()),
+    ]);
+
+    let edge_kinds: HashSet<EdgeType> = [EdgeType::Calls].into_iter().collect();
+    let calls_only = filter_kinds(&graph, &HashSet::new(), &edge_kinds);
+    assert_eq!(calls_only.node_count(), 4);
+    assert_eq!(edge_names(&calls_only), [
+        ("Call: helper".to_string(), EdgeType::Calls, "helper".to_string()),
+    ]);
+}
+
+#[test]
+fn entry_subgraph_keeps_only_what_the_entry_reaches() {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let helper = add(&mut graph, "helper", NodeType::Function);
+    let handler = add(&mut graph, "handler", NodeType::Function);
+    let unused = add(&mut graph, "unused", NodeType::Function);
+    let call = add(&mut graph, "Call: helper", NodeType::Call);
+    let var = add(&mut graph, "Var: count", NodeType::Variable);
+    let callback = add(&mut graph, "Pointer: cb (void (*)(void))", NodeType::Pointer);
+    let unused_call = add(&mut graph, "Call: helper", NodeType::Call);
+
+    link(&mut graph, main, call, EdgeType::Contains);
+    link(&mut graph, call, helper, EdgeType::Calls);
+    link(&mut graph, helper, var, EdgeType::Contains);
+    link(&mut graph, helper, callback, EdgeType::Contains);
+    link(&mut graph, callback, handler, EdgeType::References);
+    link(&mut graph, unused, unused_call, EdgeType::Contains);
+    link(&mut graph, unused_call, helper, EdgeType::Calls);
+
+    assert_eq!(find_function(&graph, "main"), Some(main));
+    assert_eq!(find_function(&graph, "Call: helper"), None);
+    assert_eq!(find_function(&graph, "missing"), None);
+
+    let reachable = reachable_from(&graph, main);
+    assert_eq!(reachable, HashSet::from([main, call, helper, var, callback, handler]));
+
+    let subgraph = retain_nodes(&graph, &reachable);
+    assert_eq!(subgraph.node_count(), 6);
+    assert_eq!(edge_names(&subgraph), [
+        ("Call: helper".to_string(), EdgeType::Calls, "helper".to_string()),
+        ("Pointer: cb (void (*)(void))".to_string(), EdgeType::References, "handler".to_string()),
+        ("helper".to_string(), EdgeType::Contains, "Pointer: cb (void (*)(void))".to_string()),
+        ("helper".to_string(), EdgeType::Contains, "Var: count".to_string()),
+        ("main".to_string(), EdgeType::Contains, "Call: helper".to_string()),
+    ]);
+}
+
+#[test]
+fn function_subgraph_follows_contains_calls_and_uses() {
+    let mut graph = DiGraph::new();
+    let main = add(&mut graph, "main", NodeType::Main);
+    let parse = add(&mut graph, "parse", NodeType::Function);
+    let helper = add(&mut graph, "helper", NodeType::Function);
+    let handler = add(&mut graph, "handler", NodeType::Function);
+    let main_call = add(&mut graph, "Call: parse", NodeType::Call);
+    let call = add(&mut graph, "Call: helper", NodeType::Call);
+    let global = add(&mut graph, "Var: verbose", NodeType::Variable);
+    let callback = add(&mut graph, "Pointer: cb (void (*)(void))", NodeType::Pointer);
+
+    link(&mut graph, main, main_call, EdgeType::Contains);
+    link(&mut graph, main_call, parse, EdgeType::Calls);
+    link(&mut graph, parse, call, EdgeType::Contains);
+    link(&mut graph, call, helper, EdgeType::Calls);
+    link(&mut graph, call, global, EdgeType::Uses);
+    link(&mut graph, helper, callback, EdgeType::Contains);
+    link(&mut graph, callback, handler, EdgeType::References);
+
+    // Callers are left out, and so are functions only referenced through a pointer
+    let subgraph = subgraph_from_function(&graph, "parse");
+    assert_eq!(edge_names(&subgraph), [
+        ("Call: helper".to_string(), EdgeType::Uses, "Var: verbose".to_string()),
+        ("Call: helper".to_string(), EdgeType::Calls, "helper".to_string()),
+        ("helper".to_string(), EdgeType::Contains, "Pointer: cb (void (*)(void))".to_string()),
+        ("parse".to_string(), EdgeType::Contains, "Call: helper".to_string()),
+    ]);
+
+    assert_eq!(subgraph_from_function(&graph, "missing").node_count(), 0);
+}
+
+#[test]
+fn deduplicate_edges_keeps_one_edge_per_kind() {
+    let mut graph = DiGraph::new();
+    let call = add(&mut graph, "Call: helper", NodeType::Call);
+    let helper = add(&mut graph, "helper", NodeType::Function);
+    let buf = add(&mut graph, "Var: buf", NodeType::Variable);
+    link(&mut graph, call, helper, EdgeType::Calls);
+    link(&mut graph, call, helper, EdgeType::Calls);
+    graph.add_edge(call, helper, Edge { kind: EdgeType::Calls, indirect: true });
+    link(&mut graph, call, buf, EdgeType::Uses);
+    link(&mut graph, call, buf, EdgeType::Uses);
+    link(&mut graph, call, buf, EdgeType::References);
+
+    // The indirect call and the reference are not repeats of the direct call and the use
+    let deduplicated = deduplicate_edges(&graph);
+    assert_eq!(deduplicated.node_count(), 3);
+    assert_eq!(deduplicated.edge_count(), 4);
+    assert_eq!(edge_names(&deduplicated), [
+        ("Call: helper".to_string(), EdgeType::Uses, "Var: buf".to_string()),
+        ("Call: helper".to_string(), EdgeType::References, "Var: buf".to_string()),
+        ("Call: helper".to_string(), EdgeType::Calls, "helper".to_string()),
+        ("Call: helper".to_string(), EdgeType::Calls, "helper".to_string()),
+    ]);
+}
@@ -0,0 +1,27 @@
+use cparser::types::{EdgeType, NodeType};
+
+#[test]
+fn node_types_round_trip_through_their_canonical_names() {
+    for kind in NodeType::ALL {
+        assert_eq!(kind.to_string().parse::<NodeType>(), Ok(kind.clone()));
+    }
+    assert_eq!(NodeType::UnsafeCall.to_string(), "unsafe_call");
+}
+
+#[test]
+fn edge_types_round_trip_through_their_canonical_names() {
+    for kind in EdgeType::ALL {
+        assert_eq!(kind.to_string().parse::<EdgeType>(), Ok(kind.clone()));
+    }
+    assert_eq!(EdgeType::Points.to_string(), "points_to");
+}
+
+#[test]
+fn kind_names_accept_variant_spelling_and_reject_unknown_names() {
+    assert_eq!("MemoryOp".parse::<NodeType>(), Ok(NodeType::MemoryOp));
+    assert_eq!("memory-op".parse::<NodeType>(), Ok(NodeType::MemoryOp));
+    assert_eq!("Points".parse::<EdgeType>(), Ok(EdgeType::Points));
+
+    let err = "loop".parse::<NodeType>().unwrap_err();
+    assert!(err.contains("for_loop"), "error should list valid names: {}", err);
+}